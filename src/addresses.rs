@@ -0,0 +1,390 @@
+// Chain addresses of a secp256k1 public key: Ethereum (EIP-55), Bitcoin P2PKH and
+// P2WPKH per network, and Cosmos. Stored with each HD key and searched by /addresses.
+// Also names the destinations of Bitcoin output scripts for PSBT signing policies.
+
+use anyhow::Result;
+use axum::{extract::Path, http::StatusCode, response::Json as ResponseJson};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd::Ripemd160;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3; // BIP350, for segwit v1 and later
+const COSMOS_HRP: &str = "cosmos";
+
+// P2PKH and P2SH Base58Check version bytes and bech32 human-readable part of each
+// Bitcoin network. Testnet and regtest share the versions, so their legacy addresses
+// are identical.
+const BITCOIN_NETWORKS: [(&str, u8, u8, &str); 3] = [
+    ("mainnet", 0x00, 0x05, "bc"),
+    ("testnet", 0x6f, 0xc4, "tb"),
+    ("regtest", 0x6f, 0xc4, "bcrt"),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BitcoinAddresses {
+    pub p2pkh: String,  // Base58Check legacy address
+    pub p2wpkh: String, // Bech32 native SegWit v0 address
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BitcoinNetworks {
+    pub mainnet: BitcoinAddresses,
+    pub testnet: BitcoinAddresses,
+    pub regtest: BitcoinAddresses,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KeyAddresses {
+    pub ethereum: String, // EIP-55 checksummed
+    pub bitcoin: BitcoinNetworks,
+    pub cosmos: String,
+}
+
+// Which of a key's addresses a lookup matched
+#[derive(Debug, Serialize, Clone)]
+pub struct AddressMatch {
+    pub chain: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<&'static str>,
+    pub format: &'static str,
+}
+
+impl KeyAddresses {
+    // Addresses of a SEC1 public key, compressed or uncompressed
+    pub fn from_public_key(public_key: &[u8]) -> Result<Self> {
+        let public_key = k256::PublicKey::from_sec1_bytes(public_key)
+            .map_err(|_| anyhow::anyhow!("Invalid secp256k1 public key"))?;
+        let compressed = public_key.to_encoded_point(true);
+        let uncompressed = public_key.to_encoded_point(false);
+
+        let key_hash = hash160(compressed.as_bytes());
+        let bitcoin = |(_, version, _, hrp): (&str, u8, u8, &str)| BitcoinAddresses {
+            p2pkh: base58check(version, &key_hash),
+            p2wpkh: bech32_encode(hrp, &[&[0u8][..], &convert_bits(&key_hash)].concat(), BECH32_CONST),
+        };
+
+        Ok(Self {
+            ethereum: ethereum_address(&uncompressed.as_bytes()[1..]),
+            bitcoin: BitcoinNetworks {
+                mainnet: bitcoin(BITCOIN_NETWORKS[0]),
+                testnet: bitcoin(BITCOIN_NETWORKS[1]),
+                regtest: bitcoin(BITCOIN_NETWORKS[2]),
+            },
+            cosmos: bech32_encode(COSMOS_HRP, &convert_bits(&key_hash), BECH32_CONST),
+        })
+    }
+
+    pub fn from_public_key_hex(public_key_hex: &str) -> Result<Self> {
+        Self::from_public_key(&hex::decode(public_key_hex)?)
+    }
+
+    // Hex and bech32 addresses compare case-insensitively, Base58 ones exactly
+    pub fn find(&self, address: &str) -> Option<AddressMatch> {
+        let address = address.trim();
+        if self.ethereum.eq_ignore_ascii_case(address) {
+            return Some(AddressMatch { chain: "ethereum", network: None, format: "eip55" });
+        }
+        if self.cosmos.eq_ignore_ascii_case(address) {
+            return Some(AddressMatch { chain: "cosmos", network: None, format: "bech32" });
+        }
+        let networks = [
+            ("mainnet", &self.bitcoin.mainnet),
+            ("testnet", &self.bitcoin.testnet),
+            ("regtest", &self.bitcoin.regtest),
+        ];
+        for (network, addresses) in networks {
+            if addresses.p2pkh == address {
+                return Some(AddressMatch { chain: "bitcoin", network: Some(network), format: "p2pkh" });
+            }
+            if addresses.p2wpkh.eq_ignore_ascii_case(address) {
+                return Some(AddressMatch { chain: "bitcoin", network: Some(network), format: "p2wpkh" });
+            }
+        }
+        None
+    }
+}
+
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+// Address paying to a Bitcoin output script on `network`: P2PKH, P2SH or a segwit
+// program. None for scripts without an address, such as OP_RETURN outputs.
+pub fn bitcoin_script_address(script_pubkey: &[u8], network: &str) -> Result<Option<String>> {
+    let (_, p2pkh_version, p2sh_version, hrp) = BITCOIN_NETWORKS
+        .into_iter()
+        .find(|(name, ..)| *name == network)
+        .ok_or_else(|| anyhow::anyhow!("Unknown Bitcoin network '{}'", network))?;
+
+    let address = match script_pubkey {
+        [0x76, 0xa9, 0x14, hash @ .., 0x88, 0xac] if hash.len() == 20 => Some(base58check(p2pkh_version, hash)),
+        [0xa9, 0x14, hash @ .., 0x87] if hash.len() == 20 => Some(base58check(p2sh_version, hash)),
+        // OP_0 or OP_1..OP_16, then a direct push of a 2 to 40 byte witness program
+        [opcode @ (0x00 | 0x51..=0x60), length, program @ ..]
+            if (2..=40).contains(length) && program.len() == *length as usize =>
+        {
+            let version = opcode.saturating_sub(0x50);
+            match version {
+                0 if program.len() != 20 && program.len() != 32 => None,
+                0 => Some(bech32_encode(hrp, &[&[0u8][..], &convert_bits(program)].concat(), BECH32_CONST)),
+                _ => Some(bech32_encode(hrp, &[&[version][..], &convert_bits(program)].concat(), BECH32M_CONST)),
+            }
+        }
+        _ => None,
+    };
+    Ok(address)
+}
+
+// Last 20 bytes of Keccak256(x || y)
+fn ethereum_address(public_key_xy: &[u8]) -> String {
+    eip55_checksum(&hex::encode(&Keccak256::digest(public_key_xy)[12..]))
+}
+
+// Hex letters uppercased where the matching nibble of Keccak256(lowercase hex
+// address) is 8 or more (EIP-55)
+fn eip55_checksum(address_hex: &str) -> String {
+    let address = address_hex.to_ascii_lowercase();
+    let checksum = Keccak256::digest(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (checksum[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+fn base58check(version: u8, payload: &[u8]) -> String {
+    let mut data = Vec::with_capacity(payload.len() + 5);
+    data.push(version);
+    data.extend_from_slice(payload);
+    let checksum = Sha256::digest(Sha256::digest(&data));
+    data.extend_from_slice(&checksum[..4]);
+    bs58::encode(data).into_string()
+}
+
+// Regroups bytes into 5-bit words, zero-padding the last one (BIP173 convertbits)
+fn convert_bits(data: &[u8]) -> Vec<u8> {
+    let mut words = Vec::with_capacity(data.len() * 8 / 5 + 1);
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+    for byte in data {
+        accumulator = (accumulator << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            words.push(((accumulator >> bits) & 0x1f) as u8);
+        }
+    }
+    if bits > 0 {
+        words.push(((accumulator << (5 - bits)) & 0x1f) as u8);
+    }
+    words
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ *value as u32;
+        for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+// Bech32 (BIP173) or bech32m (BIP350) string of 5-bit `words` under `hrp`, as
+// selected by the checksum `constant`
+fn bech32_encode(hrp: &str, words: &[u8], constant: u32) -> String {
+    let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 0x1f));
+    values.extend_from_slice(words);
+    values.extend_from_slice(&[0; 6]);
+    let checksum = bech32_polymod(&values) ^ constant;
+
+    let mut encoded = format!("{}1", hrp);
+    let checksum_words = (0..6).map(|i| ((checksum >> (5 * (5 - i))) & 0x1f) as u8);
+    for word in words.iter().copied().chain(checksum_words) {
+        encoded.push(BECH32_CHARSET[word as usize] as char);
+    }
+    encoded
+}
+
+#[derive(Serialize)]
+pub struct AddressLookupResponse {
+    pub success: bool,
+    pub message: String,
+    pub key_id: Option<String>,
+    pub path: Option<String>,
+    pub label: Option<String>,
+    pub public_key: Option<String>,
+    #[serde(rename = "match")]
+    pub matched: Option<AddressMatch>,
+}
+
+// Keys recorded before addresses were stored get them computed here
+fn find_key(auth: &crate::BasicAuth, address: &str) -> Result<Option<(String, crate::hd_keys::DerivedKeyInfo, AddressMatch)>> {
+    crate::hd_keys::find_accessible_key(auth, |key| {
+        let addresses = match &key.addresses {
+            Some(addresses) => addresses.clone(),
+            None => KeyAddresses::from_public_key_hex(&key.public_key_hex).ok()?,
+        };
+        addresses.find(address)
+    })
+}
+
+// Reverse lookup endpoint (GET): which of the caller's keys owns an address
+pub async fn lookup_address(
+    auth: crate::BasicAuth,
+    Path(address): Path<String>,
+) -> (StatusCode, ResponseJson<AddressLookupResponse>) {
+    let not_found = |status: StatusCode, message: String| {
+        (status, ResponseJson(AddressLookupResponse {
+            success: false,
+            message,
+            key_id: None,
+            path: None,
+            label: None,
+            public_key: None,
+            matched: None,
+        }))
+    };
+
+    match find_key(&auth, &address) {
+        Ok(Some((key_id, key, matched))) => {
+            tracing::info!(
+                address = %address,
+                key_id = %key_id,
+                path = %key.path,
+                chain = matched.chain,
+                "🔎 Address lookup matched a key"
+            );
+            (StatusCode::OK, ResponseJson(AddressLookupResponse {
+                success: true,
+                message: format!("Address belongs to key {} of wallet {}", key.path, key_id),
+                key_id: Some(key_id),
+                path: Some(key.path),
+                label: key.label,
+                public_key: Some(key.public_key_hex),
+                matched: Some(matched),
+            }))
+        }
+        Ok(None) => {
+            tracing::debug!(address = %address, username = %auth.username, "🔎 Address lookup found no key");
+            not_found(StatusCode::NOT_FOUND, format!("No key with address {}", address))
+        }
+        Err(e) => {
+            tracing::error!(address = %address, error = %e, "❌ Address lookup failed");
+            not_found(StatusCode::INTERNAL_SERVER_ERROR, format!("Address lookup failed: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Compressed public key of private key 1, i.e. the generator point
+    const GENERATOR: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    #[test]
+    fn generator_addresses_match_known_vectors() {
+        let addresses = KeyAddresses::from_public_key_hex(GENERATOR).unwrap();
+        assert_eq!(addresses.ethereum, "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+        // BIP173 P2WPKH test vectors
+        assert_eq!(addresses.bitcoin.mainnet.p2wpkh, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(addresses.bitcoin.testnet.p2wpkh, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx");
+        assert_eq!(addresses.bitcoin.regtest.p2wpkh, "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080");
+        assert_eq!(addresses.bitcoin.mainnet.p2pkh, "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+        assert_eq!(addresses.bitcoin.testnet.p2pkh, "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r");
+        assert_eq!(addresses.bitcoin.regtest.p2pkh, addresses.bitcoin.testnet.p2pkh);
+        assert_eq!(addresses.cosmos, "cosmos1w508d6qejxtdg4y5r3zarvary0c5xw7k6ah60c");
+    }
+
+    #[test]
+    fn uncompressed_keys_give_the_same_addresses() {
+        let compressed = hex::decode(GENERATOR).unwrap();
+        let uncompressed = k256::PublicKey::from_sec1_bytes(&compressed).unwrap().to_encoded_point(false);
+        assert_eq!(
+            KeyAddresses::from_public_key(uncompressed.as_bytes()).unwrap(),
+            KeyAddresses::from_public_key(&compressed).unwrap()
+        );
+    }
+
+    // Test vectors of the EIP-55 specification
+    #[test]
+    fn eip55_checksum_vectors() {
+        let vectors = [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x27b1fdb04752bbc536007a920d24acb045561c26",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ];
+        for address in vectors {
+            assert_eq!(eip55_checksum(&address[2..]), address);
+            assert_eq!(eip55_checksum(&address[2..].to_ascii_uppercase()), address);
+        }
+    }
+
+    #[test]
+    fn find_matches_by_chain_and_network() {
+        let addresses = KeyAddresses::from_public_key_hex(GENERATOR).unwrap();
+        let found = |address: &str| addresses.find(address).map(|found| (found.chain, found.network, found.format));
+
+        assert_eq!(found("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"), Some(("ethereum", None, "eip55")));
+        assert_eq!(found("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4"), Some(("bitcoin", Some("mainnet"), "p2wpkh")));
+        assert_eq!(found("mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r"), Some(("bitcoin", Some("testnet"), "p2pkh")));
+        // Base58 is case-sensitive
+        assert_eq!(found("1bggz9tcn4rm9kbzdn7kprqz87sz26samh"), None);
+        assert_eq!(found(" cosmos1w508d6qejxtdg4y5r3zarvary0c5xw7k6ah60c "), Some(("cosmos", None, "bech32")));
+    }
+
+    // Address vectors of rust-bitcoin and BIP350
+    #[test]
+    fn output_scripts_map_to_addresses() {
+        let address = |script: &str, network: &str| bitcoin_script_address(&hex::decode(script).unwrap(), network).unwrap();
+
+        let p2pkh = "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac";
+        assert_eq!(address(p2pkh, "mainnet").as_deref(), Some("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"));
+        assert_eq!(address(p2pkh, "regtest").as_deref(), Some("mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r"));
+        assert_eq!(
+            address("a914162c5ea71c0b23f5b9022ef047c4a86470a5b07087", "mainnet").as_deref(),
+            Some("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k")
+        );
+        assert_eq!(
+            address("0014751e76e8199196d454941c45d1b3a323f1433bd6", "testnet").as_deref(),
+            Some("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+        );
+        assert_eq!(
+            address("0020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d", "mainnet").as_deref(),
+            Some("bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej")
+        );
+        assert_eq!(
+            address(
+                "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+                "mainnet"
+            )
+            .as_deref(),
+            Some("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y")
+        );
+        // OP_RETURN and a v0 program of neither 20 nor 32 bytes
+        assert_eq!(address("6a0448656c6c6f", "mainnet"), None);
+        assert_eq!(address("0010751e76e8199196d454941c45d1b3a323", "mainnet"), None);
+        assert!(bitcoin_script_address(&hex::decode(p2pkh).unwrap(), "signet").is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::{
+    extract::{Json, Path},
+    http::StatusCode,
+    response::Json as ResponseJson,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::rbac::Permission;
+use crate::session_store::{read_service_json, write_service_json, ServiceRecord};
+
+// Tokens look like waas_<id>_<secret>; the id part is not secret and shows up in logs
+const TOKEN_PREFIX: &str = "waas_";
+const DEFAULT_TOKEN_TTL_DAYS: u32 = 90;
+const MAX_TOKEN_TTL_DAYS: u32 = 365;
+const MAX_TOKEN_NAME_LEN: usize = 128;
+
+// Serializes read-modify-write cycles of the tokens file
+static TOKENS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,    // GET endpoints and signature verification
+    Sign,    // /sign and transaction signing
+    Derive,  // child key derivation and deletion
+    Approve, // approving and rejecting signing requests
+    Admin,   // everything the owning user's role allows, including wallet and token management
+}
+
+impl Scope {
+    pub const ALL: [Scope; 5] = [Scope::Read, Scope::Sign, Scope::Derive, Scope::Approve, Scope::Admin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Sign => "sign",
+            Scope::Derive => "derive",
+            Scope::Approve => "approve",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+// Scope a token needs for an operation. Everything beyond reading, signing,
+// derivation and approvals needs the admin scope.
+pub fn required_scope(permission: Permission) -> Scope {
+    match permission {
+        Permission::Read => Scope::Read,
+        Permission::Sign => Scope::Sign,
+        Permission::Derive => Scope::Derive,
+        Permission::Approve => Scope::Approve,
+        Permission::ManageWallets | Permission::ManageTokens | Permission::Admin => Scope::Admin,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub owner: String, // Username the token acts as
+    pub scopes: Vec<Scope>,
+    pub secret_hash: String, // SHA-256 of the full token, hex
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked: bool,
+}
+
+impl ApiToken {
+    fn is_expired(&self) -> bool {
+        match chrono::DateTime::parse_from_rfc3339(&self.expires_at) {
+            Ok(expires_at) => expires_at <= chrono::Utc::now(),
+            Err(_) => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct TokenStore {
+    tokens: Vec<ApiToken>,
+}
+
+// Token as returned by the API, without the hash
+#[derive(Serialize)]
+pub struct TokenSummary {
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked: bool,
+}
+
+impl From<&ApiToken> for TokenSummary {
+    fn from(token: &ApiToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            owner: token.owner.clone(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at.clone(),
+            expires_at: token.expires_at.clone(),
+            revoked: token.revoked,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_in_days: Option<u32>, // Defaults to 90 days
+}
+
+#[derive(Serialize)]
+pub struct CreateTokenResponse {
+    pub success: bool,
+    pub message: String,
+    pub token: Option<String>, // Shown once; only its hash is stored
+    pub info: Option<TokenSummary>,
+}
+
+#[derive(Serialize)]
+pub struct ListTokensResponse {
+    pub success: bool,
+    pub message: String,
+    pub tokens: Vec<TokenSummary>,
+}
+
+#[derive(Serialize)]
+pub struct RevokeTokenResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+fn load_tokens() -> Result<TokenStore> {
+    Ok(read_service_json(ServiceRecord::ApiTokens)?.unwrap_or_default())
+}
+
+fn save_tokens(store: &TokenStore) -> Result<()> {
+    write_service_json(ServiceRecord::ApiTokens, store)
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Constant time, so the comparison reveals nothing about how much of the hash matched
+fn secret_matches(record: &ApiToken, token: &str) -> bool {
+    bool::from(record.secret_hash.as_bytes().ct_eq(hash_token(token).as_bytes()))
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Resolves a bearer token to the token record and the user it acts for.
+// Unknown, revoked and expired tokens and tokens of disabled users all fail.
+pub fn authenticate(token: &str) -> Result<Option<(ApiToken, crate::users::User)>> {
+    let Some((id, _secret)) = token
+        .strip_prefix(TOKEN_PREFIX)
+        .and_then(|rest| rest.split_once('_'))
+    else {
+        return Ok(None);
+    };
+
+    let store = load_tokens()?;
+    let Some(record) = store.tokens.iter().find(|record| record.id == id) else {
+        return Ok(None);
+    };
+    if !secret_matches(record, token) {
+        return Ok(None);
+    }
+    if record.revoked || record.is_expired() {
+        tracing::warn!(token_id = %record.id, owner = %record.owner, "🎫 Revoked or expired API token presented");
+        return Ok(None);
+    }
+
+    let Some(user) = crate::users::find_user(&record.owner)? else {
+        return Ok(None);
+    };
+    if user.disabled {
+        return Ok(None);
+    }
+    Ok(Some((record.clone(), user)))
+}
+
+fn create_token_impl(owner: &str, request: &CreateTokenRequest) -> Result<(String, ApiToken)> {
+    if request.name.trim().is_empty() || request.name.len() > MAX_TOKEN_NAME_LEN {
+        anyhow::bail!("Token name must be 1 to {} characters", MAX_TOKEN_NAME_LEN);
+    }
+    if request.scopes.is_empty() {
+        anyhow::bail!("At least one scope is required ({})", Scope::ALL.map(Scope::name).join(", "));
+    }
+    let ttl_days = request.expires_in_days.unwrap_or(DEFAULT_TOKEN_TTL_DAYS);
+    if !(1..=MAX_TOKEN_TTL_DAYS).contains(&ttl_days) {
+        anyhow::bail!("Token lifetime must be between 1 and {} days", MAX_TOKEN_TTL_DAYS);
+    }
+
+    let id = random_hex(8);
+    let token = format!("{}{}_{}", TOKEN_PREFIX, id, random_hex(32));
+    let now = chrono::Utc::now();
+    let mut scopes: Vec<Scope> = Vec::new();
+    for scope in &request.scopes {
+        if !scopes.contains(scope) {
+            scopes.push(*scope);
+        }
+    }
+    let record = ApiToken {
+        id,
+        name: request.name.clone(),
+        owner: owner.to_string(),
+        scopes,
+        secret_hash: hash_token(&token),
+        created_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::days(ttl_days.into())).to_rfc3339(),
+        revoked: false,
+    };
+
+    let _guard = TOKENS_LOCK.lock().unwrap();
+    let mut store = load_tokens()?;
+    store.tokens.push(record.clone());
+    save_tokens(&store)?;
+    Ok((token, record))
+}
+
+// Owners revoke their own tokens; admins may revoke anyone's
+fn revoke_token_impl(auth: &crate::BasicAuth, id: &str) -> Result<ApiToken> {
+    let _guard = TOKENS_LOCK.lock().unwrap();
+    let mut store = load_tokens()?;
+    let record = store
+        .tokens
+        .iter_mut()
+        .find(|record| record.id == id && (record.owner == auth.username || auth.is_admin()))
+        .ok_or_else(|| anyhow::anyhow!("Token {} not found", id))?;
+    record.revoked = true;
+    let record = record.clone();
+    save_tokens(&store)?;
+    Ok(record)
+}
+
+// Create an API token for the calling user (POST)
+pub async fn create_token(
+    auth: crate::BasicAuth,
+    Json(request): Json<CreateTokenRequest>,
+) -> (StatusCode, ResponseJson<CreateTokenResponse>) {
+    match create_token_impl(&auth.username, &request) {
+        Ok((token, record)) => {
+            tracing::info!(
+                token_id = %record.id,
+                owner = %record.owner,
+                created_by_token = ?auth.token_id,
+                scopes = ?record.scopes,
+                expires_at = %record.expires_at,
+                "🎫 API token created"
+            );
+            (StatusCode::OK, ResponseJson(CreateTokenResponse {
+                success: true,
+                message: "Token created. Store it now, it cannot be shown again".to_string(),
+                token: Some(token),
+                info: Some(TokenSummary::from(&record)),
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(owner = %auth.username, error = %e, "⚠️ Failed to create API token");
+            (StatusCode::BAD_REQUEST, ResponseJson(CreateTokenResponse {
+                success: false,
+                message: format!("Failed to create token: {}", e),
+                token: None,
+                info: None,
+            }))
+        }
+    }
+}
+
+// List the calling user's API tokens (GET)
+pub async fn list_tokens(auth: crate::BasicAuth) -> ResponseJson<ListTokensResponse> {
+    match load_tokens() {
+        Ok(store) => {
+            let tokens: Vec<TokenSummary> = store
+                .tokens
+                .iter()
+                .filter(|token| token.owner == auth.username)
+                .map(TokenSummary::from)
+                .collect();
+            ResponseJson(ListTokensResponse {
+                success: true,
+                message: format!("{} tokens", tokens.len()),
+                tokens,
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to load API tokens");
+            ResponseJson(ListTokensResponse {
+                success: false,
+                message: format!("Failed to load tokens: {}", e),
+                tokens: vec![],
+            })
+        }
+    }
+}
+
+// Revoke an API token (POST)
+pub async fn revoke_token(
+    auth: crate::BasicAuth,
+    Path(id): Path<String>,
+) -> (StatusCode, ResponseJson<RevokeTokenResponse>) {
+    match revoke_token_impl(&auth, &id) {
+        Ok(record) => {
+            tracing::info!(
+                token_id = %record.id,
+                owner = %record.owner,
+                revoked_by = %auth.username,
+                "🎫 API token revoked"
+            );
+            (StatusCode::OK, ResponseJson(RevokeTokenResponse {
+                success: true,
+                message: format!("Token {} revoked", record.id),
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(token_id = %id, error = %e, "⚠️ Failed to revoke API token");
+            (StatusCode::NOT_FOUND, ResponseJson(RevokeTokenResponse {
+                success: false,
+                message: format!("Failed to revoke token: {}", e),
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_issued_secret_matches() {
+        let token = format!("{}0011223344556677_{}", TOKEN_PREFIX, "ab".repeat(32));
+        let record = ApiToken {
+            id: "0011223344556677".to_string(),
+            name: "ci".to_string(),
+            owner: "alice".to_string(),
+            scopes: vec![Scope::Read],
+            secret_hash: hash_token(&token),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            expires_at: chrono::Utc::now().to_rfc3339(),
+            revoked: false,
+        };
+
+        assert!(secret_matches(&record, &token));
+        assert!(!secret_matches(&record, &format!("{}c", &token[..token.len() - 1])));
+        assert!(!secret_matches(&record, &token[..token.len() - 1]));
+        assert!(!secret_matches(&record, &record.secret_hash));
+    }
+}
@@ -0,0 +1,839 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::{
+    extract::{Json, Path, Query},
+    http::StatusCode,
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::rbac::Role;
+use crate::session_store::{read_service_json, write_service_json, ServiceRecord};
+
+const DEFAULT_APPROVAL_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
+// Serializes read-modify-write cycles of the signing requests file
+static REQUESTS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestStatus {
+    Pending,  // Waiting for approvals
+    Approved, // Approvals collected, signing not started yet
+    Signing,  // TSS protocol running
+    Signed,
+    Rejected,
+    Expired, // Deadline passed before the approvals were collected
+    Failed,  // Approved, but the TSS protocol failed
+}
+
+impl RequestStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            RequestStatus::Pending => "pending",
+            RequestStatus::Approved => "approved",
+            RequestStatus::Signing => "signing",
+            RequestStatus::Signed => "signed",
+            RequestStatus::Rejected => "rejected",
+            RequestStatus::Expired => "expired",
+            RequestStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Decision {
+    pub username: String,
+    pub at: String,
+    pub comment: Option<String>,
+}
+
+// A transaction kept as sent, so that approving the request signs exactly that transaction
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PendingTransaction {
+    Ethereum { transaction: crate::eth_tx::EthTransaction },
+    Psbt { psbt: String, network: String }, // Base64 PSBT as sent
+}
+
+// What signing an approved request produced
+pub struct ApprovedSignature {
+    pub signature: Option<String>,
+    pub recovery_id: Option<u8>,
+    pub signers: Vec<String>,
+    pub signed_transaction: Option<String>,
+}
+
+// A /sign or transaction signing request held back until enough approvers agree
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SigningRequest {
+    pub id: String,
+    pub key_id: String,
+    pub path: String,
+    pub message: String,
+    #[serde(default)]
+    pub mode: crate::sign::SigningMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed_data: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<crate::sign::SignatureEncoding>,
+    #[serde(default)]
+    pub hash: crate::sign::HashAlgorithm,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<PendingTransaction>, // Set for transactions; `message` then holds the hex payload
+    pub participants: Option<Vec<String>>,
+    pub destination: Option<String>,
+    pub value: Option<String>,
+    pub requested_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub required_approvals: u32,
+    pub approvers: Vec<String>, // Designated approvers; empty means any approver or admin
+    pub approvals: Vec<Decision>,
+    pub rejection: Option<Decision>,
+    pub status: RequestStatus,
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_id: Option<u8>,
+    pub signers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_transaction: Option<String>, // Raw signed Ethereum transaction, or the PSBT with its new signatures
+    pub error: Option<String>,
+}
+
+impl SigningRequest {
+    // A pending request of `auth` for `key_path`; callers fill in what is signed
+    pub fn new(auth: &crate::BasicAuth, key_id: &str, key_path: &str, evaluation: &crate::policy::Evaluation) -> Self {
+        let now = chrono::Utc::now();
+        let timeout = evaluation.approval_timeout_secs.unwrap_or(DEFAULT_APPROVAL_TIMEOUT_SECS);
+        SigningRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            key_id: key_id.to_string(),
+            path: key_path.to_string(),
+            message: String::new(),
+            mode: Default::default(),
+            typed_data: None,
+            encoding: None,
+            hash: Default::default(),
+            digest: None,
+            transaction: None,
+            participants: None,
+            destination: None,
+            value: None,
+            requested_by: auth.username.clone(),
+            created_at: now.to_rfc3339(),
+            expires_at: (now + chrono::Duration::seconds(timeout.min(i64::MAX as u64) as i64)).to_rfc3339(),
+            required_approvals: evaluation.required_approvals,
+            approvers: evaluation.approvers.clone(),
+            approvals: vec![],
+            rejection: None,
+            status: RequestStatus::Pending,
+            signature: None,
+            recovery_id: None,
+            signers: vec![],
+            signed_transaction: None,
+            error: None,
+        }
+    }
+
+    // The payload whose digest is signed, as /sign or the transaction endpoint computed it
+    fn payload(&self) -> Result<Vec<u8>> {
+        if self.transaction.is_some() {
+            return Ok(hex::decode(self.message.strip_prefix("0x").unwrap_or(&self.message))?);
+        }
+        crate::sign::signing_payload(self.mode, &self.message, self.typed_data.as_ref())
+    }
+
+    // The digest the signature covers; every PSBT input has its own
+    fn signed_digest(&self) -> Option<[u8; 32]> {
+        let payload = self.payload().ok()?;
+        match &self.transaction {
+            None => crate::sign::message_digest(self.mode, self.hash, self.digest.as_deref(), &payload).ok(),
+            Some(PendingTransaction::Ethereum { .. }) => Some(crate::sign::HashAlgorithm::Keccak256.digest(&payload)),
+            Some(PendingTransaction::Psbt { .. }) => None,
+        }
+    }
+
+    fn format(&self) -> crate::policy::MessageFormat {
+        if self.transaction.is_some() {
+            return crate::policy::MessageFormat::Hex;
+        }
+        crate::sign::message_format(self.mode, &self.message, self.digest.as_deref())
+    }
+
+    fn is_past_deadline(&self) -> bool {
+        match chrono::DateTime::parse_from_rfc3339(&self.expires_at) {
+            Ok(expires_at) => expires_at <= chrono::Utc::now(),
+            Err(_) => true,
+        }
+    }
+
+    // Marks a pending request whose deadline passed as expired; true if it changed
+    fn expire_if_due(&mut self) -> bool {
+        if self.status == RequestStatus::Pending && self.is_past_deadline() {
+            self.status = RequestStatus::Expired;
+            return true;
+        }
+        false
+    }
+
+    // Designated approvers were picked by someone allowed to set the wallet's policy;
+    // without them, approvers and admins may only decide on wallets they can access
+    fn can_decide(&self, auth: &crate::BasicAuth) -> bool {
+        if auth.username == self.requested_by {
+            return false;
+        }
+        if self.approvers.is_empty() {
+            matches!(auth.role, Role::Approver | Role::Admin) && crate::wallets::authorize(auth, &self.key_id).is_ok()
+        } else {
+            self.approvers.contains(&auth.username)
+        }
+    }
+
+    fn is_visible_to(&self, auth: &crate::BasicAuth) -> bool {
+        auth.is_admin() || auth.username == self.requested_by || self.can_decide(auth)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RequestStore {
+    requests: Vec<SigningRequest>,
+}
+
+#[derive(Deserialize)]
+pub struct ListRequestsQuery {
+    pub status: Option<RequestStatus>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct DecisionRequest {
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SigningRequestsResponse {
+    pub success: bool,
+    pub message: String,
+    pub requests: Vec<SigningRequest>,
+}
+
+// Unknown request IDs and requests the caller may not see look the same
+#[derive(Debug)]
+struct RequestNotFound(String);
+
+impl std::fmt::Display for RequestNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Signing request {} not found", self.0)
+    }
+}
+
+impl std::error::Error for RequestNotFound {}
+
+#[derive(Debug)]
+struct NotAnApprover;
+
+impl std::fmt::Display for NotAnApprover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "You are not an approver of this signing request")
+    }
+}
+
+impl std::error::Error for NotAnApprover {}
+
+fn load_requests() -> Result<RequestStore> {
+    Ok(read_service_json(ServiceRecord::SigningRequests)?.unwrap_or_default())
+}
+
+fn save_requests(store: &RequestStore) -> Result<()> {
+    write_service_json(ServiceRecord::SigningRequests, store)
+}
+
+// Runs `change` on request `id` under the lock and saves the result
+fn update_request<T>(id: &str, change: impl FnOnce(&mut SigningRequest) -> Result<T>) -> Result<(SigningRequest, T)> {
+    let _guard = REQUESTS_LOCK.lock().unwrap();
+    let mut store = load_requests()?;
+    let request = store
+        .requests
+        .iter_mut()
+        .find(|request| request.id == id)
+        .ok_or_else(|| RequestNotFound(id.to_string()))?;
+    let expired = request.expire_if_due();
+    let outcome = change(request);
+    let request = request.clone();
+    if outcome.is_ok() || expired {
+        save_requests(&store)?;
+    }
+    Ok((request, outcome?))
+}
+
+// Stores a /sign request that needs `evaluation.required_approvals` approvals
+pub fn create_request(
+    auth: &crate::BasicAuth,
+    sign_request: &crate::sign::SignRequest,
+    key_path: &str,
+    evaluation: &crate::policy::Evaluation,
+) -> Result<SigningRequest> {
+    submit_request(auth, SigningRequest {
+        message: sign_request.message.clone(),
+        mode: sign_request.mode,
+        typed_data: sign_request.typed_data.clone(),
+        encoding: sign_request.encoding,
+        hash: sign_request.hash,
+        digest: sign_request.digest.clone(),
+        participants: sign_request.participants.clone(),
+        destination: sign_request.destination.clone(),
+        value: sign_request.value.clone(),
+        ..SigningRequest::new(auth, &sign_request.key_id, key_path, evaluation)
+    })
+}
+
+// Stores a new request built with `SigningRequest::new`
+pub fn submit_request(auth: &crate::BasicAuth, request: SigningRequest) -> Result<SigningRequest> {
+    let _guard = REQUESTS_LOCK.lock().unwrap();
+    let mut store = load_requests()?;
+    store.requests.push(request.clone());
+    save_requests(&store)?;
+
+    tracing::info!(
+        request_id = %request.id,
+        key_id = %request.key_id,
+        path = %request.path,
+        requested_by = %request.requested_by,
+        required_approvals = request.required_approvals,
+        expires_at = %request.expires_at,
+        "🗳️ Signing request waiting for approvals"
+    );
+    crate::audit::record(
+        crate::audit::AuditEvent::new(auth, "sign_request", "pending")
+            .key_id(&request.key_id)
+            .path(&request.path)
+            .message(request.payload().unwrap_or_default())
+            .detail(format!("request {} needs {} approvals", request.id, request.required_approvals)),
+    );
+    Ok(request)
+}
+
+fn decide_impl(auth: &crate::BasicAuth, id: &str, approve: bool, comment: Option<String>) -> Result<SigningRequest> {
+    let (request, ()) = update_request(id, |request| {
+        if !request.is_visible_to(auth) {
+            return Err(RequestNotFound(id.to_string()).into());
+        }
+        if !request.can_decide(auth) {
+            return Err(NotAnApprover.into());
+        }
+        if request.status != RequestStatus::Pending {
+            anyhow::bail!("Signing request {} is {}", request.id, request.status.name());
+        }
+        if request.approvals.iter().any(|decision| decision.username == auth.username) {
+            anyhow::bail!("You already approved signing request {}", request.id);
+        }
+
+        let decision = Decision {
+            username: auth.username.clone(),
+            at: chrono::Utc::now().to_rfc3339(),
+            comment,
+        };
+        if approve {
+            request.approvals.push(decision);
+            if request.approvals.len() as u32 >= request.required_approvals {
+                request.status = RequestStatus::Approved;
+            }
+        } else {
+            // A single rejection ends the request
+            request.rejection = Some(decision);
+            request.status = RequestStatus::Rejected;
+        }
+        Ok(())
+    })?;
+
+    crate::audit::record(
+        crate::audit::AuditEvent::new(auth, if approve { "approve" } else { "reject" }, request.status.name())
+            .key_id(&request.key_id)
+            .path(&request.path)
+            .message(request.payload().unwrap_or_default())
+            .detail(format!("request {}", request.id)),
+    );
+    Ok(request)
+}
+
+// Runs the TSS protocol for an approved request. Claiming the request under the
+// lock first makes sure it is signed at most once and only with enough approvals.
+async fn execute(id: String) {
+    let claimed = update_request(&id, |request| {
+        if request.status != RequestStatus::Approved || (request.approvals.len() as u32) < request.required_approvals {
+            anyhow::bail!("Signing request {} is {}, not approved", request.id, request.status.name());
+        }
+        request.status = RequestStatus::Signing;
+        Ok(())
+    });
+    let request = match claimed {
+        Ok((request, ())) => request,
+        Err(e) => {
+            tracing::warn!(request_id = %id, error = %e, "⚠️ Approved signing request not executed");
+            return;
+        }
+    };
+
+    tracing::info!(request_id = %request.id, key_id = %request.key_id, "🔐 Approvals collected, starting TSS signing");
+    let start_time = std::time::Instant::now();
+    let result = async {
+        check_requester_access(&request)?;
+        match &request.transaction {
+            // Checks the policy of every key that signs an input
+            Some(PendingTransaction::Psbt { psbt, network }) => crate::btc_psbt::sign_approved(&request, psbt, network).await,
+            Some(PendingTransaction::Ethereum { transaction }) => {
+                enforce_policy_again(&request)?;
+                crate::eth_tx::sign_approved(&request, transaction).await
+            }
+            None => {
+                enforce_policy_again(&request)?;
+                sign_approved_message(&request).await
+            }
+        }
+    }
+    .await;
+
+    let outcome = match &result {
+        Ok(_) => "signed",
+        Err(_) => "failed",
+    };
+    let stored = update_request(&id, |request| {
+        match result {
+            Ok(signed) => {
+                request.status = RequestStatus::Signed;
+                request.signature = signed.signature;
+                request.recovery_id = signed.recovery_id;
+                request.signers = signed.signers;
+                request.signed_transaction = signed.signed_transaction;
+            }
+            Err(e) => {
+                request.status = RequestStatus::Failed;
+                request.error = Some(e.to_string());
+            }
+        }
+        Ok(())
+    });
+
+    match stored {
+        Ok((request, ())) => {
+            tracing::info!(
+                request_id = %request.id,
+                status = request.status.name(),
+                error = ?request.error,
+                duration_ms = start_time.elapsed().as_millis(),
+                "🗳️ Approved signing request finished"
+            );
+            let mut event = crate::audit::AuditEvent::by(&request.requested_by, "sign", outcome)
+                .key_id(&request.key_id)
+                .path(&request.path)
+                .message(request.payload().unwrap_or_default())
+                .detail(format!("request {} approved by {}", request.id, approver_names(&request)));
+            if let Some(digest) = request.signed_digest() {
+                event = event.signed_digest(&digest);
+            }
+            crate::audit::record(event);
+        }
+        Err(e) => tracing::error!(request_id = %id, error = %e, "❌ Failed to store signing request result"),
+    }
+}
+
+// The policy may have changed while the request waited; this also counts it against the rate limit
+fn enforce_policy_again(request: &SigningRequest) -> Result<()> {
+    let evaluation = crate::policy::enforce_approved(&crate::policy::SigningContext {
+        key_id: &request.key_id,
+        path: &request.path,
+        format: request.format(),
+        destination: request.destination.as_deref(),
+        value: request.value.as_deref(),
+    })?;
+    if !evaluation.allowed {
+        anyhow::bail!("Signing refused by policy: {}", evaluation.violations.join("; "));
+    }
+    Ok(())
+}
+
+async fn sign_approved_message(request: &SigningRequest) -> Result<ApprovedSignature> {
+    let payload = request.payload()?;
+    let digest = crate::sign::message_digest(request.mode, request.hash, request.digest.as_deref(), &payload)?;
+    let target = crate::sign::SignTarget::select(request.hash, request.digest.is_some(), &payload, &digest);
+    let encoding = request.encoding.unwrap_or(crate::sign::SignatureEncoding::default_for(request.mode));
+    let signed =
+        crate::sign::sign_message(&request.key_id, &request.path, encoding, target, request.participants.as_deref()).await?;
+    Ok(ApprovedSignature {
+        signature: Some(signed.signature),
+        recovery_id: Some(signed.recovery_id),
+        signers: signed.signers,
+        signed_transaction: None,
+    })
+}
+
+// The wallet may have been deleted, or the requester removed, while the request waited
+fn check_requester_access(request: &SigningRequest) -> Result<()> {
+    let wallet = crate::wallets::load_wallet(&request.key_id)?;
+    let requester = crate::users::find_user(&request.requested_by)?
+        .filter(|user| !user.disabled)
+        .ok_or_else(|| anyhow::anyhow!("Requester {} no longer has an active account", request.requested_by))?;
+    if wallet.owner() != requester.username && requester.role != Role::Admin {
+        anyhow::bail!("Requester {} no longer has access to wallet {}", request.requested_by, request.key_id);
+    }
+    Ok(())
+}
+
+fn approver_names(request: &SigningRequest) -> String {
+    request
+        .approvals
+        .iter()
+        .map(|decision| decision.username.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Picks up requests interrupted by a restart: approved ones are signed now, and
+// ones that were mid-signature are signed again from the start
+pub fn resume_approved_requests() -> Result<()> {
+    let ids: Vec<String> = {
+        let _guard = REQUESTS_LOCK.lock().unwrap();
+        let mut store = load_requests()?;
+        let mut ids = Vec::new();
+        for request in &mut store.requests {
+            request.expire_if_due();
+            if request.status == RequestStatus::Signing {
+                request.status = RequestStatus::Approved;
+            }
+            if request.status == RequestStatus::Approved {
+                ids.push(request.id.clone());
+            }
+        }
+        save_requests(&store)?;
+        ids
+    };
+
+    if !ids.is_empty() {
+        tracing::info!(requests = ids.len(), "🗳️ Resuming approved signing requests");
+    }
+    for id in ids {
+        tokio::spawn(execute(id));
+    }
+    Ok(())
+}
+
+fn error_status(e: &anyhow::Error) -> StatusCode {
+    if e.downcast_ref::<RequestNotFound>().is_some() {
+        StatusCode::NOT_FOUND
+    } else if e.downcast_ref::<NotAnApprover>().is_some() {
+        StatusCode::FORBIDDEN
+    } else {
+        StatusCode::CONFLICT
+    }
+}
+
+// Signing requests the caller made or may decide on; admins see all (GET)
+pub async fn list_requests(
+    auth: crate::BasicAuth,
+    Query(query): Query<ListRequestsQuery>,
+) -> (StatusCode, ResponseJson<SigningRequestsResponse>) {
+    let loaded = {
+        let _guard = REQUESTS_LOCK.lock().unwrap();
+        load_requests().and_then(|mut store| {
+            // Persist expiries so the file matches what is reported
+            let mut expired = false;
+            for request in &mut store.requests {
+                expired |= request.expire_if_due();
+            }
+            if expired {
+                save_requests(&store)?;
+            }
+            Ok(store)
+        })
+    };
+
+    match loaded {
+        Ok(store) => {
+            let requests: Vec<SigningRequest> = store
+                .requests
+                .into_iter()
+                .filter(|request| request.is_visible_to(&auth))
+                .filter(|request| query.status.is_none_or(|status| request.status == status))
+                .collect();
+            (StatusCode::OK, ResponseJson(SigningRequestsResponse {
+                success: true,
+                message: format!("{} signing requests", requests.len()),
+                requests,
+            }))
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to load signing requests");
+            (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(SigningRequestsResponse {
+                success: false,
+                message: format!("Failed to load signing requests: {}", e),
+                requests: vec![],
+            }))
+        }
+    }
+}
+
+// One signing request, e.g. to poll for the signature (GET)
+pub async fn get_request(
+    auth: crate::BasicAuth,
+    Path(id): Path<String>,
+) -> (StatusCode, ResponseJson<SigningRequestsResponse>) {
+    let found = update_request(&id, |request| {
+        if !request.is_visible_to(&auth) {
+            return Err(RequestNotFound(id.clone()).into());
+        }
+        Ok(())
+    });
+
+    match found {
+        Ok((request, ())) => (StatusCode::OK, ResponseJson(SigningRequestsResponse {
+            success: true,
+            message: format!("Signing request {} is {}", request.id, request.status.name()),
+            requests: vec![request],
+        })),
+        Err(e) => (error_status(&e), ResponseJson(SigningRequestsResponse {
+            success: false,
+            message: e.to_string(),
+            requests: vec![],
+        })),
+    }
+}
+
+async fn decide(
+    auth: crate::BasicAuth,
+    id: String,
+    approve: bool,
+    comment: Option<String>,
+) -> (StatusCode, ResponseJson<SigningRequestsResponse>) {
+    match decide_impl(&auth, &id, approve, comment) {
+        Ok(request) => {
+            tracing::info!(
+                request_id = %request.id,
+                username = %auth.username,
+                approve = approve,
+                approvals = request.approvals.len(),
+                required_approvals = request.required_approvals,
+                status = request.status.name(),
+                "🗳️ Signing request decision recorded"
+            );
+            if request.status == RequestStatus::Approved {
+                tokio::spawn(execute(request.id.clone()));
+            }
+            let message = match request.status {
+                RequestStatus::Approved => format!("Signing request {} approved, signing started", request.id),
+                RequestStatus::Rejected => format!("Signing request {} rejected", request.id),
+                _ => format!(
+                    "Approval recorded, {} of {} collected",
+                    request.approvals.len(),
+                    request.required_approvals
+                ),
+            };
+            (StatusCode::OK, ResponseJson(SigningRequestsResponse {
+                success: true,
+                message,
+                requests: vec![request],
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(request_id = %id, username = %auth.username, error = %e, "⚠️ Signing request decision refused");
+            (error_status(&e), ResponseJson(SigningRequestsResponse {
+                success: false,
+                message: e.to_string(),
+                requests: vec![],
+            }))
+        }
+    }
+}
+
+// Approve a signing request (POST)
+pub async fn approve_request(
+    auth: crate::BasicAuth,
+    Path(id): Path<String>,
+    body: Option<Json<DecisionRequest>>,
+) -> (StatusCode, ResponseJson<SigningRequestsResponse>) {
+    let Json(body) = body.unwrap_or_default();
+    decide(auth, id, true, body.comment).await
+}
+
+// Reject a signing request (POST)
+pub async fn reject_request(
+    auth: crate::BasicAuth,
+    Path(id): Path<String>,
+    body: Option<Json<DecisionRequest>>,
+) -> (StatusCode, ResponseJson<SigningRequestsResponse>) {
+    let Json(body) = body.unwrap_or_default();
+    decide(auth, id, false, body.comment).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(username: &str, role: Role) -> crate::BasicAuth {
+        crate::BasicAuth { username: username.to_string(), role, token_id: None, scopes: None }
+    }
+
+    // A wallet owned by `owner` and a pending request on it; names are unique per test
+    fn pending_request(name: &str, owner: &str, required_approvals: u32, approvers: &[&str]) -> String {
+        crate::session_store::init_test_key_store();
+        let key_id = format!("approvals-{}-{}", name, std::process::id());
+        crate::wallets::save_wallet(&crate::wallets::WalletInfo {
+            key_id: key_id.clone(),
+            name: None,
+            public_key: "02aa".to_string(),
+            threshold: 2,
+            participants: 3,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            owner: Some(owner.to_string()),
+        })
+        .unwrap();
+        store_request(&key_id, owner, required_approvals, approvers)
+    }
+
+    fn store_request(key_id: &str, requested_by: &str, required_approvals: u32, approvers: &[&str]) -> String {
+        let requester = auth(requested_by, Role::Operator);
+        let evaluation = crate::policy::Evaluation {
+            allowed: true,
+            required_approvals,
+            approvers: approvers.iter().map(|approver| approver.to_string()).collect(),
+            approval_timeout_secs: None,
+            violations: vec![],
+            policy: None,
+        };
+        let request = SigningRequest {
+            message: "pay 1 ETH".to_string(),
+            ..SigningRequest::new(&requester, key_id, "m", &evaluation)
+        };
+        submit_request(&requester, request).unwrap().id
+    }
+
+    fn refusal(auth: &crate::BasicAuth, id: &str, approve: bool) -> (StatusCode, String) {
+        match decide_impl(auth, id, approve, None) {
+            Ok(request) => panic!("decision unexpectedly recorded, request is {}", request.status.name()),
+            Err(e) => (error_status(&e), e.to_string()),
+        }
+    }
+
+    async fn visible_ids(auth: crate::BasicAuth) -> Vec<String> {
+        let (_, ResponseJson(response)) = list_requests(auth, Query(ListRequestsQuery { status: None })).await;
+        response.requests.into_iter().map(|request| request.id).collect()
+    }
+
+    #[test]
+    fn requesters_cannot_approve_their_own_request() {
+        let id = pending_request("self", "self-owner", 1, &[]);
+        let (status, _) = refusal(&auth("self-owner", Role::Admin), &id, true);
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Not even when designated as an approver
+        let id = pending_request("self-designated", "self-approver", 1, &["self-approver"]);
+        let (status, _) = refusal(&auth("self-approver", Role::Approver), &id, true);
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn approvers_only_see_requests_for_wallets_they_can_access() {
+        let id = pending_request("tenant", "tenant-alice", 1, &[]);
+        let outsider = auth("tenant-mallory", Role::Approver);
+        assert!(!visible_ids(outsider.clone()).await.contains(&id));
+        let (status, _) = refusal(&outsider, &id, true);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, ResponseJson(_)) = get_request(outsider.clone(), Path(id.clone())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = refusal(&outsider, &id, false);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Admins can access every wallet
+        let admin = auth("tenant-admin", Role::Admin);
+        assert!(visible_ids(admin.clone()).await.contains(&id));
+        assert_eq!(decide_impl(&admin, &id, true, None).unwrap().status, RequestStatus::Approved);
+
+        // A designated approver needs no access of their own
+        let id = pending_request("tenant-designated", "tenant-alice", 1, &["tenant-bob"]);
+        let designated = auth("tenant-bob", Role::Approver);
+        assert!(visible_ids(designated.clone()).await.contains(&id));
+        assert!(!visible_ids(outsider.clone()).await.contains(&id));
+        let (status, _) = refusal(&outsider, &id, true);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(decide_impl(&designated, &id, true, None).unwrap().status, RequestStatus::Approved);
+    }
+
+    #[test]
+    fn each_designated_approver_counts_once() {
+        let id = pending_request("quorum", "quorum-alice", 2, &["quorum-bob", "quorum-carol"]);
+        let (bob, carol) = (auth("quorum-bob", Role::Approver), auth("quorum-carol", Role::Viewer));
+
+        let request = decide_impl(&bob, &id, true, Some("looks right".to_string())).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(request.approvals.len(), 1);
+        let (status, message) = refusal(&bob, &id, true);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(message.contains("already approved"), "{}", message);
+
+        let request = decide_impl(&carol, &id, true, None).unwrap();
+        assert_eq!(request.status, RequestStatus::Approved);
+        assert_eq!(approver_names(&request), "quorum-bob, quorum-carol");
+        let (status, _) = refusal(&bob, &id, false);
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn a_single_rejection_ends_the_request() {
+        let id = pending_request("reject", "reject-alice", 2, &["reject-bob", "reject-carol"]);
+        let (bob, carol) = (auth("reject-bob", Role::Approver), auth("reject-carol", Role::Approver));
+
+        decide_impl(&bob, &id, true, None).unwrap();
+        let request = decide_impl(&carol, &id, false, Some("wrong amount".to_string())).unwrap();
+        assert_eq!(request.status, RequestStatus::Rejected);
+        assert_eq!(request.rejection.unwrap().comment.as_deref(), Some("wrong amount"));
+
+        let (status, message) = refusal(&bob, &id, true);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(message.contains("is rejected"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn approved_requests_are_not_signed_without_wallet_access() {
+        let id = pending_request("gone", "gone-alice", 1, &[]);
+        decide_impl(&auth("gone-admin", Role::Admin), &id, true, None).unwrap();
+        // The requester has no account in the user store
+        execute(id.clone()).await;
+        let (request, ()) = update_request(&id, |_| Ok(())).unwrap();
+        assert_eq!(request.status, RequestStatus::Failed);
+        assert!(request.error.unwrap().contains("no longer has an active account"));
+
+        // The wallet was deleted while the request waited
+        let id = store_request(&format!("approvals-deleted-{}", std::process::id()), "gone-alice", 1, &["gone-bob"]);
+        decide_impl(&auth("gone-bob", Role::Approver), &id, true, None).unwrap();
+        execute(id.clone()).await;
+        let (request, ()) = update_request(&id, |_| Ok(())).unwrap();
+        assert_eq!(request.status, RequestStatus::Failed);
+        assert!(request.error.unwrap().contains("not found"));
+    }
+
+    #[test]
+    fn transaction_requests_are_checked_as_hex_payloads() {
+        let transaction: crate::eth_tx::EthTransaction = serde_json::from_value(serde_json::json!({
+            "chain_id": 1, "nonce": 0, "gas_price": 1, "gas_limit": 21000, "value": "5",
+        }))
+        .unwrap();
+        let request = SigningRequest {
+            message: "0xc0ffee".to_string(),
+            transaction: Some(PendingTransaction::Ethereum { transaction }),
+            ..SigningRequest::new(&auth("tx-alice", Role::Operator), "tx-key", "m", &crate::policy::Evaluation {
+                allowed: true,
+                required_approvals: 1,
+                approvers: vec![],
+                approval_timeout_secs: None,
+                violations: vec![],
+                policy: None,
+            })
+        };
+        let stored: SigningRequest = serde_json::from_value(serde_json::to_value(&request).unwrap()).unwrap();
+        assert_eq!(stored.payload().unwrap(), vec![0xc0, 0xff, 0xee]);
+        assert_eq!(stored.format(), crate::policy::MessageFormat::Hex);
+        assert!(matches!(stored.transaction, Some(PendingTransaction::Ethereum { .. })));
+    }
+}
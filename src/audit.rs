@@ -0,0 +1,562 @@
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::{extract::Query, http::StatusCode, response::Json as ResponseJson};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::session_store::{read_service_json, write_file_atomic, write_service_json, ServiceRecord};
+
+const AUDIT_LOG_ENV: &str = "WAAS_AUDIT_LOG";
+const DEFAULT_AUDIT_LOG: &str = "audit.log";
+const CHECKPOINT_MAC_CONTEXT: &str = "waas audit checkpoint";
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+// Serializes appends so concurrent entries never interleave, and holds the last
+// entry of the chain once it is known
+static AUDIT_LOCK: Mutex<Option<ChainHead>> = Mutex::new(None);
+
+// What happened, as recorded by the handlers
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub actor: String,
+    pub token_id: Option<String>,
+    pub operation: String,
+    pub key_id: Option<String>,
+    pub path: Option<String>,           // Derivation path of the child key, e.g. m/0/5
+    pub message_digest: Option<String>, // SHA-256 of the signed message, hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_digest: Option<String>, // The 32-byte digest a signature covers, hex
+    pub outcome: String,
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(auth: &crate::BasicAuth, operation: &str, outcome: &str) -> Self {
+        let mut event = Self::by(&auth.username, operation, outcome);
+        event.token_id = auth.token_id.clone();
+        event
+    }
+
+    // Event on behalf of `actor` outside a request, e.g. an approved signature
+    pub fn by(actor: &str, operation: &str, outcome: &str) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            actor: actor.to_string(),
+            token_id: None,
+            operation: operation.to_string(),
+            key_id: None,
+            path: None,
+            message_digest: None,
+            signed_digest: None,
+            outcome: outcome.to_string(),
+            detail: None,
+        }
+    }
+
+    pub fn key_id(mut self, key_id: &str) -> Self {
+        self.key_id = Some(key_id.to_string());
+        self
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    // Records the digest of `message`; the message itself stays out of the log
+    pub fn message(mut self, message: impl AsRef<[u8]>) -> Self {
+        self.message_digest = Some(hex::encode(Sha256::digest(message.as_ref())));
+        self
+    }
+
+    pub fn signed_digest(mut self, digest: &[u8; 32]) -> Self {
+        self.signed_digest = Some(hex::encode(digest));
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+// One line of the audit log: the event, chained to the entry before it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ChainHead {
+    pub seq: u64,
+    pub hash: String,
+}
+
+// Last entry of the log, authenticated with the master key. Rewriting the chain
+// or cutting entries off its end no longer matches it.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    #[serde(flatten)]
+    head: ChainHead,
+    updated_at: String,
+    mac: String,
+}
+
+// Result of checking the whole log
+pub struct VerifyReport {
+    pub entries: u64,
+    pub head: Option<ChainHead>,
+    pub issues: Vec<String>,   // Evidence of modification or truncation
+    pub warnings: Vec<String>, // Inconsistencies a crash can also leave behind
+}
+
+#[derive(Deserialize)]
+pub struct AuditLogQuery {
+    pub after: Option<u64>, // Return entries with a larger sequence number
+    pub limit: Option<usize>,
+    pub key_id: Option<String>,
+    pub actor: Option<String>,
+    pub operation: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuditLogResponse {
+    pub success: bool,
+    pub message: String,
+    pub entries: Vec<AuditEntry>,
+    pub next_after: Option<u64>, // Pass as `after` to get the next page; None on the last page
+}
+
+// In the storage directory unless WAAS_AUDIT_LOG names another file
+fn audit_log_file() -> PathBuf {
+    match std::env::var(AUDIT_LOG_ENV) {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => crate::session_store::storage_dir().join(DEFAULT_AUDIT_LOG),
+    }
+}
+
+// Earlier versions kept the log in the working directory
+fn move_legacy_log() -> Result<()> {
+    let path = audit_log_file();
+    let legacy_path = PathBuf::from(DEFAULT_AUDIT_LOG);
+    if std::env::var(AUDIT_LOG_ENV).is_ok() || path.exists() || !legacy_path.is_file() {
+        return Ok(());
+    }
+    std::fs::rename(&legacy_path, &path)
+        .map_err(|e| anyhow::anyhow!("Failed to move {} to {}: {}", legacy_path.display(), path.display(), e))?;
+    tracing::info!(path = %path.display(), "🔄 Moved the audit log into the storage directory");
+    Ok(())
+}
+
+fn entry_hash(seq: u64, prev_hash: &str, event: &AuditEvent) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(seq.to_be_bytes());
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(serde_json::to_string(event)?.as_bytes());
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn checkpoint_data(head: &ChainHead) -> String {
+    format!("{}:{}", head.seq, head.hash)
+}
+
+fn read_checkpoint() -> Result<Option<Checkpoint>> {
+    read_service_json(ServiceRecord::AuditCheckpoint)
+}
+
+fn new_checkpoint(head: &ChainHead) -> Result<Checkpoint> {
+    Ok(Checkpoint {
+        head: head.clone(),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        mac: crate::master_key::mac(CHECKPOINT_MAC_CONTEXT, checkpoint_data(head).as_bytes())?,
+    })
+}
+
+fn write_checkpoint(head: &ChainHead) -> Result<()> {
+    write_service_json(ServiceRecord::AuditCheckpoint, &new_checkpoint(head)?)
+}
+
+// Non-empty lines of the log, or none if it doesn't exist yet
+fn read_lines() -> Result<Vec<String>> {
+    let file = match std::fs::File::open(audit_log_file()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(anyhow::anyhow!("Failed to open audit log: {}", e)),
+    };
+    let mut lines = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+// Last entry of the log as written, without checking the chain
+fn read_head() -> Result<Option<ChainHead>> {
+    match read_lines()?.last() {
+        Some(line) => {
+            let entry: AuditEntry = serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("Last audit log line is not a chained entry: {}", e))?;
+            Ok(Some(ChainHead { seq: entry.seq, hash: entry.hash }))
+        }
+        None => Ok(None),
+    }
+}
+
+fn append(event: &AuditEvent) -> Result<()> {
+    let mut head = AUDIT_LOCK.lock().unwrap();
+    if head.is_none() {
+        *head = read_head()?;
+    }
+    let (seq, prev_hash) = match head.as_ref() {
+        Some(head) => (head.seq + 1, head.hash.clone()),
+        None => (1, GENESIS_HASH.to_string()),
+    };
+    let entry = AuditEntry {
+        seq,
+        event: event.clone(),
+        hash: entry_hash(seq, &prev_hash, event)?,
+        prev_hash,
+    };
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_log_file())?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    file.sync_data()?;
+
+    let new_head = ChainHead { seq: entry.seq, hash: entry.hash };
+    write_checkpoint(&new_head)?;
+    *head = Some(new_head);
+    Ok(())
+}
+
+// Appends `event` to the audit log. A failed write is logged, never returned:
+// the operation being audited has already happened or been refused.
+pub fn record(event: AuditEvent) {
+    if let Err(e) = append(&event) {
+        tracing::error!(
+            error = %e,
+            actor = %event.actor,
+            operation = %event.operation,
+            outcome = %event.outcome,
+            "❌ Failed to write audit record"
+        );
+    }
+}
+
+pub fn verify_log() -> Result<VerifyReport> {
+    verify_entries(&read_lines()?, read_checkpoint()?.as_ref())
+}
+
+// Recomputes every hash of the chain and compares its end with the checkpoint
+fn verify_entries(lines: &[String], checkpoint: Option<&Checkpoint>) -> Result<VerifyReport> {
+    let mut report = VerifyReport {
+        entries: 0,
+        head: None,
+        issues: vec![],
+        warnings: vec![],
+    };
+    let mut expected_seq = 1;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut checkpoint_seen = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        let entry: AuditEntry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                report.issues.push(format!("line {}: not a chained audit entry ({})", index + 1, e));
+                continue;
+            }
+        };
+        report.entries += 1;
+
+        if entry.seq != expected_seq {
+            report.issues.push(format!(
+                "line {}: entry {} follows entry {}, entries are missing or out of order",
+                index + 1,
+                entry.seq,
+                expected_seq - 1
+            ));
+        } else if entry.prev_hash != prev_hash {
+            report.issues.push(format!("entry {}: does not chain to the entry before it", entry.seq));
+        }
+        if entry_hash(entry.seq, &entry.prev_hash, &entry.event)? != entry.hash {
+            report.issues.push(format!("entry {}: hash does not match its contents, entry was modified", entry.seq));
+        }
+        if let Some(checkpoint) = checkpoint
+            && checkpoint.head.seq == entry.seq
+        {
+            checkpoint_seen = true;
+            if checkpoint.head.hash != entry.hash {
+                report.issues.push(format!("entry {}: does not match the checkpoint", entry.seq));
+            }
+        }
+
+        // Carry on from this entry so each break is reported once
+        expected_seq = entry.seq + 1;
+        prev_hash = entry.hash.clone();
+        report.head = Some(ChainHead { seq: entry.seq, hash: entry.hash });
+    }
+
+    match checkpoint {
+        Some(checkpoint) => {
+            if !crate::master_key::verify_mac(
+                CHECKPOINT_MAC_CONTEXT,
+                checkpoint_data(&checkpoint.head).as_bytes(),
+                &checkpoint.mac,
+            )? {
+                report.issues.push("checkpoint MAC is invalid: checkpoint modified or a different master key".to_string());
+            }
+            let last_seq = report.head.as_ref().map_or(0, |head| head.seq);
+            if !checkpoint_seen {
+                report.issues.push(format!(
+                    "log truncated: checkpoint is at entry {}, but the log ends at entry {}",
+                    checkpoint.head.seq,
+                    last_seq
+                ));
+            } else if last_seq == checkpoint.head.seq + 1 {
+                // An append writes the entry, then the checkpoint
+                report.warnings.push(format!(
+                    "entry {} is past the checkpoint; the server stopped between writing it and the checkpoint",
+                    last_seq
+                ));
+            } else if last_seq > checkpoint.head.seq {
+                report.issues.push(format!(
+                    "{} entries after the checkpoint at entry {}; at most one can be left by a crash, entries were appended",
+                    last_seq - checkpoint.head.seq,
+                    checkpoint.head.seq
+                ));
+            }
+        }
+        None if report.entries > 0 => report.issues.push("checkpoint file is missing".to_string()),
+        None => {}
+    }
+    Ok(report)
+}
+
+// Whether the log was written before entries were hash-chained
+fn is_unchained(lines: &[String]) -> Result<bool> {
+    match lines.first() {
+        Some(line) => Ok(serde_json::from_str::<serde_json::Value>(line)?.get("hash").is_none()),
+        None => Ok(false),
+    }
+}
+
+// Chains the entries written before the log was hash-chained. Never done
+// implicitly: a log that lost its checkpoint must not be re-chained silently.
+fn chain_unchained_log() -> Result<usize> {
+    let lines = read_lines()?;
+    if read_checkpoint()?.is_some() {
+        anyhow::bail!("The audit log already has a checkpoint, it is chained");
+    }
+    let mut events = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        let value: serde_json::Value = serde_json::from_str(line)?;
+        if value.get("hash").is_some() {
+            anyhow::bail!("Line {} is already a chained entry; only logs written before chaining can be chained", index + 1);
+        }
+        events.push(serde_json::from_value::<AuditEvent>(value)?);
+    }
+
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut contents = String::new();
+    for (index, event) in events.into_iter().enumerate() {
+        let seq = index as u64 + 1;
+        let hash = entry_hash(seq, &prev_hash, &event)?;
+        let entry = AuditEntry { seq, event, prev_hash, hash: hash.clone() };
+        contents.push_str(&serde_json::to_string(&entry)?);
+        contents.push('\n');
+        prev_hash = hash;
+    }
+
+    write_file_atomic(&audit_log_file(), contents.as_bytes())?;
+    write_checkpoint(&ChainHead { seq: lines.len() as u64, hash: prev_hash })?;
+    tracing::warn!(entries = lines.len(), "⚠️ Chained audit entries written before the log was tamper-evident");
+    Ok(lines.len())
+}
+
+// Verifies the log at startup. The server refuses to start on a modified or
+// truncated log, since appending to it would overwrite the checkpoint.
+pub fn init_audit_log() -> Result<()> {
+    move_legacy_log()?;
+    if is_unchained(&read_lines()?)? && read_checkpoint()?.is_none() {
+        anyhow::bail!(
+            "Audit log {} was written before entries were chained; review it, then run `waas chain-audit-log` once",
+            audit_log_file().display()
+        );
+    }
+    let report = verify_log()?;
+    for warning in &report.warnings {
+        tracing::warn!(warning = %warning, "⚠️ Audit log warning");
+    }
+    if !report.issues.is_empty() {
+        for issue in &report.issues {
+            tracing::error!(issue = %issue, "❌ Audit log verification failed");
+        }
+        anyhow::bail!(
+            "Audit log {} failed verification ({} issues); run `waas verify-audit` for details",
+            audit_log_file().display(),
+            report.issues.len()
+        );
+    }
+    // Verification allows at most one entry past the checkpoint, left by a crash; adopt it
+    if let Some(head) = &report.head {
+        write_checkpoint(head)?;
+    }
+    tracing::info!(entries = report.entries, file = %audit_log_file().display(), "📒 Audit log verified");
+    *AUDIT_LOCK.lock().unwrap() = report.head;
+    Ok(())
+}
+
+// `waas verify-audit`: prints the verification result, exit status 1 on any issue
+pub fn run_verify_command() -> Result<bool> {
+    let report = verify_log()?;
+    println!("📒 Audit log: {}", audit_log_file().display());
+    println!("   Entries: {}", report.entries);
+    if let Some(head) = &report.head {
+        println!("   Last entry: {} ({})", head.seq, head.hash);
+    }
+    for warning in &report.warnings {
+        println!("⚠️ {}", warning);
+    }
+    for issue in &report.issues {
+        println!("❌ {}", issue);
+    }
+    if report.issues.is_empty() {
+        println!("✅ Audit log is intact");
+    }
+    Ok(report.issues.is_empty())
+}
+
+// `waas chain-audit-log`: chains a log written before entries were chained
+pub fn run_chain_command() -> Result<()> {
+    let entries = chain_unchained_log()?;
+    println!("📒 Audit log: {}", audit_log_file().display());
+    println!("✅ Chained {} entries", entries);
+    Ok(())
+}
+
+// Pages through the audit log in sequence order, optionally filtered (GET)
+pub async fn audit_log(Query(query): Query<AuditLogQuery>) -> (StatusCode, ResponseJson<AuditLogResponse>) {
+    let after = query.after.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let lines = {
+        // Don't read a line that is only half written
+        let _guard = AUDIT_LOCK.lock().unwrap();
+        read_lines()
+    };
+    let lines = match lines {
+        Ok(lines) => lines,
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to read audit log");
+            return (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(AuditLogResponse {
+                success: false,
+                message: format!("Failed to read audit log: {}", e),
+                entries: vec![],
+                next_after: None,
+            }));
+        }
+    };
+
+    let mut entries = Vec::new();
+    let mut more = false;
+    for line in &lines {
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(line) else {
+            continue;
+        };
+        if entry.seq <= after
+            || query.key_id.as_ref().is_some_and(|key_id| entry.event.key_id.as_ref() != Some(key_id))
+            || query.actor.as_ref().is_some_and(|actor| &entry.event.actor != actor)
+            || query.operation.as_ref().is_some_and(|operation| &entry.event.operation != operation)
+        {
+            continue;
+        }
+        if entries.len() == limit {
+            more = true;
+            break;
+        }
+        entries.push(entry);
+    }
+
+    let next_after = if more { entries.last().map(|entry| entry.seq) } else { None };
+    (StatusCode::OK, ResponseJson(AuditLogResponse {
+        success: true,
+        message: format!("{} audit entries", entries.len()),
+        entries,
+        next_after,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::master_key::init_test_master_key;
+
+    // Log lines of a chain of `count` entries, and the head after each entry
+    fn chain(count: u64) -> (Vec<String>, Vec<ChainHead>) {
+        let mut lines = vec![];
+        let mut heads = vec![];
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for seq in 1..=count {
+            let event = AuditEvent::by("alice", "sign", "success");
+            let hash = entry_hash(seq, &prev_hash, &event).unwrap();
+            let entry = AuditEntry { seq, event, prev_hash, hash: hash.clone() };
+            lines.push(serde_json::to_string(&entry).unwrap());
+            heads.push(ChainHead { seq, hash: hash.clone() });
+            prev_hash = hash;
+        }
+        (lines, heads)
+    }
+
+    #[test]
+    fn one_entry_past_the_checkpoint_is_a_warning() {
+        init_test_master_key();
+        let (lines, heads) = chain(4);
+        let checkpoint = new_checkpoint(&heads[2]).unwrap();
+
+        let report = verify_entries(&lines, Some(&checkpoint)).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.head.unwrap().seq, 4);
+    }
+
+    #[test]
+    fn more_entries_past_the_checkpoint_are_an_issue() {
+        init_test_master_key();
+        let (lines, heads) = chain(5);
+        let checkpoint = new_checkpoint(&heads[2]).unwrap();
+
+        let report = verify_entries(&lines, Some(&checkpoint)).unwrap();
+        assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+        assert!(report.issues[0].contains("2 entries after the checkpoint"));
+    }
+
+    #[test]
+    fn truncated_or_uncheckpointed_logs_are_an_issue() {
+        init_test_master_key();
+        let (lines, heads) = chain(3);
+
+        let checkpoint = new_checkpoint(&heads[2]).unwrap();
+        let report = verify_entries(&lines[..2], Some(&checkpoint)).unwrap();
+        assert!(report.issues.iter().any(|issue| issue.starts_with("log truncated")));
+
+        let report = verify_entries(&lines, None).unwrap();
+        assert_eq!(report.issues, vec!["checkpoint file is missing".to_string()]);
+        assert!(!is_unchained(&lines).unwrap());
+    }
+}
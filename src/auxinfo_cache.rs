@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::{extract::Json, http::StatusCode, response::Json as ResponseJson};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tss_ecdsa::{
+    auxinfo::AuxInfoParticipant,
+    curve::TestCurve,
+    ParticipantConfig, ParticipantIdentifier, ProtocolParticipant,
+};
+
+use crate::session_store::Record;
+
+// Serializes cache updates so concurrent sessions don't both generate auxinfo for one quorum
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+pub type AuxInfoOutputs = HashMap<ParticipantIdentifier, <AuxInfoParticipant<TestCurve> as ProtocolParticipant>::Output>;
+
+#[derive(Serialize, Deserialize, Default)]
+struct AuxInfoCache {
+    public_key: String, // Root key the outputs were generated for
+    epoch: u64,         // Bumped on every explicit refresh
+    quorums: BTreeMap<String, CachedAuxInfo>, // Keyed by the sorted participant IDs of the session
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedAuxInfo {
+    created_at: String,
+    outputs: AuxInfoOutputs,
+}
+
+#[derive(Deserialize)]
+pub struct AuxInfoRefreshRequest {
+    pub key_id: String,
+}
+
+#[derive(Serialize)]
+pub struct AuxInfoRefreshResponse {
+    pub success: bool,
+    pub message: String,
+    pub epoch: u64,
+}
+
+fn quorum_key(configs: &[ParticipantConfig]) -> String {
+    let mut ids: Vec<ParticipantIdentifier> = configs.iter().map(|config| config.id()).collect();
+    ids.sort();
+    ids.iter().map(|pid| pid.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn load_cache(key_id: &str) -> Result<AuxInfoCache> {
+    Ok(crate::encryption::read_encrypted(key_id, Record::AuxInfoCache)?.unwrap_or_default())
+}
+
+// Paillier private keys live in here, so the cache is encrypted like the key shares
+fn save_cache(key_id: &str, cache: &AuxInfoCache) -> Result<()> {
+    crate::encryption::write_encrypted(key_id, Record::AuxInfoCache, cache)
+}
+
+// Loads the cache, dropping it if it belongs to a different key
+fn load_cache_for_key(key_id: &str, public_key: &str) -> Result<AuxInfoCache> {
+    let cache = load_cache(key_id)?;
+    if cache.public_key == public_key {
+        return Ok(cache);
+    }
+    if !cache.quorums.is_empty() {
+        tracing::warn!(
+            stale_quorums = cache.quorums.len(),
+            "🗑️ Discarding auxinfo outputs generated for a previous key"
+        );
+    }
+    Ok(AuxInfoCache {
+        public_key: public_key.to_string(),
+        epoch: cache.epoch,
+        quorums: BTreeMap::new(),
+    })
+}
+
+// Stores auxinfo outputs produced elsewhere (keygen runs auxinfo for the full quorum)
+pub fn store_auxinfo(
+    key_id: &str,
+    public_key: &str,
+    configs: &[ParticipantConfig],
+    outputs: &AuxInfoOutputs,
+) -> Result<()> {
+    let _guard = CACHE_LOCK.lock().unwrap();
+    let mut cache = load_cache_for_key(key_id, public_key)?;
+    cache.quorums.insert(
+        quorum_key(configs),
+        CachedAuxInfo {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            outputs: outputs.clone(),
+        },
+    );
+    save_cache(key_id, &cache)?;
+
+    tracing::debug!(
+        key_id = %key_id,
+        participants = configs.len(),
+        epoch = cache.epoch,
+        "💾 Auxinfo outputs cached"
+    );
+    Ok(())
+}
+
+// Returns the auxinfo outputs for this quorum, running the auxinfo protocol only
+// if none are cached for the current key and refresh epoch
+pub fn load_or_generate_auxinfo(
+    key_id: &str,
+    public_key: &str,
+    configs: &[ParticipantConfig],
+) -> Result<AuxInfoOutputs> {
+    let _guard = CACHE_LOCK.lock().unwrap();
+    let mut cache = load_cache_for_key(key_id, public_key)?;
+    let key = quorum_key(configs);
+
+    if let Some(cached) = cache.quorums.get(&key) {
+        tracing::debug!(
+            participants = configs.len(),
+            epoch = cache.epoch,
+            created_at = %cached.created_at,
+            "♻️ Reusing cached auxinfo outputs"
+        );
+        return Ok(cached.outputs.clone());
+    }
+
+    tracing::debug!(
+        participants = configs.len(),
+        epoch = cache.epoch,
+        "🔧 No cached auxinfo for this quorum, generating"
+    );
+    let auxinfo_start = std::time::Instant::now();
+
+    // SECURITY: Paillier keys and ring-Pedersen parameters always come from fresh entropy
+    let auxinfo_result = crate::auxinfo::auxinfo_helper::<TestCurve>(configs.to_vec(), StdRng::from_entropy())?;
+
+    tracing::info!(
+        duration_ms = auxinfo_start.elapsed().as_millis(),
+        participants = configs.len(),
+        epoch = cache.epoch,
+        "✅ Auxiliary info generated and cached"
+    );
+
+    cache.quorums.insert(
+        key,
+        CachedAuxInfo {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            outputs: auxinfo_result.auxinfo_outputs.clone(),
+        },
+    );
+    save_cache(key_id, &cache)?;
+
+    Ok(auxinfo_result.auxinfo_outputs)
+}
+
+// Drops all cached auxinfo and starts a new epoch; outputs are regenerated on next use
+pub fn refresh_auxinfo_cache(key_id: &str) -> Result<u64> {
+    crate::wallets::load_wallet(key_id)?;
+    let _guard = CACHE_LOCK.lock().unwrap();
+    let mut cache = load_cache(key_id)?;
+    let dropped = cache.quorums.len();
+    cache.quorums.clear();
+    cache.epoch += 1;
+    save_cache(key_id, &cache)?;
+
+    tracing::info!(
+        key_id = %key_id,
+        epoch = cache.epoch,
+        dropped_quorums = dropped,
+        "🔄 Auxinfo cache refreshed"
+    );
+    Ok(cache.epoch)
+}
+
+// Explicit auxinfo rotation endpoint (POST)
+pub async fn refresh_auxinfo(
+    auth: crate::BasicAuth,
+    Json(request): Json<AuxInfoRefreshRequest>,
+) -> (StatusCode, ResponseJson<AuxInfoRefreshResponse>) {
+    let result = crate::wallets::authorize(&auth, &request.key_id)
+        .and_then(|_| refresh_auxinfo_cache(&request.key_id));
+    match result {
+        Ok(epoch) => (StatusCode::OK, ResponseJson(AuxInfoRefreshResponse {
+            success: true,
+            message: format!("Auxinfo outputs will be regenerated for epoch {}", epoch),
+            epoch,
+        })),
+        Err(e) => {
+            tracing::error!(key_id = %request.key_id, error = %e, "❌ Failed to refresh auxinfo cache");
+            let status = if crate::wallets::is_wallet_not_found(&e) {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::OK
+            };
+            (status, ResponseJson(AuxInfoRefreshResponse {
+                success: false,
+                message: format!("Failed to refresh auxinfo: {}", e),
+                epoch: 0,
+            }))
+        }
+    }
+}
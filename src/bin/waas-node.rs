@@ -0,0 +1,457 @@
+// Participant node: holds exactly one key share and runs its side of each protocol,
+// exchanging messages with the other nodes through the coordinator.
+//
+// Usage: waas-node --listen 127.0.0.1:9001 --data-dir node1 [--cert cert.pem --key key.pem]
+
+#[path = "../node_api.rs"]
+mod node_api;
+// Only the envelope and master key handling is used here, not the migration and MAC helpers
+#[path = "../master_key.rs"]
+#[allow(dead_code)]
+mod master_key;
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{request::Parts, StatusCode},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use node_api::{
+    DeliverMessageRequest, NodeStatusResponse, Phase, SessionOutput, SessionResponse, StartSessionRequest,
+    DELIVER_MESSAGE_PATH, NODE_STATUS_PATH, NODE_TOKEN_ENV, START_SESSION_PATH,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use subtle::ConstantTimeEq;
+use tss_ecdsa::{
+    auxinfo::AuxInfoParticipant,
+    curve::TestCurve,
+    keygen::KeygenParticipant,
+    messages::Message,
+    presign::{Input as PresignInput, PresignParticipant, PresignRecord},
+    sign::{Input as SignInput, SignParticipant},
+    Identifier, Participant, ParticipantConfig, ProtocolParticipant,
+};
+
+type KeygenOutput = <KeygenParticipant<TestCurve> as ProtocolParticipant>::Output;
+type AuxInfoOutput = <AuxInfoParticipant<TestCurve> as ProtocolParticipant>::Output;
+
+const CONFIG_FILE: &str = "participant_config.json";
+const KEYGEN_OUTPUT_FILE: &str = "keygen_output.json";
+const AUXINFO_OUTPUT_FILE: &str = "auxinfo_output.json";
+// Files holding secrets: the key share and the Paillier keys of the auxinfo output
+const SEALED_FILES: [&str; 2] = [KEYGEN_OUTPUT_FILE, AUXINFO_OUTPUT_FILE];
+
+enum NodeSession {
+    Keygen(Participant<KeygenParticipant<TestCurve>>),
+    AuxInfo(Participant<AuxInfoParticipant<TestCurve>>),
+    Presign(Participant<PresignParticipant<TestCurve>>),
+    Sign(Participant<SignParticipant<TestCurve>>),
+}
+
+struct NodeState {
+    data_dir: PathBuf,
+    sessions: HashMap<Identifier, NodeSession>,
+    // Sessions that already produced an output; late messages for them are dropped
+    finished_sessions: HashSet<Identifier>,
+    // Presign records waiting for their sign session, keyed by presign session id
+    presign_records: HashMap<Identifier, PresignRecord<TestCurve>>,
+    rng: StdRng,
+}
+
+type SharedState = Arc<Mutex<NodeState>>;
+
+impl NodeState {
+    fn path(&self, file: &str) -> PathBuf {
+        self.data_dir.join(file)
+    }
+
+    fn load<T: serde::de::DeserializeOwned>(&self, file: &str) -> Result<T> {
+        let json = std::fs::read_to_string(self.path(file))
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    fn store<T: serde::Serialize>(&self, file: &str, value: &T) -> Result<()> {
+        std::fs::write(self.path(file), serde_json::to_string(value)?)?;
+        Ok(())
+    }
+
+    // Secrets are written as envelopes under the node's master key
+    fn load_sealed<T: serde::de::DeserializeOwned>(&self, file: &str) -> Result<T> {
+        let envelope: master_key::EncryptedEnvelope = self.load(file)?;
+        if envelope.format != master_key::ENCRYPTED_FORMAT {
+            anyhow::bail!("{} is not encrypted", file);
+        }
+        master_key::open_json(&envelope, &sealed_context(file))
+    }
+
+    fn store_sealed<T: serde::Serialize>(&self, file: &str, value: &T) -> Result<()> {
+        self.store(file, &master_key::seal_json(value, &sealed_context(file))?)
+    }
+
+    fn start_session(&mut self, request: StartSessionRequest) -> Result<SessionResponse> {
+        if self.sessions.contains_key(&request.session_id) || self.finished_sessions.contains(&request.session_id) {
+            anyhow::bail!("Session {} already exists", request.session_id);
+        }
+
+        let session = match request.phase {
+            Phase::Keygen => {
+                let config = request
+                    .config
+                    .ok_or_else(|| anyhow::anyhow!("Keygen requires a participant config"))?;
+                // The identity assigned at keygen is the one this share belongs to from now on
+                self.store(CONFIG_FILE, &config)?;
+                NodeSession::Keygen(Participant::from_config(config, request.session_id, ())?)
+            }
+            Phase::AuxInfo => {
+                let config: ParticipantConfig = self.load(CONFIG_FILE)?;
+                NodeSession::AuxInfo(Participant::from_config(config, request.session_id, ())?)
+            }
+            Phase::Presign => {
+                let config: ParticipantConfig = self.load(CONFIG_FILE)?;
+                let keygen_output: KeygenOutput = self.load_sealed(KEYGEN_OUTPUT_FILE)?;
+                let auxinfo_output: AuxInfoOutput = self.load_sealed(AUXINFO_OUTPUT_FILE)?;
+                let input = PresignInput::new(auxinfo_output, keygen_output)?;
+                NodeSession::Presign(Participant::from_config(config, request.session_id, input)?)
+            }
+            Phase::Sign => {
+                let config: ParticipantConfig = self.load(CONFIG_FILE)?;
+                let keygen_output: KeygenOutput = self.load_sealed(KEYGEN_OUTPUT_FILE)?;
+                let message = hex::decode(
+                    request
+                        .message_hex
+                        .ok_or_else(|| anyhow::anyhow!("Sign requires a message"))?,
+                )?;
+                let presign_session_id = request
+                    .presign_session_id
+                    .ok_or_else(|| anyhow::anyhow!("Sign requires a presign session id"))?;
+                // Removed before use so a presign record can never sign twice
+                let record = self
+                    .presign_records
+                    .remove(&presign_session_id)
+                    .ok_or_else(|| anyhow::anyhow!("No presign record for session {}", presign_session_id))?;
+                let threshold = config.other_ids().len() + 1;
+                let input = SignInput::new(
+                    &message,
+                    record,
+                    keygen_output.public_key_shares().to_vec(),
+                    threshold,
+                    None,
+                );
+                NodeSession::Sign(Participant::from_config(config, request.session_id, input)?)
+            }
+        };
+
+        let initial_message = match &session {
+            NodeSession::Keygen(p) => p.initialize_message()?,
+            NodeSession::AuxInfo(p) => p.initialize_message()?,
+            NodeSession::Presign(p) => p.initialize_message()?,
+            NodeSession::Sign(p) => p.initialize_message()?,
+        };
+        self.sessions.insert(request.session_id, session);
+
+        tracing::info!(
+            session_id = %request.session_id,
+            phase = ?request.phase,
+            "🚀 Session started"
+        );
+
+        // The initialize message is addressed to ourselves; process it right away
+        self.deliver(request.session_id, initial_message)
+    }
+
+    fn deliver(&mut self, session_id: Identifier, message: Message) -> Result<SessionResponse> {
+        if self.finished_sessions.contains(&session_id) {
+            tracing::debug!(session_id = %session_id, "📭 Dropping message for finished session");
+            return Ok(SessionResponse {
+                success: true,
+                message: "Session already finished".to_string(),
+                outgoing: vec![],
+                output: None,
+            });
+        }
+
+        let session = self
+            .sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown session {}", session_id))?;
+
+        let rng = &mut self.rng;
+        let (output, outgoing) = match session {
+            NodeSession::Keygen(p) => {
+                let (output, outgoing) = p.process_single_message(&message, rng)?;
+                (output.map(Output::Keygen), outgoing)
+            }
+            NodeSession::AuxInfo(p) => {
+                let (output, outgoing) = p.process_single_message(&message, rng)?;
+                (output.map(Output::AuxInfo), outgoing)
+            }
+            NodeSession::Presign(p) => {
+                let (output, outgoing) = p.process_single_message(&message, rng)?;
+                (output.map(Output::Presign), outgoing)
+            }
+            NodeSession::Sign(p) => {
+                let (output, outgoing) = p.process_single_message(&message, rng)?;
+                (output.map(Output::Sign), outgoing)
+            }
+        };
+
+        let output = match output {
+            Some(output) => {
+                self.sessions.remove(&session_id);
+                self.finished_sessions.insert(session_id);
+                Some(self.finish_session(session_id, output)?)
+            }
+            None => None,
+        };
+
+        Ok(SessionResponse {
+            success: true,
+            message: "Message processed".to_string(),
+            outgoing,
+            output,
+        })
+    }
+
+    // Persists private results locally and returns only their public parts
+    fn finish_session(&mut self, session_id: Identifier, output: Output) -> Result<SessionOutput> {
+        let public_output = match output {
+            Output::Keygen(keygen_output) => {
+                self.store_sealed(KEYGEN_OUTPUT_FILE, &keygen_output)?;
+                // Auxinfo from a previous key is useless for the new share
+                let _ = std::fs::remove_file(self.path(AUXINFO_OUTPUT_FILE));
+                self.presign_records.clear();
+                SessionOutput::Keygen {
+                    public_key: hex::encode(keygen_output.public_key()?.to_sec1_bytes()),
+                    chain_code: hex::encode(keygen_output.chain_code()),
+                }
+            }
+            Output::AuxInfo(auxinfo_output) => {
+                self.store_sealed(AUXINFO_OUTPUT_FILE, &auxinfo_output)?;
+                SessionOutput::AuxInfo
+            }
+            Output::Presign(record) => {
+                self.presign_records.insert(session_id, record);
+                SessionOutput::Presign
+            }
+            Output::Sign(signature) => {
+                use std::ops::Deref;
+                SessionOutput::Sign {
+                    signature: hex::encode(signature.deref().to_der().as_bytes()),
+                }
+            }
+        };
+
+        tracing::info!(session_id = %session_id, "✅ Session completed");
+        Ok(public_output)
+    }
+
+    fn status(&self) -> NodeStatusResponse {
+        let participant_id = self
+            .load::<ParticipantConfig>(CONFIG_FILE)
+            .ok()
+            .map(|config| config.id());
+        NodeStatusResponse {
+            success: true,
+            participant_id,
+            has_key_share: self.path(KEYGEN_OUTPUT_FILE).exists(),
+            has_auxinfo: self.path(AUXINFO_OUTPUT_FILE).exists(),
+            presign_records: self.presign_records.len(),
+            active_sessions: self.sessions.len(),
+        }
+    }
+}
+
+enum Output {
+    Keygen(KeygenOutput),
+    AuxInfo(AuxInfoOutput),
+    Presign(PresignRecord<TestCurve>),
+    Sign(<SignParticipant<TestCurve> as ProtocolParticipant>::Output),
+}
+
+// Bearer token shared with the coordinator
+struct NodeAuth;
+
+impl<S> FromRequestParts<S> for NodeAuth
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let expected = std::env::var(NODE_TOKEN_ENV).unwrap_or_default();
+        let header = parts
+            .headers
+            .get("authorization")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("");
+
+        match header.strip_prefix("Bearer ") {
+            Some(token) if !expected.is_empty() && bool::from(token.as_bytes().ct_eq(expected.as_bytes())) => Ok(NodeAuth),
+            _ => Err((StatusCode::UNAUTHORIZED, "Invalid node token".into())),
+        }
+    }
+}
+
+fn sealed_context(file: &str) -> String {
+    format!("waas-node/{}", file)
+}
+
+// Encrypts secrets written in plaintext by earlier versions; only runs on a data
+// directory whose master key metadata predates encryption
+fn migrate_plaintext_files(data_dir: &std::path::Path) -> Result<()> {
+    if !master_key::migration_pending() {
+        return Ok(());
+    }
+    for file in SEALED_FILES {
+        let path = data_dir.join(file);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if master_key::is_encrypted(&json, master_key::ENCRYPTED_FORMAT) {
+            continue;
+        }
+        let envelope = master_key::seal_envelope(json.as_bytes(), &sealed_context(file))?;
+        std::fs::write(&path, serde_json::to_string(&envelope)?)?;
+        tracing::info!(file = file, "🔐 Encrypted plaintext node file");
+    }
+    master_key::complete_migration()
+}
+
+fn error_response(e: anyhow::Error) -> Json<SessionResponse> {
+    tracing::error!(error = %e, "❌ Session step failed");
+    Json(SessionResponse {
+        success: false,
+        message: e.to_string(),
+        outgoing: vec![],
+        output: None,
+    })
+}
+
+async fn start_session(
+    _auth: NodeAuth,
+    State(state): State<SharedState>,
+    Json(request): Json<StartSessionRequest>,
+) -> Json<SessionResponse> {
+    // Protocol steps are CPU bound (auxinfo generates Paillier keys), keep them off the async workers
+    let result = tokio::task::spawn_blocking(move || state.lock().unwrap().start_session(request)).await;
+    match result {
+        Ok(Ok(response)) => Json(response),
+        Ok(Err(e)) => error_response(e),
+        Err(e) => error_response(e.into()),
+    }
+}
+
+async fn deliver_message(
+    _auth: NodeAuth,
+    State(state): State<SharedState>,
+    Json(request): Json<DeliverMessageRequest>,
+) -> Json<SessionResponse> {
+    let result = tokio::task::spawn_blocking(move || {
+        state.lock().unwrap().deliver(request.session_id, request.message)
+    })
+    .await;
+    match result {
+        Ok(Ok(response)) => Json(response),
+        Ok(Err(e)) => error_response(e),
+        Err(e) => error_response(e.into()),
+    }
+}
+
+async fn node_status(_auth: NodeAuth, State(state): State<SharedState>) -> Json<NodeStatusResponse> {
+    Json(state.lock().unwrap().status())
+}
+
+struct NodeArgs {
+    listen: SocketAddr,
+    data_dir: PathBuf,
+    cert: String,
+    key: String,
+}
+
+fn parse_args() -> Result<NodeArgs> {
+    let mut listen = "127.0.0.1:9001".to_string();
+    let mut data_dir = "node_data".to_string();
+    let mut cert = "cert.pem".to_string();
+    let mut key = "key.pem".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--listen" => listen = value,
+            "--data-dir" => data_dir = value,
+            "--cert" => cert = value,
+            "--key" => key = value,
+            _ => anyhow::bail!("Unknown argument: {}", arg),
+        }
+    }
+
+    Ok(NodeArgs {
+        listen: listen.parse()?,
+        data_dir: PathBuf::from(data_dir),
+        cert,
+        key,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
+    let args = parse_args()?;
+    if std::env::var(NODE_TOKEN_ENV).unwrap_or_default().is_empty() {
+        anyhow::bail!("{} must be set to the token shared with the coordinator", NODE_TOKEN_ENV);
+    }
+    std::fs::create_dir_all(&args.data_dir)?;
+    // The share never touches disk in the clear; master_key.json sits in the data directory
+    master_key::init_master_key(&args.data_dir)?;
+    migrate_plaintext_files(&args.data_dir)?;
+
+    let state: SharedState = Arc::new(Mutex::new(NodeState {
+        data_dir: args.data_dir.clone(),
+        sessions: HashMap::new(),
+        finished_sessions: HashSet::new(),
+        presign_records: HashMap::new(),
+        rng: StdRng::from_entropy(),
+    }));
+
+    let app = Router::new()
+        .route(START_SESSION_PATH, post(start_session))
+        .route(DELIVER_MESSAGE_PATH, post(deliver_message))
+        .route(NODE_STATUS_PATH, get(node_status))
+        .with_state(state);
+
+    // axum-server and reqwest enable different rustls crypto providers, so pick one explicitly
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .map_err(|_| anyhow::anyhow!("Failed to install the TLS crypto provider"))?;
+    let tls = RustlsConfig::from_pem_file(&args.cert, &args.key).await?;
+
+    tracing::info!(
+        address = %args.listen,
+        data_dir = %args.data_dir.display(),
+        "🌐 Participant node listening"
+    );
+
+    axum_server::bind_rustls(args.listen, tls)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,1136 @@
+// Bitcoin PSBT signing (BIP174): parses a version 0 PSBT, computes the BIP143 segwit
+// v0 or legacy sighash of every input a wallet key can sign, signs those digests with
+// the TSS protocol and inserts the partial signatures.
+
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, bail, Result};
+use axum::{extract::Json, http::StatusCode, response::Json as ResponseJson};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+const SIGHASH_ALL: u32 = 0x01;
+const DEFAULT_NETWORK: &str = "mainnet";
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+// Cursor over consensus-encoded bytes
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        if self.data.len() < length {
+            bail!("Unexpected end of data");
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn compact_size(&mut self) -> Result<u64> {
+        Ok(match self.byte()? {
+            0xfd => u16::from_le_bytes(self.array()?) as u64,
+            0xfe => self.u32()? as u64,
+            0xff => self.u64()?,
+            size => size as u64,
+        })
+    }
+
+    fn var_bytes(&mut self) -> Result<&'a [u8]> {
+        let length = usize::try_from(self.compact_size()?)?;
+        self.take(length)
+    }
+}
+
+fn write_compact_size(out: &mut Vec<u8>, size: u64) {
+    match size {
+        0..=0xfc => out.push(size as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(size as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&size.to_le_bytes());
+        }
+    }
+}
+
+fn write_var_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TxIn {
+    prev_txid: [u8; 32], // Internal byte order, the reverse of the displayed txid
+    prev_vout: u32,
+    script_sig: Vec<u8>,
+    sequence: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TxOut {
+    value: u64, // In satoshis
+    script_pubkey: Vec<u8>,
+}
+
+impl TxOut {
+    fn read(reader: &mut Reader) -> Result<Self> {
+        Ok(Self {
+            value: reader.u64()?,
+            script_pubkey: reader.var_bytes()?.to_vec(),
+        })
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let output = Self::read(&mut reader)?;
+        if !reader.is_empty() {
+            bail!("Trailing data after the transaction output");
+        }
+        Ok(output)
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value.to_le_bytes());
+        write_var_bytes(out, &self.script_pubkey);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transaction {
+    version: u32,
+    inputs: Vec<TxIn>,
+    outputs: Vec<TxOut>,
+    lock_time: u32,
+}
+
+impl Transaction {
+    // Parses a transaction in either serialization and tells whether it carried
+    // witnesses. The witnesses themselves are dropped.
+    fn parse(data: &[u8]) -> Result<(Self, bool)> {
+        let mut reader = Reader::new(data);
+        let version = reader.u32()?;
+        let mut input_count = reader.compact_size()?;
+        // BIP144: a zero input count followed by flag 0x01 marks the witness serialization
+        let has_witness = input_count == 0 && reader.data.first() == Some(&0x01);
+        if has_witness {
+            reader.byte()?;
+            input_count = reader.compact_size()?;
+        }
+
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            inputs.push(TxIn {
+                prev_txid: reader.array()?,
+                prev_vout: reader.u32()?,
+                script_sig: reader.var_bytes()?.to_vec(),
+                sequence: reader.u32()?,
+            });
+        }
+        let mut outputs = Vec::new();
+        for _ in 0..reader.compact_size()? {
+            outputs.push(TxOut::read(&mut reader)?);
+        }
+        if has_witness {
+            for _ in 0..inputs.len() {
+                for _ in 0..reader.compact_size()? {
+                    reader.var_bytes()?;
+                }
+            }
+        }
+        let lock_time = reader.u32()?;
+        if !reader.is_empty() {
+            bail!("Trailing data after the transaction");
+        }
+
+        Ok((Self { version, inputs, outputs, lock_time }, has_witness))
+    }
+
+    // Serialization without witnesses, which the txid and the sighashes commit to
+    fn encode(&self) -> Vec<u8> {
+        let mut out = self.version.to_le_bytes().to_vec();
+        write_compact_size(&mut out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            out.extend_from_slice(&input.prev_txid);
+            out.extend_from_slice(&input.prev_vout.to_le_bytes());
+            write_var_bytes(&mut out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            output.write(&mut out);
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+
+    fn txid(&self) -> [u8; 32] {
+        sha256d(&self.encode())
+    }
+
+    // Txid as block explorers and RPCs display it
+    fn display_txid(&self) -> String {
+        let mut txid = self.txid();
+        txid.reverse();
+        hex::encode(txid)
+    }
+
+    // BIP143 SIGHASH_ALL digest of input `index`, which spends `value` satoshis
+    fn segwit_v0_sighash(&self, index: usize, script_code: &[u8], value: u64) -> [u8; 32] {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in &self.inputs {
+            prevouts.extend_from_slice(&input.prev_txid);
+            prevouts.extend_from_slice(&input.prev_vout.to_le_bytes());
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        let mut outputs = Vec::new();
+        for output in &self.outputs {
+            output.write(&mut outputs);
+        }
+
+        let input = &self.inputs[index];
+        let mut preimage = self.version.to_le_bytes().to_vec();
+        preimage.extend_from_slice(&sha256d(&prevouts));
+        preimage.extend_from_slice(&sha256d(&sequences));
+        preimage.extend_from_slice(&input.prev_txid);
+        preimage.extend_from_slice(&input.prev_vout.to_le_bytes());
+        write_var_bytes(&mut preimage, script_code);
+        preimage.extend_from_slice(&value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&sha256d(&outputs));
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&SIGHASH_ALL.to_le_bytes());
+        sha256d(&preimage)
+    }
+
+    // Pre-segwit SIGHASH_ALL digest of input `index`: the transaction with that
+    // input's scriptSig replaced by `script_code` and every other one emptied
+    fn legacy_sighash(&self, index: usize, script_code: &[u8]) -> [u8; 32] {
+        let mut transaction = self.clone();
+        for (i, input) in transaction.inputs.iter_mut().enumerate() {
+            input.script_sig = if i == index { script_code.to_vec() } else { Vec::new() };
+        }
+        let mut preimage = transaction.encode();
+        preimage.extend_from_slice(&SIGHASH_ALL.to_le_bytes());
+        sha256d(&preimage)
+    }
+}
+
+// Key-value map of a PSBT section. Keys start with their type byte.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct PsbtMap {
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl PsbtMap {
+    fn read(reader: &mut Reader) -> Result<Self> {
+        let mut map = Self::default();
+        loop {
+            let key = reader.var_bytes()?;
+            if key.is_empty() {
+                return Ok(map);
+            }
+            if map.get(key).is_some() {
+                bail!("Duplicate PSBT key {}", hex::encode(key));
+            }
+            let value = reader.var_bytes()?;
+            map.entries.push((key.to_vec(), value.to_vec()));
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        for (key, value) in &self.entries {
+            write_var_bytes(out, key);
+            write_var_bytes(out, value);
+        }
+        out.push(0x00);
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, value)| value.as_slice())
+    }
+
+    // Key data (the key after its type byte) and value of every entry of `key_type`
+    fn entries_of(&self, key_type: u8) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key[0] == key_type)
+            .map(|(key, value)| (&key[1..], value.as_slice()))
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+}
+
+// Version 0 PSBT. Entries this module does not interpret are kept as they are.
+#[derive(Debug, PartialEq, Eq)]
+struct Psbt {
+    global: PsbtMap,
+    unsigned_tx: Transaction,
+    inputs: Vec<PsbtMap>,
+    outputs: Vec<PsbtMap>,
+}
+
+impl Psbt {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let data = data.strip_prefix(PSBT_MAGIC).ok_or_else(|| anyhow!("Missing PSBT magic bytes"))?;
+        let mut reader = Reader::new(data);
+        let global = PsbtMap::read(&mut reader)?;
+
+        if let Some(version) = global.get(&[PSBT_GLOBAL_VERSION]) {
+            let version = <[u8; 4]>::try_from(version).map_err(|_| anyhow!("Invalid PSBT version"))?;
+            if u32::from_le_bytes(version) != 0 {
+                bail!("PSBT version {} is not supported", u32::from_le_bytes(version));
+            }
+        }
+        let unsigned_tx = global
+            .get(&[PSBT_GLOBAL_UNSIGNED_TX])
+            .ok_or_else(|| anyhow!("PSBT has no unsigned transaction"))?;
+        let (unsigned_tx, has_witness) = Transaction::parse(unsigned_tx)?;
+        if has_witness || unsigned_tx.inputs.iter().any(|input| !input.script_sig.is_empty()) {
+            bail!("The unsigned transaction carries scriptSigs or witnesses");
+        }
+
+        let inputs = (0..unsigned_tx.inputs.len())
+            .map(|_| PsbtMap::read(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = (0..unsigned_tx.outputs.len())
+            .map(|_| PsbtMap::read(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        if !reader.is_empty() {
+            bail!("Trailing data after the PSBT");
+        }
+
+        Ok(Self { global, unsigned_tx, inputs, outputs })
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut out = PSBT_MAGIC.to_vec();
+        self.global.write(&mut out);
+        for map in self.inputs.iter().chain(&self.outputs) {
+            map.write(&mut out);
+        }
+        out
+    }
+
+    // Partial signatures are DER followed by the sighash type byte
+    fn add_partial_signature(&mut self, index: usize, public_key: &[u8], signature: &k256::ecdsa::Signature) {
+        let mut value = signature.to_der().as_bytes().to_vec();
+        value.push(SIGHASH_ALL as u8);
+        self.inputs[index].insert([&[PSBT_IN_PARTIAL_SIG][..], public_key].concat(), value);
+    }
+}
+
+// Output scripts whose inputs a single wallet key signs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScriptType {
+    P2wpkh([u8; 20]),
+    P2pkh([u8; 20]),
+}
+
+impl ScriptType {
+    // The error names the script type when it is not signable
+    fn detect(script_pubkey: &[u8]) -> std::result::Result<Self, &'static str> {
+        if let [0x00, 0x14, hash @ ..] = script_pubkey
+            && let Ok(hash) = <[u8; 20]>::try_from(hash)
+        {
+            return Ok(ScriptType::P2wpkh(hash));
+        }
+        if let [0x76, 0xa9, 0x14, hash @ .., 0x88, 0xac] = script_pubkey
+            && let Ok(hash) = <[u8; 20]>::try_from(hash)
+        {
+            return Ok(ScriptType::P2pkh(hash));
+        }
+        Err(match script_pubkey {
+            [0xa9, 0x14, .., 0x87] if script_pubkey.len() == 23 => "p2sh",
+            [0x00, 0x20, ..] if script_pubkey.len() == 34 => "p2wsh",
+            [0x51, 0x20, ..] if script_pubkey.len() == 34 => "p2tr",
+            _ => "nonstandard",
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ScriptType::P2wpkh(_) => "p2wpkh",
+            ScriptType::P2pkh(_) => "p2pkh",
+        }
+    }
+
+    fn key_hash(self) -> [u8; 20] {
+        match self {
+            ScriptType::P2wpkh(hash) | ScriptType::P2pkh(hash) => hash,
+        }
+    }
+}
+
+// OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG, which is also the BIP143
+// scriptCode of a P2WPKH input
+fn p2pkh_script(key_hash: &[u8; 20]) -> Vec<u8> {
+    [&[0x76, 0xa9, 0x14][..], key_hash, &[0x88, 0xac]].concat()
+}
+
+// An input a wallet key can sign
+#[derive(Debug)]
+struct InputPlan {
+    index: usize,
+    path: String,
+    public_key: Vec<u8>, // As listed in the input's bip32_derivation
+    script_type: ScriptType,
+    sighash: [u8; 32],
+}
+
+// Works out how to sign input `index` with one of `wallet_keys` (path and key). The
+// error is the reason the input cannot be signed.
+fn plan_input(psbt: &Psbt, index: usize, wallet_keys: &[(String, k256::PublicKey)]) -> Result<InputPlan> {
+    let input = &psbt.inputs[index];
+    let txin = &psbt.unsigned_tx.inputs[index];
+
+    if input.get(&[PSBT_IN_FINAL_SCRIPTSIG]).is_some() || input.get(&[PSBT_IN_FINAL_SCRIPTWITNESS]).is_some() {
+        bail!("Input is already finalized");
+    }
+    if let Some(sighash_type) = input.get(&[PSBT_IN_SIGHASH_TYPE]) {
+        let sighash_type = <[u8; 4]>::try_from(sighash_type)
+            .map(u32::from_le_bytes)
+            .map_err(|_| anyhow!("Invalid sighash type"))?;
+        if sighash_type != SIGHASH_ALL {
+            bail!("Sighash type {:#x} is not supported, only SIGHASH_ALL", sighash_type);
+        }
+    }
+
+    // The output this input spends
+    let non_witness_utxo = input
+        .get(&[PSBT_IN_NON_WITNESS_UTXO])
+        .map(|bytes| -> Result<TxOut> {
+            let (previous, _) = Transaction::parse(bytes)?;
+            if previous.txid() != txin.prev_txid {
+                bail!("non_witness_utxo is not the transaction this input spends");
+            }
+            previous
+                .outputs
+                .get(txin.prev_vout as usize)
+                .cloned()
+                .ok_or_else(|| anyhow!("non_witness_utxo has no output {}", txin.prev_vout))
+        })
+        .transpose()?;
+    let witness_utxo = input.get(&[PSBT_IN_WITNESS_UTXO]).map(TxOut::parse).transpose()?;
+    let has_non_witness_utxo = non_witness_utxo.is_some();
+    let spent = match (non_witness_utxo, witness_utxo) {
+        (Some(full), Some(witness)) if full != witness => bail!("witness_utxo and non_witness_utxo disagree"),
+        (Some(utxo), _) | (None, Some(utxo)) => utxo,
+        (None, None) => bail!("Input has no witness_utxo or non_witness_utxo"),
+    };
+
+    let script_type = ScriptType::detect(&spent.script_pubkey)
+        .map_err(|name| anyhow!("Spending {} outputs is not supported", name))?;
+    // The legacy sighash does not commit to the spent value, so BIP174 requires the
+    // whole previous transaction
+    if matches!(script_type, ScriptType::P2pkh(_)) && !has_non_witness_utxo {
+        bail!("P2PKH inputs need a non_witness_utxo");
+    }
+
+    let owned: Vec<(&String, &[u8])> = input
+        .entries_of(PSBT_IN_BIP32_DERIVATION)
+        .filter_map(|(public_key, _)| {
+            let point = k256::PublicKey::from_sec1_bytes(public_key).ok()?;
+            let (path, _) = wallet_keys.iter().find(|(_, wallet_key)| *wallet_key == point)?;
+            Some((path, public_key))
+        })
+        .collect();
+    if owned.is_empty() {
+        bail!("No bip32_derivation key of this input belongs to the wallet");
+    }
+    let (path, public_key) = owned
+        .into_iter()
+        .find(|(_, public_key)| crate::addresses::hash160(public_key) == script_type.key_hash())
+        .ok_or_else(|| anyhow!("The wallet key of this input does not match the {} script it spends", script_type.name()))?;
+    if matches!(script_type, ScriptType::P2wpkh(_)) && public_key.len() != 33 {
+        bail!("P2WPKH inputs need a compressed public key");
+    }
+    if input.get(&[&[PSBT_IN_PARTIAL_SIG][..], public_key].concat()).is_some() {
+        bail!("Input already has a signature from key {}", path);
+    }
+
+    let script_code = p2pkh_script(&script_type.key_hash());
+    let sighash = match script_type {
+        ScriptType::P2wpkh(_) => psbt.unsigned_tx.segwit_v0_sighash(index, &script_code, spent.value),
+        ScriptType::P2pkh(_) => psbt.unsigned_tx.legacy_sighash(index, &script_code),
+    };
+
+    Ok(InputPlan {
+        index,
+        path: path.clone(),
+        public_key: public_key.to_vec(),
+        script_type,
+        sighash,
+    })
+}
+
+// Public keys of every key recorded for the wallet, by path
+fn wallet_keys(key_id: &str) -> Result<Vec<(String, k256::PublicKey)>> {
+    let store = crate::hd_keys::load_hd_key_store(key_id)?;
+    Ok(store
+        .list_all_keys()
+        .into_iter()
+        .filter_map(|key| {
+            let public_key = hex::decode(&key.public_key_hex).ok()?;
+            Some((key.path.clone(), k256::PublicKey::from_sec1_bytes(&public_key).ok()?))
+        })
+        .collect())
+}
+
+// Addresses and total value of the outputs that do not pay back to the wallet.
+// Outputs without an address, such as OP_RETURN data, only count towards the value.
+fn external_outputs(
+    transaction: &Transaction,
+    wallet_keys: &[(String, k256::PublicKey)],
+    network: &str,
+) -> Result<(Vec<String>, u128)> {
+    let wallet_hashes: BTreeSet<[u8; 20]> = wallet_keys
+        .iter()
+        .map(|(_, public_key)| crate::addresses::hash160(&public_key.to_sec1_bytes()))
+        .collect();
+
+    let mut destinations = Vec::new();
+    let mut value = 0u128;
+    for output in &transaction.outputs {
+        let address = crate::addresses::bitcoin_script_address(&output.script_pubkey, network)?;
+        if ScriptType::detect(&output.script_pubkey).is_ok_and(|script_type| wallet_hashes.contains(&script_type.key_hash())) {
+            continue;
+        }
+        value += output.value as u128;
+        destinations.extend(address);
+    }
+    Ok((destinations, value))
+}
+
+// Policies see one destination per request. Every destination but the last is
+// dry-run, so that only the last check reserves a rate limit slot.
+fn check_psbt_policy(
+    auth: &crate::BasicAuth,
+    key_id: &str,
+    path: &str,
+    destinations: &[String],
+    value: &str,
+    message: &[u8],
+) -> std::result::Result<crate::policy::Evaluation, String> {
+    let context = |destination| crate::policy::SigningContext {
+        key_id,
+        path,
+        format: crate::policy::MessageFormat::Hex,
+        destination,
+        value: Some(value),
+    };
+    let Some((last, others)) = destinations.split_last() else {
+        return crate::sign::check_policy(auth, &context(None), message);
+    };
+    for destination in others {
+        let context = context(Some(destination.as_str()));
+        if !crate::policy::dry_run(&context).is_ok_and(|evaluation| evaluation.allowed) {
+            // Reports and audits the refusal
+            crate::sign::check_policy(auth, &context, message)?;
+        }
+    }
+    crate::sign::check_policy(auth, &context(Some(last.as_str())), message)
+}
+
+// check_psbt_policy again for an approved signing request, which now counts against the rate limit
+fn enforce_approved_psbt_policy(key_id: &str, path: &str, destinations: &[String], value: &str) -> Result<()> {
+    let context = |destination| crate::policy::SigningContext {
+        key_id,
+        path,
+        format: crate::policy::MessageFormat::Hex,
+        destination,
+        value: Some(value),
+    };
+    let mut evaluations = Vec::new();
+    if let Some((last, others)) = destinations.split_last() {
+        for destination in others {
+            evaluations.push(crate::policy::dry_run(&context(Some(destination.as_str())))?);
+        }
+        evaluations.push(crate::policy::enforce_approved(&context(Some(last.as_str())))?);
+    } else {
+        evaluations.push(crate::policy::enforce_approved(&context(None))?);
+    }
+    if let Some(refused) = evaluations.iter().find(|evaluation| !evaluation.allowed) {
+        bail!("Signing refused by policy: {}", refused.violations.join("; "));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SignPsbtRequest {
+    pub key_id: String,
+    pub psbt: String,            // Base64 BIP174 PSBT
+    pub network: Option<String>, // mainnet (default), testnet or regtest; names output addresses for policies
+    pub participants: Option<Vec<String>>, // Optional signer subset; if None, t random signers are chosen
+}
+
+#[derive(Serialize)]
+pub struct PsbtInputResult {
+    pub index: usize,
+    pub signed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub signers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>, // Why the input was not signed
+}
+
+impl PsbtInputResult {
+    fn rejected(index: usize, reason: String) -> Self {
+        Self {
+            index,
+            signed: false,
+            path: None,
+            public_key: None,
+            script_type: None,
+            signers: vec![],
+            reason: Some(reason),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SignPsbtResponse {
+    pub success: bool,
+    pub message: String,
+    pub psbt: String, // Base64, with the new partial signatures
+    pub txid: String,
+    pub signed_inputs: usize,
+    pub inputs: Vec<PsbtInputResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // Set when the PSBT waits for approvals
+}
+
+impl SignPsbtResponse {
+    fn failure(message: String) -> Self {
+        Self {
+            success: false,
+            message,
+            psbt: String::new(),
+            txid: String::new(),
+            signed_inputs: 0,
+            inputs: vec![],
+            request_id: None,
+        }
+    }
+}
+
+// Signs every input of a PSBT that a key of the wallet can sign (POST)
+pub async fn sign_psbt(
+    auth: crate::BasicAuth,
+    Json(request): Json<SignPsbtRequest>,
+) -> (StatusCode, ResponseJson<SignPsbtResponse>) {
+    let network = request.network.as_deref().unwrap_or(DEFAULT_NETWORK);
+    tracing::info!(key_id = %request.key_id, network = %network, "🪙 Starting PSBT signing");
+    let start_time = std::time::Instant::now();
+
+    if let Err(e) = crate::wallets::authorize(&auth, &request.key_id) {
+        tracing::error!(key_id = %request.key_id, error = %e, "❌ PSBT signing refused");
+        let status = if crate::wallets::is_wallet_not_found(&e) {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        return (status, ResponseJson(SignPsbtResponse::failure(format!("Signing failed: {}", e))));
+    }
+    let wallet_keys = match wallet_keys(&request.key_id) {
+        Ok(wallet_keys) => wallet_keys,
+        Err(e) => {
+            tracing::error!(key_id = %request.key_id, error = %e, "❌ Failed to load wallet keys");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(SignPsbtResponse::failure(format!("Signing failed: {}", e))),
+            );
+        }
+    };
+
+    let parsed = general_purpose::STANDARD
+        .decode(request.psbt.trim())
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| Psbt::parse(&bytes));
+    let mut psbt = match parsed {
+        Ok(psbt) => psbt,
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, error = %e, "⚠️ Invalid PSBT");
+            return (StatusCode::BAD_REQUEST, ResponseJson(SignPsbtResponse::failure(format!("Invalid PSBT: {}", e))));
+        }
+    };
+    let txid = psbt.unsigned_tx.display_txid();
+    let unsigned_tx = psbt.unsigned_tx.encode();
+
+    let (destinations, value) = match external_outputs(&psbt.unsigned_tx, &wallet_keys, network) {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, error = %e, "⚠️ Invalid PSBT signing request");
+            return (StatusCode::BAD_REQUEST, ResponseJson(SignPsbtResponse::failure(format!("Invalid request: {}", e))));
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut plans = Vec::new();
+    for index in 0..psbt.inputs.len() {
+        match plan_input(&psbt, index, &wallet_keys) {
+            Ok(plan) => plans.push(plan),
+            Err(e) => results.push(PsbtInputResult::rejected(index, e.to_string())),
+        }
+    }
+    if plans.is_empty() {
+        tracing::warn!(key_id = %request.key_id, txid = %txid, "⚠️ No PSBT input can be signed by this wallet");
+        return (StatusCode::BAD_REQUEST, ResponseJson(SignPsbtResponse {
+            inputs: results,
+            txid,
+            ..SignPsbtResponse::failure("No input can be signed by this wallet".to_string())
+        }));
+    }
+
+    // One policy decision per signing key, all taken before anything is signed.
+    // The key whose policy asks for the most approvals decides them for the whole PSBT.
+    let value = value.to_string();
+    let paths: BTreeSet<&str> = plans.iter().map(|plan| plan.path.as_str()).collect();
+    let mut strictest: Option<(&str, crate::policy::Evaluation)> = None;
+    for path in paths {
+        let evaluation = match check_psbt_policy(&auth, &request.key_id, path, &destinations, &value, &unsigned_tx) {
+            Ok(evaluation) => evaluation,
+            Err(refusal) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    ResponseJson(SignPsbtResponse::failure(format!("Signing refused: {}", refusal))),
+                );
+            }
+        };
+        if evaluation.required_approvals > strictest.as_ref().map_or(0, |(_, strictest)| strictest.required_approvals) {
+            strictest = Some((path, evaluation));
+        }
+    }
+    if let Some((path, evaluation)) = strictest {
+        let pending = crate::approvals::SigningRequest {
+            message: format!("0x{}", hex::encode(&unsigned_tx)),
+            transaction: Some(crate::approvals::PendingTransaction::Psbt {
+                psbt: request.psbt.trim().to_string(),
+                network: network.to_string(),
+            }),
+            participants: request.participants.clone(),
+            destination: (!destinations.is_empty()).then(|| destinations.join(", ")),
+            value: Some(value.clone()),
+            ..crate::approvals::SigningRequest::new(&auth, &request.key_id, path, &evaluation)
+        };
+        return match crate::approvals::submit_request(&auth, pending) {
+            Ok(pending) => (StatusCode::ACCEPTED, ResponseJson(SignPsbtResponse {
+                success: true,
+                txid,
+                inputs: results,
+                request_id: Some(pending.id.clone()),
+                ..SignPsbtResponse::failure(format!(
+                    "Signing request {} needs {} approvals before {}",
+                    pending.id, pending.required_approvals, pending.expires_at
+                ))
+            })),
+            Err(e) => {
+                tracing::error!(key_id = %request.key_id, error = %e, "❌ Failed to store signing request");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ResponseJson(SignPsbtResponse::failure(format!("Failed to store signing request: {}", e))),
+                )
+            }
+        };
+    }
+
+    for plan in plans {
+        let target = crate::sign::SignTarget::Digest(&plan.sighash);
+        let signed = crate::sign::run_tss_sign(&request.key_id, target, &plan.path, request.participants.as_deref()).await;
+        let detail = format!("{} input {} of transaction {}", plan.script_type.name(), plan.index, txid);
+        let result = match signed {
+            Ok(signed) => {
+                psbt.add_partial_signature(plan.index, &plan.public_key, &signed.signature);
+                tracing::info!(
+                    key_id = %request.key_id,
+                    path = %plan.path,
+                    txid = %txid,
+                    input = plan.index,
+                    signers = ?signed.signers,
+                    "✅ PSBT input signed"
+                );
+                crate::audit::record(
+                    crate::audit::AuditEvent::new(&auth, "sign_psbt", "success")
+                        .key_id(&request.key_id)
+                        .path(&plan.path)
+                        .message(&unsigned_tx)
+                        .detail(detail),
+                );
+                PsbtInputResult {
+                    index: plan.index,
+                    signed: true,
+                    path: Some(plan.path),
+                    public_key: Some(hex::encode(&plan.public_key)),
+                    script_type: Some(plan.script_type.name()),
+                    signers: signed.signers,
+                    reason: None,
+                }
+            }
+            Err(e) => {
+                tracing::error!(key_id = %request.key_id, txid = %txid, input = plan.index, error = %e, "❌ PSBT input signing failed");
+                crate::audit::record(
+                    crate::audit::AuditEvent::new(&auth, "sign_psbt", "failed")
+                        .key_id(&request.key_id)
+                        .path(&plan.path)
+                        .message(&unsigned_tx)
+                        .detail(format!("{}: {}", detail, e)),
+                );
+                PsbtInputResult {
+                    path: Some(plan.path),
+                    public_key: Some(hex::encode(&plan.public_key)),
+                    script_type: Some(plan.script_type.name()),
+                    ..PsbtInputResult::rejected(plan.index, format!("Signing failed: {}", e))
+                }
+            }
+        };
+        results.push(result);
+    }
+    results.sort_by_key(|result| result.index);
+
+    let signed_inputs = results.iter().filter(|result| result.signed).count();
+    tracing::info!(
+        key_id = %request.key_id,
+        txid = %txid,
+        signed_inputs = signed_inputs,
+        inputs = results.len(),
+        duration_ms = start_time.elapsed().as_millis(),
+        "🪙 PSBT signing finished"
+    );
+    let status = if signed_inputs > 0 { StatusCode::OK } else { StatusCode::INTERNAL_SERVER_ERROR };
+    (status, ResponseJson(SignPsbtResponse {
+        success: signed_inputs > 0,
+        message: format!("Signed {} of {} inputs", signed_inputs, results.len()),
+        psbt: general_purpose::STANDARD.encode(psbt.serialize()),
+        txid,
+        signed_inputs,
+        inputs: results,
+        request_id: None,
+    }))
+}
+
+// Signs the PSBT of an approved signing request after checking the policy of every
+// signing key again. Every input the wallet could sign when it was requested must be signed.
+pub async fn sign_approved(
+    request: &crate::approvals::SigningRequest,
+    psbt: &str,
+    network: &str,
+) -> Result<crate::approvals::ApprovedSignature> {
+    let wallet_keys = wallet_keys(&request.key_id)?;
+    let mut psbt = Psbt::parse(&general_purpose::STANDARD.decode(psbt)?)?;
+    let (destinations, value) = external_outputs(&psbt.unsigned_tx, &wallet_keys, network)?;
+    let plans = (0..psbt.inputs.len())
+        .filter_map(|index| plan_input(&psbt, index, &wallet_keys).ok())
+        .collect::<Vec<_>>();
+    if plans.is_empty() {
+        bail!("No input can be signed by this wallet");
+    }
+
+    let value = value.to_string();
+    let paths: BTreeSet<&str> = plans.iter().map(|plan| plan.path.as_str()).collect();
+    for path in paths {
+        enforce_approved_psbt_policy(&request.key_id, path, &destinations, &value)?;
+    }
+
+    let mut signers = BTreeSet::new();
+    for plan in &plans {
+        let target = crate::sign::SignTarget::Digest(&plan.sighash);
+        let signed = crate::sign::run_tss_sign(&request.key_id, target, &plan.path, request.participants.as_deref())
+            .await
+            .map_err(|e| anyhow!("Signing input {} failed: {}", plan.index, e))?;
+        psbt.add_partial_signature(plan.index, &plan.public_key, &signed.signature);
+        signers.extend(signed.signers);
+    }
+    tracing::info!(
+        key_id = %request.key_id,
+        txid = %psbt.unsigned_tx.display_txid(),
+        signed_inputs = plans.len(),
+        "🪙 Approved PSBT signed"
+    );
+    Ok(crate::approvals::ApprovedSignature {
+        signature: None,
+        recovery_id: None,
+        signers: signers.into_iter().collect(),
+        signed_transaction: Some(general_purpose::STANDARD.encode(psbt.serialize())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+
+    // Native P2WPKH example of BIP143: input 0 spends a P2PK output, input 1 a P2WPKH one
+    const BIP143_UNSIGNED_TX: &str = concat!(
+        "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffff",
+        "ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206",
+        "000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42db",
+        "ee7e4dbe6a21b2d50ce2f0167faa815988ac11000000",
+    );
+    const BIP143_SIGNED_TX: &str = concat!(
+        "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830",
+        "450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f928",
+        "1a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e81",
+        "5b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f6",
+        "6f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988",
+        "ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c",
+        "4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7aca",
+        "fcdb3566bb0ad253f62fc70f07aeee635711000000",
+    );
+    const BIP143_P2PK_KEY: &str = "03c9f4836b9a4f77fc0d81f7bcb01b7f1b35916864b9476c241ce9fc198bd25432";
+    const BIP143_P2PK_SIGNATURE: &str = concat!(
+        "30450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9",
+        "281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed",
+    );
+    const BIP143_P2WPKH_KEY: &str = "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357";
+    const BIP143_P2WPKH_SIGNATURE: &str = concat!(
+        "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c45183315",
+        "61406f90300e8f3358f51928d43c212a8caed02de67eebee",
+    );
+
+    // BIP174 test vectors, as rust-bitcoin carries them
+    const VALID_P2PKH_AND_P2SH_P2WPKH: &str = concat!(
+        "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPF",
+        "usKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtN",
+        "IOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6Uwpy",
+        "N+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7",
+        "NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf",
+        "3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/j",
+        "nF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8",
+        "hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA",
+    );
+    const VALID_TWO_P2PKH: &str = concat!(
+        "cHNidP8BAKACAAAAAqsJSaCMWvfEm4IS9Bfi8Vqz9cM9zxU4IagTn4d6W3vkAAAAAAD+////qwlJoIxa98SbghL0F+LxWrP1",
+        "wz3PFTghqBOfh3pbe+QBAAAAAP7///8CYDvqCwAAAAAZdqkUdopAu9dAy+gdmI5x3ipNXHE5ax2IrI4kAAAAAAAAGXapFG9G",
+        "ILVT+glechue4O/p+gOcykWXiKwAAAAAAAEA3wIAAAABJoFxNx7f8oXpN63upLN7eAAMBWbLs61kZBcTykIXG/YAAAAAakcw",
+        "RAIgcLIkUSPmv0dNYMW1DAQ9TGkaXSQ18Jo0p2YqncJReQoCIAEynKnazygL3zB0DsA5BCJCLIHLRYOUV663b8Eu3ZWzASEC",
+        "ZX0RjTNXuOD0ws1G23s59tnDjZpwq8ubLeXcjb/kzjH+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA",
+        "4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQEgAOH1BQAAAAAXqRQ1RebjO4MsRwUPJNPuuTycA5SLx4cB",
+        "BBYAFIXRNTfy4mVAWjTbr6nj3aAfuCMIACICAurVlmh8qAYEPtw94RbN8p1eklfBls0FXPaYyNAr8k6ZELSmumcAAACAAAAA",
+        "gAIAAIAAIgIDlPYr6d8ZlSxVh3aK63aYBhrSxKJciU9H2MFitNchPQUQtKa6ZwAAAIABAACAAgAAgAA=",
+    );
+    const INVALID_NETWORK_TRANSACTION: &str = concat!(
+        "AgAAAAEmgXE3Ht/yhek3re6ks3t4AAwFZsuzrWRkFxPKQhcb9gAAAABqRzBEAiBwsiRRI+a/R01gxbUMBD1MaRpdJDXwmjSn",
+        "ZiqdwlF5CgIgATKcqdrPKAvfMHQOwDkEIkIsgctFg5RXrrdvwS7dlbMBIQJlfRGNM1e44PTCzUbbezn22cONmnCry5st5dyN",
+        "v+TOMf7///8C09/1BQAAAAAZdqkU0MWZA8W6woaHYOkP1SGkZlqnZSCIrADh9QUAAAAAF6kUNUXm4zuDLEcFDyTT7rk8nAOU",
+        "i8eHsy4TAA==",
+    );
+    const INVALID_SIGNED_UNSIGNED_TX: &str = concat!(
+        "cHNidP8BAP0KAQIAAAACqwlJoIxa98SbghL0F+LxWrP1wz3PFTghqBOfh3pbe+QAAAAAakcwRAIgR1lmF5fAGwNrJZKJSGhi",
+        "GDR9iYZLcZ4ff89X0eURZYcCIFMJ6r9Wqk2Ikf/REf3xM286KdqGbX+EhtdVRs7tr5MZASEDXNxh/HupccC1AaZGoqg7ECy0",
+        "OIEhfKaC3Ibi1z+ogpL+////qwlJoIxa98SbghL0F+LxWrP1wz3PFTghqBOfh3pbe+QBAAAAAP7///8CYDvqCwAAAAAZdqkU",
+        "dopAu9dAy+gdmI5x3ipNXHE5ax2IrI4kAAAAAAAAGXapFG9GILVT+glechue4O/p+gOcykWXiKwAAAAAAAABASAA4fUFAAAA",
+        "ABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHhwEEFgAUhdE1N/LiZUBaNNuvqePdoB+4IwgAAAA=",
+    );
+    const INVALID_NO_UNSIGNED_TX: &str = concat!(
+        "cHNidP8AAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95P",
+        "UzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU///",
+        "//8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcw",
+        "RAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED",
+        "0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0C",
+        "IGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAA",
+        "AA==",
+    );
+
+    fn decode(psbt: &str) -> Result<Psbt> {
+        Psbt::parse(&general_purpose::STANDARD.decode(psbt).unwrap())
+    }
+
+    fn verify(public_key: &str, digest: &[u8; 32], der: &str) {
+        let key = VerifyingKey::from_sec1_bytes(&hex::decode(public_key).unwrap()).unwrap();
+        let signature = Signature::from_der(&hex::decode(der).unwrap()).unwrap();
+        key.verify_prehash(digest, &signature).unwrap();
+    }
+
+    // The BIP143 example as a PSBT whose P2WPKH input belongs to a wallet key at m/0/1
+    fn bip143_psbt() -> (Psbt, Vec<(String, k256::PublicKey)>) {
+        let unsigned_tx = hex::decode(BIP143_UNSIGNED_TX).unwrap();
+        let (transaction, _) = Transaction::parse(&unsigned_tx).unwrap();
+        let mut global = PsbtMap::default();
+        global.insert(vec![PSBT_GLOBAL_UNSIGNED_TX], unsigned_tx);
+
+        let public_key = hex::decode(BIP143_P2WPKH_KEY).unwrap();
+        let mut input = PsbtMap::default();
+        let spent = TxOut {
+            value: 600_000_000,
+            script_pubkey: hex::decode("00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1").unwrap(),
+        };
+        let mut witness_utxo = Vec::new();
+        spent.write(&mut witness_utxo);
+        input.insert(vec![PSBT_IN_WITNESS_UTXO], witness_utxo);
+        input.insert([&[PSBT_IN_BIP32_DERIVATION][..], &public_key].concat(), hex::decode("d90c6a4f0000000001000000").unwrap());
+
+        let psbt = Psbt {
+            global,
+            inputs: vec![PsbtMap::default(), input],
+            outputs: vec![PsbtMap::default(); transaction.outputs.len()],
+            unsigned_tx: transaction,
+        };
+        let wallet_keys = vec![("m/0/1".to_string(), k256::PublicKey::from_sec1_bytes(&public_key).unwrap())];
+        (psbt, wallet_keys)
+    }
+
+    #[test]
+    fn sighashes_match_the_signatures_of_the_bip143_example() {
+        let (signed, has_witness) = Transaction::parse(&hex::decode(BIP143_SIGNED_TX).unwrap()).unwrap();
+        let (unsigned, _) = Transaction::parse(&hex::decode(BIP143_UNSIGNED_TX).unwrap()).unwrap();
+        assert!(has_witness);
+        assert_eq!(signed.outputs, unsigned.outputs);
+        assert_eq!(signed.display_txid(), "e8151a2af31c368a35053ddd4bdb285a8595c769a3ad83e0fa02314a602d4609");
+
+        let p2pk_script = [&[0x21][..], &hex::decode(BIP143_P2PK_KEY).unwrap(), &[0xac]].concat();
+        verify(BIP143_P2PK_KEY, &unsigned.legacy_sighash(0, &p2pk_script), BIP143_P2PK_SIGNATURE);
+
+        let key_hash = crate::addresses::hash160(&hex::decode(BIP143_P2WPKH_KEY).unwrap());
+        let sighash = unsigned.segwit_v0_sighash(1, &p2pkh_script(&key_hash), 600_000_000);
+        assert_eq!(hex::encode(sighash), "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670");
+        verify(BIP143_P2WPKH_KEY, &sighash, BIP143_P2WPKH_SIGNATURE);
+    }
+
+    #[test]
+    fn wallet_inputs_are_planned_and_others_get_a_reason() {
+        let (psbt, wallet_keys) = bip143_psbt();
+        let plan = plan_input(&psbt, 1, &wallet_keys).unwrap();
+        assert_eq!(plan.path, "m/0/1");
+        assert_eq!(plan.script_type.name(), "p2wpkh");
+        assert_eq!(hex::encode(plan.sighash), "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670");
+        assert_eq!(
+            plan_input(&psbt, 0, &wallet_keys).unwrap_err().to_string(),
+            "Input has no witness_utxo or non_witness_utxo"
+        );
+
+        let reason = |mutate: fn(&mut PsbtMap)| {
+            let (mut psbt, wallet_keys) = bip143_psbt();
+            mutate(&mut psbt.inputs[1]);
+            plan_input(&psbt, 1, &wallet_keys).unwrap_err().to_string()
+        };
+        assert_eq!(
+            reason(|input| input.insert(vec![PSBT_IN_SIGHASH_TYPE], 0x83u32.to_le_bytes().to_vec())),
+            "Sighash type 0x83 is not supported, only SIGHASH_ALL"
+        );
+        assert_eq!(reason(|input| input.insert(vec![PSBT_IN_FINAL_SCRIPTWITNESS], vec![0x00])), "Input is already finalized");
+        assert_eq!(
+            reason(|input| input.entries.retain(|(key, _)| key[0] != PSBT_IN_BIP32_DERIVATION)),
+            "No bip32_derivation key of this input belongs to the wallet"
+        );
+        assert_eq!(
+            reason(|input| {
+                let mut witness_utxo = Vec::new();
+                TxOut { value: 600_000_000, script_pubkey: vec![0x00, 0x20, 0x11, 0x22] }.write(&mut witness_utxo);
+                input.insert(vec![PSBT_IN_WITNESS_UTXO], witness_utxo);
+            }),
+            "Spending nonstandard outputs is not supported"
+        );
+    }
+
+    #[test]
+    fn partial_signatures_are_inserted_and_round_trip() {
+        let (mut psbt, wallet_keys) = bip143_psbt();
+        let plan = plan_input(&psbt, 1, &wallet_keys).unwrap();
+        let signature = Signature::from_der(&hex::decode(BIP143_P2WPKH_SIGNATURE).unwrap()).unwrap();
+        psbt.add_partial_signature(plan.index, &plan.public_key, &signature);
+
+        let reparsed = Psbt::parse(&psbt.serialize()).unwrap();
+        let (key, value) = reparsed.inputs[1].entries_of(PSBT_IN_PARTIAL_SIG).next().unwrap();
+        assert_eq!(hex::encode(key), BIP143_P2WPKH_KEY);
+        assert_eq!(hex::encode(value), format!("{}01", BIP143_P2WPKH_SIGNATURE));
+        assert_eq!(reparsed, psbt);
+        assert_eq!(
+            plan_input(&reparsed, 1, &wallet_keys).unwrap_err().to_string(),
+            "Input already has a signature from key m/0/1"
+        );
+    }
+
+    #[test]
+    fn bip174_vectors_parse_and_round_trip() {
+        for vector in [VALID_P2PKH_AND_P2SH_P2WPKH, VALID_TWO_P2PKH] {
+            let psbt = decode(vector).unwrap();
+            assert_eq!(general_purpose::STANDARD.encode(psbt.serialize()), vector);
+        }
+
+        let psbt = decode(VALID_TWO_P2PKH).unwrap();
+        assert_eq!(psbt.inputs.len(), 2);
+        assert_eq!(psbt.outputs.len(), 2);
+        // Input 0 spends a P2PKH output of its non_witness_utxo, but no key belongs to the wallet
+        assert_eq!(
+            plan_input(&psbt, 0, &[]).unwrap_err().to_string(),
+            "No bip32_derivation key of this input belongs to the wallet"
+        );
+
+        assert_eq!(decode(INVALID_NETWORK_TRANSACTION).unwrap_err().to_string(), "Missing PSBT magic bytes");
+        assert_eq!(
+            decode(INVALID_SIGNED_UNSIGNED_TX).unwrap_err().to_string(),
+            "The unsigned transaction carries scriptSigs or witnesses"
+        );
+        assert_eq!(decode(INVALID_NO_UNSIGNED_TX).unwrap_err().to_string(), "PSBT has no unsigned transaction");
+    }
+
+    #[test]
+    fn change_outputs_are_not_destinations() {
+        let (psbt, _) = bip143_psbt();
+        let change_key = k256::PublicKey::from_sec1_bytes(&hex::decode(BIP143_P2WPKH_KEY).unwrap()).unwrap();
+        let mut transaction = psbt.unsigned_tx.clone();
+        transaction.outputs[1].script_pubkey = p2pkh_script(&crate::addresses::hash160(&change_key.to_sec1_bytes()));
+
+        let (destinations, value) = external_outputs(&transaction, &[("m/0/1".to_string(), change_key)], "mainnet").unwrap();
+        assert_eq!(destinations, ["1Cu32FVupVCgHkMMRJdYJugxwo2Aprgk7H"]);
+        assert_eq!(value, 112_340_000);
+        assert!(external_outputs(&transaction, &[], "signet").is_err());
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::Result;
+use axum::{extract::Json, response::Json as ResponseJson};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tss_ecdsa::{curve::{CurveTrait, TestCurve}, messages::Message, Identifier, ParticipantConfig, ParticipantIdentifier};
+
+use crate::node_api::{
+    DeliverMessageRequest, NodeStatusResponse, Phase, SessionOutput, SessionResponse, StartSessionRequest,
+    DELIVER_MESSAGE_PATH, NODE_STATUS_PATH, NODE_TOKEN_ENV, START_SESSION_PATH,
+};
+use crate::session_store::{read_service_json, write_service_json, ServiceRecord};
+
+// Comma separated node URLs, e.g. https://127.0.0.1:9001,https://127.0.0.1:9002
+const NODES_ENV: &str = "WAAS_NODES";
+// CA certificate used to authenticate the nodes' TLS certificates
+const NODE_CA_CERT_ENV: &str = "WAAS_NODE_CA_CERT";
+const DEFAULT_NODE_CA_CERT: &str = "cert.pem";
+// Upper bound on routed messages per session, so a misbehaving node can't keep a session alive forever
+const MAX_ROUTED_MESSAGES: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NodeAssignment {
+    pub url: String,
+    pub participant_id: ParticipantIdentifier,
+}
+
+// Public record of the key held by the nodes; no share ever reaches the coordinator
+#[derive(Serialize, Deserialize)]
+pub struct DistributedKey {
+    pub nodes: Vec<NodeAssignment>,
+    pub public_key: String,
+    pub chain_code: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+pub struct DistributedKeygenResponse {
+    pub success: bool,
+    pub message: String,
+    pub public_key: String,
+    pub chain_code: String,
+    pub participants: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DistributedSignRequest {
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct DistributedSignResponse {
+    pub success: bool,
+    pub message: String,
+    pub signature: String,
+}
+
+#[derive(Serialize)]
+pub struct NodeStatusEntry {
+    pub url: String,
+    pub reachable: bool,
+    pub error: Option<String>,
+    pub status: Option<NodeStatusResponse>,
+}
+
+#[derive(Serialize)]
+pub struct NodesStatusResponse {
+    pub success: bool,
+    pub message: String,
+    pub nodes: Vec<NodeStatusEntry>,
+}
+
+fn configured_nodes() -> Result<Vec<String>> {
+    let nodes: Vec<String> = std::env::var(NODES_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    if nodes.len() < 2 {
+        anyhow::bail!("{} must list at least two participant node URLs", NODES_ENV);
+    }
+    Ok(nodes)
+}
+
+struct NodeClient {
+    http: reqwest::Client,
+    token: String,
+}
+
+impl NodeClient {
+    fn new() -> Result<Self> {
+        let token = std::env::var(NODE_TOKEN_ENV).unwrap_or_default();
+        if token.is_empty() {
+            anyhow::bail!("{} must be set to the token shared with the participant nodes", NODE_TOKEN_ENV);
+        }
+
+        // Nodes are authenticated by their certificate; only the configured CA is trusted
+        let ca_path = std::env::var(NODE_CA_CERT_ENV).unwrap_or_else(|_| DEFAULT_NODE_CA_CERT.to_string());
+        let ca_cert = reqwest::Certificate::from_pem(&std::fs::read(&ca_path)?)?;
+        let http = reqwest::Client::builder()
+            .use_rustls_tls()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(ca_cert)
+            .build()?;
+
+        Ok(NodeClient { http, token })
+    }
+
+    async fn post<T: Serialize>(&self, url: &str, path: &str, body: &T) -> Result<SessionResponse> {
+        let response: SessionResponse = self
+            .http
+            .post(format!("{}{}", url, path))
+            .bearer_auth(&self.token)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.success {
+            anyhow::bail!("Node {} failed: {}", url, response.message);
+        }
+        Ok(response)
+    }
+
+    async fn status(&self, url: &str) -> Result<NodeStatusResponse> {
+        Ok(self
+            .http
+            .get(format!("{}{}", url, NODE_STATUS_PATH))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+// Starts a session on every node and routes messages between them until each node
+// reports an output. The coordinator only ever sees protocol messages and public outputs.
+async fn run_session(
+    client: &NodeClient,
+    nodes: &[NodeAssignment],
+    phase: Phase,
+    session_id: Identifier,
+    mut start_request: impl FnMut(&NodeAssignment) -> StartSessionRequest,
+) -> Result<HashMap<ParticipantIdentifier, SessionOutput>> {
+    let urls: HashMap<ParticipantIdentifier, &str> = nodes
+        .iter()
+        .map(|node| (node.participant_id, node.url.as_str()))
+        .collect();
+
+    let mut queue: VecDeque<Message> = VecDeque::new();
+    let mut outputs = HashMap::new();
+
+    tracing::debug!(session_id = %session_id, phase = ?phase, nodes = nodes.len(), "🚀 Starting distributed session");
+
+    for node in nodes {
+        let response = client.post(&node.url, START_SESSION_PATH, &start_request(node)).await?;
+        queue.extend(response.outgoing);
+        if let Some(output) = response.output {
+            outputs.insert(node.participant_id, output);
+        }
+    }
+
+    let mut routed = 0;
+    while let Some(message) = queue.pop_front() {
+        routed += 1;
+        if routed > MAX_ROUTED_MESSAGES {
+            anyhow::bail!("{:?} session {} exceeded {} routed messages", phase, session_id, MAX_ROUTED_MESSAGES);
+        }
+
+        let recipient = message.to();
+        let url = urls
+            .get(&recipient)
+            .ok_or_else(|| anyhow::anyhow!("Message addressed to unknown participant {}", recipient))?;
+        let response = client
+            .post(url, DELIVER_MESSAGE_PATH, &DeliverMessageRequest { session_id, message })
+            .await?;
+        queue.extend(response.outgoing);
+        if let Some(output) = response.output {
+            outputs.insert(recipient, output);
+        }
+    }
+
+    if outputs.len() != nodes.len() {
+        anyhow::bail!(
+            "{:?} session {} stalled: {} of {} nodes produced an output",
+            phase,
+            session_id,
+            outputs.len(),
+            nodes.len()
+        );
+    }
+
+    tracing::info!(session_id = %session_id, phase = ?phase, routed_messages = routed, "✅ Distributed session completed");
+    Ok(outputs)
+}
+
+fn load_distributed_key() -> Result<DistributedKey> {
+    read_service_json(ServiceRecord::DistributedKey)?
+        .ok_or_else(|| anyhow::anyhow!("No distributed key found. Run /nodes/keygen first."))
+}
+
+async fn run_distributed_keygen() -> Result<DistributedKey> {
+    let client = NodeClient::new()?;
+    let urls = configured_nodes()?;
+
+    let mut rng = StdRng::from_entropy();
+    let configs = ParticipantConfig::random_quorum(urls.len(), &mut rng)?;
+    let nodes: Vec<NodeAssignment> = urls
+        .iter()
+        .zip(&configs)
+        .map(|(url, config)| NodeAssignment { url: url.clone(), participant_id: config.id() })
+        .collect();
+    let configs: HashMap<ParticipantIdentifier, ParticipantConfig> =
+        configs.into_iter().map(|config| (config.id(), config)).collect();
+
+    let keygen_sid = Identifier::random(&mut rng);
+    let outputs = run_session(&client, &nodes, Phase::Keygen, keygen_sid, |node| StartSessionRequest {
+        session_id: keygen_sid,
+        phase: Phase::Keygen,
+        config: configs.get(&node.participant_id).cloned(),
+        message_hex: None,
+        presign_session_id: None,
+    })
+    .await?;
+
+    // Every node must agree on the public key before it is recorded
+    let mut public_parts = outputs.values().map(|output| match output {
+        SessionOutput::Keygen { public_key, chain_code } => Ok((public_key.clone(), chain_code.clone())),
+        _ => Err(anyhow::anyhow!("Unexpected keygen output")),
+    });
+    let (public_key, chain_code) = public_parts.next().ok_or_else(|| anyhow::anyhow!("No keygen output"))??;
+    for parts in public_parts {
+        if parts? != (public_key.clone(), chain_code.clone()) {
+            anyhow::bail!("Participant nodes disagree on the generated public key");
+        }
+    }
+
+    // Auxinfo once per key so signing only needs presign + sign
+    let auxinfo_sid = Identifier::random(&mut rng);
+    run_session(&client, &nodes, Phase::AuxInfo, auxinfo_sid, |_| StartSessionRequest {
+        session_id: auxinfo_sid,
+        phase: Phase::AuxInfo,
+        config: None,
+        message_hex: None,
+        presign_session_id: None,
+    })
+    .await?;
+
+    let key = DistributedKey {
+        nodes,
+        public_key,
+        chain_code,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    write_service_json(ServiceRecord::DistributedKey, &key)?;
+    Ok(key)
+}
+
+async fn run_distributed_sign(message: &str) -> Result<Vec<u8>> {
+    let client = NodeClient::new()?;
+    let key = load_distributed_key()?;
+    let mut rng = StdRng::from_entropy();
+
+    let presign_sid = Identifier::random(&mut rng);
+    run_session(&client, &key.nodes, Phase::Presign, presign_sid, |_| StartSessionRequest {
+        session_id: presign_sid,
+        phase: Phase::Presign,
+        config: None,
+        message_hex: None,
+        presign_session_id: None,
+    })
+    .await?;
+
+    let sign_sid = Identifier::random(&mut rng);
+    let message_hex = hex::encode(message.as_bytes());
+    let outputs = run_session(&client, &key.nodes, Phase::Sign, sign_sid, |_| StartSessionRequest {
+        session_id: sign_sid,
+        phase: Phase::Sign,
+        config: None,
+        message_hex: Some(message_hex.clone()),
+        presign_session_id: Some(presign_sid),
+    })
+    .await?;
+
+    let signature = match outputs.values().next() {
+        Some(SessionOutput::Sign { signature }) => hex::decode(signature)?,
+        _ => anyhow::bail!("Unexpected sign output"),
+    };
+
+    let public_key = <TestCurve as CurveTrait>::VerifyingKey::from_sec1_bytes(&hex::decode(&key.public_key)?)
+        .map_err(|_| anyhow::anyhow!("Stored distributed public key is invalid"))?;
+    if !crate::sign::verify_der_signature(&public_key, message.as_bytes(), &signature)? {
+        anyhow::bail!("Signature from participant nodes does not verify against the distributed public key");
+    }
+
+    Ok(signature)
+}
+
+// Distributed keygen across the configured participant nodes (POST)
+pub async fn nodes_keygen(_auth: crate::BasicAuth) -> ResponseJson<DistributedKeygenResponse> {
+    tracing::info!("🔑 Starting distributed key generation across participant nodes");
+    let start_time = std::time::Instant::now();
+
+    match run_distributed_keygen().await {
+        Ok(key) => {
+            tracing::info!(
+                nodes = key.nodes.len(),
+                duration_ms = start_time.elapsed().as_millis(),
+                "✅ Distributed key generation completed"
+            );
+            ResponseJson(DistributedKeygenResponse {
+                success: true,
+                message: format!("Key generated across {} participant nodes", key.nodes.len()),
+                public_key: key.public_key,
+                chain_code: key.chain_code,
+                participants: key.nodes.iter().map(|node| format!("{:?}@{}", node.participant_id, node.url)).collect(),
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Distributed key generation failed");
+            ResponseJson(DistributedKeygenResponse {
+                success: false,
+                message: format!("Distributed key generation failed: {}", e),
+                public_key: String::new(),
+                chain_code: String::new(),
+                participants: vec![],
+            })
+        }
+    }
+}
+
+// Distributed signing with the key held by the participant nodes (POST)
+pub async fn nodes_sign(
+    _auth: crate::BasicAuth,
+    Json(request): Json<DistributedSignRequest>,
+) -> ResponseJson<DistributedSignResponse> {
+    tracing::info!(message_length = request.message.len(), "🔐 Starting distributed signing");
+    let start_time = std::time::Instant::now();
+
+    match run_distributed_sign(&request.message).await {
+        Ok(signature) => {
+            tracing::info!(
+                duration_ms = start_time.elapsed().as_millis(),
+                "✅ Distributed signing completed"
+            );
+            ResponseJson(DistributedSignResponse {
+                success: true,
+                message: format!("Successfully signed message: '{}'", request.message),
+                signature: hex::encode(signature),
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Distributed signing failed");
+            ResponseJson(DistributedSignResponse {
+                success: false,
+                message: format!("Signing failed: {}", e),
+                signature: String::new(),
+            })
+        }
+    }
+}
+
+// Reachability and share status of every configured node (GET)
+pub async fn nodes_status(_auth: crate::BasicAuth) -> ResponseJson<NodesStatusResponse> {
+    let (client, urls) = match NodeClient::new().and_then(|client| Ok((client, configured_nodes()?))) {
+        Ok(setup) => setup,
+        Err(e) => {
+            return ResponseJson(NodesStatusResponse {
+                success: false,
+                message: e.to_string(),
+                nodes: vec![],
+            });
+        }
+    };
+
+    let mut nodes = Vec::with_capacity(urls.len());
+    for url in urls {
+        match client.status(&url).await {
+            Ok(status) => nodes.push(NodeStatusEntry { url, reachable: true, error: None, status: Some(status) }),
+            Err(e) => nodes.push(NodeStatusEntry { url, reachable: false, error: Some(e.to_string()), status: None }),
+        }
+    }
+
+    let reachable = nodes.iter().filter(|node| node.reachable).count();
+    ResponseJson(NodesStatusResponse {
+        success: true,
+        message: format!("{} of {} participant nodes reachable", reachable, nodes.len()),
+        nodes,
+    })
+}
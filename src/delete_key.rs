@@ -1,8 +1,12 @@
 use axum::{response::Json, http::StatusCode};
 use serde::{Serialize, Deserialize};
-use std::fs;
 use anyhow::Result;
 
+#[derive(Deserialize)]
+pub struct DeleteWalletRequest {
+    pub key_id: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DeleteKeyResponse {
     pub success: bool,
@@ -10,35 +14,64 @@ pub struct DeleteKeyResponse {
     pub deleted_files: Vec<String>,
 }
 
-/// Delete all key material and associated data from local storage
-pub async fn delete_key(_auth: crate::BasicAuth) -> Result<Json<DeleteKeyResponse>, (StatusCode, Json<DeleteKeyResponse>)> {
-    tracing::info!("🗑️ Starting key deletion process");
+/// Delete a wallet's key material and associated data; other wallets are untouched
+pub async fn delete_key(
+    auth: crate::BasicAuth,
+    Json(request): Json<DeleteWalletRequest>,
+) -> Result<Json<DeleteKeyResponse>, (StatusCode, Json<DeleteKeyResponse>)> {
+    tracing::info!(key_id = %request.key_id, "🗑️ Starting key deletion process");
     let start_time = std::time::Instant::now();
-    
-    match delete_all_key_material().await {
+
+    // Another user's wallet looks exactly like a missing one
+    if let Err(e) = crate::wallets::authorize(&auth, &request.key_id) {
+        tracing::warn!(key_id = %request.key_id, error = %e, "⚠️ Wallet to delete not found");
+        return Err((StatusCode::NOT_FOUND, Json(DeleteKeyResponse {
+            success: false,
+            message: format!("Key deletion failed: {}", e),
+            deleted_files: vec![],
+        })));
+    }
+
+    match delete_wallet_key_material(&request.key_id).await {
         Ok(deleted_files) => {
             let duration = start_time.elapsed();
             tracing::info!(
+                key_id = %request.key_id,
                 deleted_files_count = deleted_files.len(),
                 duration_ms = duration.as_millis(),
                 files = ?deleted_files,
                 "✅ Key deletion completed successfully"
             );
-            
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "delete_key", "success")
+                    .key_id(&request.key_id)
+                    .detail(format!("{} key records", deleted_files.len())),
+            );
+
             Ok(Json(DeleteKeyResponse {
                 success: true,
-                message: format!("Successfully deleted {} key files from local storage", deleted_files.len()),
+                message: format!(
+                    "Successfully deleted wallet {} ({} key records)",
+                    request.key_id,
+                    deleted_files.len()
+                ),
                 deleted_files,
             }))
         },
         Err(e) => {
             let duration = start_time.elapsed();
             tracing::error!(
+                key_id = %request.key_id,
                 error = %e,
                 duration_ms = duration.as_millis(),
                 "❌ Key deletion failed"
             );
-            
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "delete_key", "failed")
+                    .key_id(&request.key_id)
+                    .detail(e.to_string()),
+            );
+
             Err((StatusCode::INTERNAL_SERVER_ERROR, Json(DeleteKeyResponse {
                 success: false,
                 message: format!("Key deletion failed: {}", e),
@@ -48,131 +81,30 @@ pub async fn delete_key(_auth: crate::BasicAuth) -> Result<Json<DeleteKeyRespons
     }
 }
 
-async fn delete_all_key_material() -> Result<Vec<String>> {
-    let mut deleted_files = Vec::new();
-    
-    // List all key-related files that should be deleted
-    let key_files = [
-        "keygen_completed.marker",    // Keygen completion marker
-        "keygen_essentials.json",     // Stored keygen configurations and essentials
-        "public_key.bin",             // Public key for verification
-        "auxinfo_outputs.json",       // Auxiliary info outputs (if cached)
-        "presign_outputs.json",       // Presign outputs (if cached)
-    ];
-    
+async fn delete_wallet_key_material(key_id: &str) -> Result<Vec<String>> {
+    let store = crate::session_store::key_store()?;
+
     tracing::debug!(
-        files_to_check = key_files.len(),
-        "🔍 Checking for key files to delete"
+        key_id = %key_id,
+        backend = store.backend(),
+        "🔍 Deleting wallet key records"
     );
-    
-    // Attempt to delete each file
-    for file_path in &key_files {
-        match delete_file_if_exists(file_path) {
-            Ok(was_deleted) => {
-                if was_deleted {
-                    deleted_files.push(file_path.to_string());
-                    tracing::debug!(
-                        file = file_path,
-                        "✅ File deleted successfully"
-                    );
-                } else {
-                    tracing::debug!(
-                        file = file_path,
-                        "ℹ️ File did not exist (skipped)"
-                    );
-                }
-            },
-            Err(e) => {
-                tracing::warn!(
-                    file = file_path,
-                    error = %e,
-                    "⚠️ Failed to delete file"
-                );
-                // Continue with other files even if one fails
-            }
-        }
-    }
-    
-    // Also check for any additional key-related files with common patterns
-    let patterns_to_check = [
-        "*.key",
-        "tss_*.json",
-        "*_key_*",
-    ];
-    
-    for pattern in &patterns_to_check {
-        if let Ok(matching_files) = find_files_by_pattern(pattern) {
-            for file_path in matching_files {
-                match delete_file_if_exists(&file_path) {
-                    Ok(was_deleted) => {
-                        if was_deleted {
-                            deleted_files.push(file_path.clone());
-                            tracing::debug!(
-                                file = %file_path,
-                                "✅ Pattern-matched file deleted"
-                            );
-                        }
-                    },
-                    Err(e) => {
-                        tracing::warn!(
-                            file = %file_path,
-                            error = %e,
-                            "⚠️ Failed to delete pattern-matched file"
-                        );
-                    }
-                }
-            }
-        }
-    }
-    
-    if deleted_files.is_empty() {
-        tracing::info!("ℹ️ No key files found to delete - storage was already clean");
-        return Ok(vec!["No key files found".to_string()]);
+
+    let deleted_files: Vec<String> = crate::wallets::delete_wallet_records(key_id)?
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let removed_policies = crate::policy::remove_wallet_policies(key_id)?;
+    if removed_policies > 0 {
+        tracing::debug!(key_id = %key_id, removed_policies = removed_policies, "📜 Removed signing policies of deleted wallet");
     }
-    
+
     tracing::info!(
+        key_id = %key_id,
         deleted_count = deleted_files.len(),
         "🧹 Key material cleanup completed"
     );
-    
-    Ok(deleted_files)
-}
 
-fn delete_file_if_exists(file_path: &str) -> Result<bool> {
-    if fs::metadata(file_path).is_ok() {
-        fs::remove_file(file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to delete file '{}': {}", file_path, e))?;
-        Ok(true) // File existed and was deleted
-    } else {
-        Ok(false) // File did not exist
-    }
+    Ok(deleted_files)
 }
-
-fn find_files_by_pattern(pattern: &str) -> Result<Vec<String>> {
-    // Simple pattern matching for wallet key file patterns (excludes TLS .pem files)
-    let current_dir = std::env::current_dir()?;
-    let mut matching_files = Vec::new();
-    
-    if let Ok(entries) = fs::read_dir(&current_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let file_name = entry.file_name();
-                let file_name_str = file_name.to_string_lossy();
-                
-                // Simple pattern matching
-                let matches = match pattern {
-                    "*.key" => file_name_str.ends_with(".key"),
-                    "tss_*.json" => file_name_str.starts_with("tss_") && file_name_str.ends_with(".json"),
-                    "*_key_*" => file_name_str.contains("_key_"),
-                    _ => false,
-                };
-                
-                if matches {
-                    matching_files.push(file_name_str.to_string());
-                }
-            }
-        }
-    }
-    
-    Ok(matching_files)
-}
\ No newline at end of file
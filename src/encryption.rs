@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tss_ecdsa::{
+    curve::TestCurve,
+    keygen::KeygenParticipant,
+    tshare::TshareParticipant,
+    ParticipantIdentifier, ProtocolParticipant,
+};
+
+use crate::keygen::KeygenHelperOutput;
+use crate::master_key::{
+    is_encrypted, open_envelope, open_json, seal_envelope, seal_json, EncryptedEnvelope, ENCRYPTED_FORMAT,
+};
+use crate::session_store::{key_store, Record};
+
+const ENCRYPTED_KEYGEN_FORMAT: &str = "waas-encrypted-keygen-v1";
+
+// keygen_result.json layout: each participant's share sits in its own envelope
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeygenResult {
+    format: String,
+    threshold: Option<usize>,
+    shares: HashMap<ParticipantIdentifier, EncryptedEnvelope>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShareRecord {
+    keygen_output: <KeygenParticipant<TestCurve> as ProtocolParticipant>::Output,
+    tshare_output: Option<<TshareParticipant<TestCurve> as ProtocolParticipant>::Output>,
+}
+
+// Associated data of a wallet record: binds the envelope to both the wallet and the slot
+fn record_context(key_id: &str, record: Record) -> String {
+    format!("{}/{}", key_id, record.name())
+}
+
+fn share_context(key_id: &str, pid: &ParticipantIdentifier) -> String {
+    format!("keygen-share:{}/{}", key_id, pid)
+}
+
+pub fn write_encrypted<T: Serialize>(key_id: &str, record: Record, value: &T) -> Result<()> {
+    let envelope = seal_json(value, &record_context(key_id, record))?;
+    key_store()?.put(key_id, record, &serde_json::to_vec(&envelope)?)
+}
+
+// Reads a record written by write_encrypted. Plaintext records are refused; those left by
+// earlier versions are only taken over by migrate_key_material at startup.
+pub fn read_encrypted<T: DeserializeOwned>(key_id: &str, record: Record) -> Result<Option<T>> {
+    let Some(data) = key_store()?.get(key_id, record)? else {
+        return Ok(None);
+    };
+    let json = String::from_utf8(data)?;
+    if !is_encrypted(&json, ENCRYPTED_FORMAT) {
+        anyhow::bail!("Record {} of wallet {} is not encrypted", record.name(), key_id);
+    }
+    let envelope: EncryptedEnvelope = serde_json::from_str(&json)?;
+    open_json(&envelope, &record_context(key_id, record)).map(Some)
+}
+
+pub fn encrypt_keygen_result(key_id: &str, keygen_result: &KeygenHelperOutput<TestCurve>) -> Result<String> {
+    let mut shares = HashMap::new();
+    for (pid, keygen_output) in &keygen_result.keygen_outputs {
+        let record = ShareRecord {
+            keygen_output: keygen_output.clone(),
+            tshare_output: keygen_result
+                .tshare_outputs
+                .as_ref()
+                .and_then(|outputs| outputs.get(pid).cloned()),
+        };
+        shares.insert(*pid, seal_json(&record, &share_context(key_id, pid))?);
+    }
+
+    Ok(serde_json::to_string_pretty(&EncryptedKeygenResult {
+        format: ENCRYPTED_KEYGEN_FORMAT.to_string(),
+        threshold: keygen_result.threshold,
+        shares,
+    })?)
+}
+
+pub fn decrypt_keygen_result(key_id: &str, json: &str) -> Result<KeygenHelperOutput<TestCurve>> {
+    if !is_encrypted(json, ENCRYPTED_KEYGEN_FORMAT) {
+        anyhow::bail!("Keygen result of wallet {} is not encrypted", key_id);
+    }
+
+    let encrypted: EncryptedKeygenResult = serde_json::from_str(json)?;
+    let mut keygen_outputs = HashMap::new();
+    let mut tshare_outputs = HashMap::new();
+    for (pid, envelope) in &encrypted.shares {
+        let record: ShareRecord = open_json(envelope, &share_context(key_id, pid))?;
+        keygen_outputs.insert(*pid, record.keygen_output);
+        if let Some(tshare_output) = record.tshare_output {
+            tshare_outputs.insert(*pid, tshare_output);
+        }
+    }
+
+    Ok(KeygenHelperOutput {
+        keygen_outputs,
+        threshold: encrypted.threshold,
+        tshare_outputs: (!tshare_outputs.is_empty()).then_some(tshare_outputs),
+    })
+}
+
+// Re-encrypts a keygen result stored in plaintext or with envelopes not bound to the wallet
+fn migrate_keygen_result(key_id: &str) -> Result<()> {
+    let store = key_store()?;
+    let Some(data) = store.get(key_id, Record::KeygenResult)? else {
+        return Ok(());
+    };
+    let json = String::from_utf8(data)?;
+    if decrypt_keygen_result(key_id, &json).is_ok() {
+        return Ok(());
+    }
+
+    let migrated = if is_encrypted(&json, ENCRYPTED_KEYGEN_FORMAT) {
+        let mut encrypted: EncryptedKeygenResult = serde_json::from_str(&json)?;
+        for (pid, envelope) in encrypted.shares.iter_mut() {
+            let plaintext = open_envelope(envelope, &format!("keygen-share:{}", pid))?;
+            *envelope = seal_envelope(&plaintext, &share_context(key_id, pid))?;
+        }
+        serde_json::to_string_pretty(&encrypted)?
+    } else {
+        encrypt_keygen_result(key_id, &serde_json::from_str(&json)?)?
+    };
+    store.put(key_id, Record::KeygenResult, migrated.as_bytes())?;
+    tracing::info!(key_id = %key_id, record = Record::KeygenResult.name(), "🔐 Migrated key shares to wallet-bound encryption");
+    Ok(())
+}
+
+// Same for a record written by write_encrypted; the plaintext bytes are sealed unchanged
+fn migrate_record(key_id: &str, record: Record) -> Result<()> {
+    let store = key_store()?;
+    let Some(data) = store.get(key_id, record)? else {
+        return Ok(());
+    };
+    let json = String::from_utf8(data)?;
+    let plaintext = if is_encrypted(&json, ENCRYPTED_FORMAT) {
+        let envelope: EncryptedEnvelope = serde_json::from_str(&json)?;
+        if open_envelope(&envelope, &record_context(key_id, record)).is_ok() {
+            return Ok(());
+        }
+        open_envelope(&envelope, record.name())?
+    } else {
+        json.into_bytes()
+    };
+    let envelope = seal_envelope(&plaintext, &record_context(key_id, record))?;
+    store.put(key_id, record, &serde_json::to_vec(&envelope)?)?;
+    tracing::info!(key_id = %key_id, record = record.name(), "🔐 Migrated record to wallet-bound encryption");
+    Ok(())
+}
+
+// Loads the service's master key, whose metadata lives in the storage directory
+pub fn init_master_key() -> Result<()> {
+    let storage_dir = crate::session_store::storage_dir();
+    crate::master_key::move_legacy_metadata(&storage_dir)?;
+    crate::master_key::init_master_key(&storage_dir)
+}
+
+// One-time startup migration of stores written by earlier versions: plaintext records from
+// before encryption at rest, and envelopes whose associated data did not name the wallet, are
+// re-encrypted. Once it completes, the metadata version is raised and every later read
+// refuses anything but the current format.
+pub fn migrate_key_material() -> Result<()> {
+    if !crate::master_key::migration_pending() {
+        return Ok(());
+    }
+
+    for key_id in key_store()?.key_ids()? {
+        migrate_keygen_result(&key_id)?;
+        for record in [Record::AuxInfoCache, Record::PresignPool] {
+            migrate_record(&key_id, record)?;
+        }
+    }
+    crate::master_key::complete_migration()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::master_key::init_test_master_key;
+
+    #[test]
+    fn envelopes_are_bound_to_their_wallet() {
+        init_test_master_key();
+        let envelope = seal_json(&"pool", &record_context("alpha", Record::PresignPool)).unwrap();
+
+        let opened: String = open_json(&envelope, &record_context("alpha", Record::PresignPool)).unwrap();
+        assert_eq!(opened, "pool");
+        assert!(open_json::<String>(&envelope, &record_context("beta", Record::PresignPool)).is_err());
+        assert!(open_json::<String>(&envelope, &record_context("alpha", Record::AuxInfoCache)).is_err());
+        assert!(open_json::<String>(&envelope, Record::PresignPool.name()).is_err());
+    }
+}
@@ -0,0 +1,561 @@
+// Ethereum message signing: EIP-191 personal messages and EIP-712 typed data.
+// Both produce a preimage whose Keccak-256 hash is what the TSS protocol signs.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+const PERSONAL_MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+// "\x19Ethereum Signed Message:\n" + len(message) + message, as personal_sign hashes it
+pub fn personal_message_preimage(message: &[u8]) -> Vec<u8> {
+    let mut preimage = format!("{}{}", PERSONAL_MESSAGE_PREFIX, message.len()).into_bytes();
+    preimage.extend_from_slice(message);
+    preimage
+}
+
+// "\x19\x01" || domainSeparator || hashStruct(message), as eth_signTypedData_v4 hashes it
+pub fn typed_data_preimage(typed_data: &Value) -> Result<Vec<u8>> {
+    let typed_data = TypedData::parse(typed_data)?;
+    let domain_separator = typed_data.hash_struct(DOMAIN_TYPE, typed_data.domain)?;
+    let message_hash = typed_data.hash_struct(typed_data.primary_type, typed_data.message)?;
+
+    let mut preimage = vec![0x19, 0x01];
+    preimage.extend_from_slice(&domain_separator);
+    preimage.extend_from_slice(&message_hash);
+    Ok(preimage)
+}
+
+struct TypedData<'a> {
+    types: &'a serde_json::Map<String, Value>,
+    primary_type: &'a str,
+    domain: &'a Value,
+    message: &'a Value,
+}
+
+impl<'a> TypedData<'a> {
+    fn parse(value: &'a Value) -> Result<Self> {
+        let types = value
+            .get("types")
+            .and_then(Value::as_object)
+            .ok_or_else(|| anyhow::anyhow!("Typed data needs a `types` object"))?;
+        if !types.contains_key(DOMAIN_TYPE) {
+            anyhow::bail!("Typed data needs an {} type", DOMAIN_TYPE);
+        }
+        let primary_type = value
+            .get("primaryType")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Typed data needs a `primaryType`"))?;
+        let domain = value
+            .get("domain")
+            .ok_or_else(|| anyhow::anyhow!("Typed data needs a `domain`"))?;
+        let message = value
+            .get("message")
+            .ok_or_else(|| anyhow::anyhow!("Typed data needs a `message`"))?;
+        Ok(Self { types, primary_type, domain, message })
+    }
+
+    // (name, type) pairs of a struct type, in declaration order
+    fn fields(&self, type_name: &str) -> Result<Vec<(&'a str, &'a str)>> {
+        let fields = self
+            .types
+            .get(type_name)
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("Unknown type {}", type_name))?;
+        fields
+            .iter()
+            .map(|field| {
+                let name = field.get("name").and_then(Value::as_str);
+                let field_type = field.get("type").and_then(Value::as_str);
+                match (name, field_type) {
+                    (Some(name), Some(field_type)) => Ok((name, field_type)),
+                    _ => Err(anyhow::anyhow!("Fields of type {} need a name and a type", type_name)),
+                }
+            })
+            .collect()
+    }
+
+    fn collect_dependencies(&self, type_name: &str, found: &mut BTreeSet<String>) -> Result<()> {
+        if found.contains(type_name) || !self.types.contains_key(type_name) {
+            return Ok(());
+        }
+        found.insert(type_name.to_string());
+        for (_, field_type) in self.fields(type_name)? {
+            self.collect_dependencies(base_type(field_type), found)?;
+        }
+        Ok(())
+    }
+
+    // "Mail(Person from,Person to,string contents)Person(string name,address wallet)":
+    // the type itself, then the structs it references in alphabetical order
+    fn encode_type(&self, type_name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies)?;
+        dependencies.remove(type_name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(dependencies.iter().map(String::as_str)) {
+            let fields: Vec<String> = self
+                .fields(name)?
+                .into_iter()
+                .map(|(field_name, field_type)| format!("{} {}", field_type, field_name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    fn hash_struct(&self, type_name: &str, data: &Value) -> Result<[u8; 32]> {
+        let object = data
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Value of type {} must be an object", type_name))?;
+        let mut encoded = keccak(self.encode_type(type_name)?.as_bytes()).to_vec();
+        for (name, field_type) in self.fields(type_name)? {
+            let value = object
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("{}.{} is missing", type_name, name))?;
+            let word = self
+                .encode_value(field_type, value)
+                .map_err(|e| anyhow::anyhow!("{}.{}: {}", type_name, name, e))?;
+            encoded.extend_from_slice(&word);
+        }
+        Ok(keccak(&encoded))
+    }
+
+    // The 32-byte word a field contributes to its struct's encoding
+    fn encode_value(&self, field_type: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some(element_type) = array_element_type(field_type) {
+            let elements = value
+                .as_array()
+                .ok_or_else(|| anyhow::anyhow!("expected an array for {}", field_type))?;
+            if let Some(length) = array_length(field_type)?
+                && elements.len() != length
+            {
+                anyhow::bail!("expected {} elements for {}, got {}", length, field_type, elements.len());
+            }
+            let mut encoded = Vec::with_capacity(elements.len() * 32);
+            for element in elements {
+                encoded.extend_from_slice(&self.encode_value(element_type, element)?);
+            }
+            return Ok(keccak(&encoded));
+        }
+        if self.types.contains_key(field_type) {
+            return self.hash_struct(field_type, value);
+        }
+
+        match field_type {
+            "string" => {
+                let text = value.as_str().ok_or_else(|| anyhow::anyhow!("expected a string"))?;
+                Ok(keccak(text.as_bytes()))
+            }
+            "bytes" => Ok(keccak(&hex_value(value)?)),
+            "bool" => {
+                let flag = match value {
+                    Value::Bool(flag) => *flag,
+                    Value::String(text) if text == "true" || text == "false" => text == "true",
+                    _ => anyhow::bail!("expected a boolean"),
+                };
+                Ok(uint_word(flag as u8))
+            }
+            "address" => {
+                let address = hex_value(value)?;
+                if address.len() != 20 {
+                    anyhow::bail!("an address must be 20 bytes, got {}", address.len());
+                }
+                let mut word = [0u8; 32];
+                word[12..].copy_from_slice(&address);
+                Ok(word)
+            }
+            _ => {
+                if let Some(bits) = field_type.strip_prefix("uint") {
+                    encode_integer(value, integer_bits(field_type, bits)?, false)
+                } else if let Some(bits) = field_type.strip_prefix("int") {
+                    encode_integer(value, integer_bits(field_type, bits)?, true)
+                } else if let Some(length) = field_type.strip_prefix("bytes") {
+                    let length: usize = length
+                        .parse()
+                        .ok()
+                        .filter(|length| (1..=32).contains(length))
+                        .ok_or_else(|| anyhow::anyhow!("unknown type {}", field_type))?;
+                    let bytes = hex_value(value)?;
+                    if bytes.len() != length {
+                        anyhow::bail!("expected {} bytes for {}, got {}", length, field_type, bytes.len());
+                    }
+                    let mut word = [0u8; 32];
+                    word[..length].copy_from_slice(&bytes);
+                    Ok(word)
+                } else {
+                    anyhow::bail!("unknown type {}", field_type)
+                }
+            }
+        }
+    }
+}
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+fn uint_word(value: u8) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[31] = value;
+    word
+}
+
+// "Person[][3]" -> Some("Person[]"); None for non-array types
+fn array_element_type(field_type: &str) -> Option<&str> {
+    if !field_type.ends_with(']') {
+        return None;
+    }
+    field_type.rfind('[').map(|start| &field_type[..start])
+}
+
+fn array_length(field_type: &str) -> Result<Option<usize>> {
+    let start = field_type.rfind('[').unwrap_or(0);
+    let length = &field_type[start + 1..field_type.len() - 1];
+    if length.is_empty() {
+        return Ok(None);
+    }
+    length
+        .parse()
+        .map(Some)
+        .map_err(|_| anyhow::anyhow!("invalid array length in {}", field_type))
+}
+
+// Struct name a field refers to, without array suffixes
+fn base_type(field_type: &str) -> &str {
+    field_type.split('[').next().unwrap_or(field_type)
+}
+
+fn integer_bits(field_type: &str, bits: &str) -> Result<usize> {
+    if bits.is_empty() {
+        return Ok(256);
+    }
+    bits.parse()
+        .ok()
+        .filter(|bits| *bits >= 8 && *bits <= 256 && bits % 8 == 0)
+        .ok_or_else(|| anyhow::anyhow!("unknown type {}", field_type))
+}
+
+fn hex_value(value: &Value) -> Result<Vec<u8>> {
+    let text = value.as_str().ok_or_else(|| anyhow::anyhow!("expected a hex string"))?;
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    hex::decode(digits).map_err(|_| anyhow::anyhow!("'{}' is not valid hex", text))
+}
+
+// Integers as 256-bit big-endian two's complement words. Values can be JSON
+// numbers or decimal/hex strings, since most exceed what JSON numbers can hold.
+fn encode_integer(value: &Value, bits: usize, signed: bool) -> Result<[u8; 32]> {
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.trim().to_string(),
+        _ => anyhow::bail!("expected an integer"),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.as_str()),
+    };
+    if negative && !signed {
+        anyhow::bail!("expected an unsigned integer, got {}", text);
+    }
+
+    let mut magnitude = [0u8; 32];
+    let (radix, digits) = match digits.strip_prefix("0x") {
+        Some(hex_digits) => (16u32, hex_digits),
+        None => (10u32, digits),
+    };
+    if digits.is_empty() {
+        anyhow::bail!("expected an integer, got '{}'", text);
+    }
+    for digit in digits.chars() {
+        let digit = digit
+            .to_digit(radix)
+            .ok_or_else(|| anyhow::anyhow!("invalid integer '{}'", text))?;
+        // magnitude = magnitude * radix + digit
+        let mut carry = digit;
+        for byte in magnitude.iter_mut().rev() {
+            let product = *byte as u32 * radix + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry != 0 {
+            anyhow::bail!("{} does not fit in 256 bits", text);
+        }
+    }
+
+    // Signed values keep one bit for the sign; the most negative value is 2^(bits-1)
+    let used_bits = magnitude
+        .iter()
+        .position(|byte| *byte != 0)
+        .map_or(0, |index| (32 - index) * 8 - magnitude[index].leading_zeros() as usize);
+    let mut min_negative = [0u8; 32];
+    min_negative[31 - (bits - 1) / 8] = 1 << ((bits - 1) % 8);
+    let limit = if signed { bits - 1 } else { bits };
+    if used_bits > limit && !(negative && magnitude == min_negative) {
+        anyhow::bail!("{} does not fit in {}{}", text, if signed { "int" } else { "uint" }, bits);
+    }
+
+    if negative {
+        // Two's complement: invert and add one
+        let mut carry = 1u16;
+        for byte in magnitude.iter_mut().rev() {
+            let sum = (!*byte) as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+    Ok(magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn domain_type() -> Value {
+        json!([
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" },
+        ])
+    }
+
+    fn domain() -> Value {
+        json!({
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+        })
+    }
+
+    // The example of the EIP-712 specification
+    fn mail_example() -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": domain_type(),
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" },
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": domain(),
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!",
+            },
+        })
+    }
+
+    #[test]
+    fn mail_example_matches_the_spec() {
+        let typed_data = mail_example();
+        let parsed = TypedData::parse(&typed_data).unwrap();
+        assert_eq!(
+            parsed.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(parsed.hash_struct(DOMAIN_TYPE, parsed.domain).unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(parsed.hash_struct("Mail", parsed.message).unwrap()),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(keccak(&typed_data_preimage(&typed_data).unwrap())),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    // ecrecover of the specification's signature by the key keccak256("cow")
+    #[test]
+    fn mail_example_signature_recovers_to_cow() {
+        use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+        let digest = keccak(&typed_data_preimage(&mail_example()).unwrap());
+        let signature = Signature::from_slice(
+            &hex::decode(
+                "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+                 07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        // v = 28
+        let key = VerifyingKey::recover_from_prehash(&digest, &signature, RecoveryId::from_byte(1).unwrap()).unwrap();
+        let address = crate::addresses::KeyAddresses::from_public_key(key.to_encoded_point(false).as_bytes())
+            .unwrap()
+            .ethereum;
+        assert_eq!(address, "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+
+        let cow = k256::ecdsa::SigningKey::from_slice(&keccak(b"cow")).unwrap();
+        assert_eq!(&key, cow.verifying_key());
+    }
+
+    #[test]
+    fn encode_type_lists_referenced_structs_alphabetically() {
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [],
+                "Order": [
+                    { "name": "zone", "type": "Zone" },
+                    { "name": "items", "type": "Item[2][]" },
+                    { "name": "buyer", "type": "Person" },
+                ],
+                "Item": [{ "name": "asset", "type": "Asset" }, { "name": "owner", "type": "Person" }],
+                "Asset": [{ "name": "token", "type": "address" }],
+                "Person": [{ "name": "name", "type": "string" }, { "name": "orders", "type": "Order[]" }],
+                "Zone": [{ "name": "id", "type": "uint8" }],
+                "Unused": [{ "name": "flag", "type": "bool" }],
+            },
+            "primaryType": "Order",
+            "domain": {},
+            "message": {},
+        });
+        let parsed = TypedData::parse(&typed_data).unwrap();
+        assert_eq!(
+            parsed.encode_type("Order").unwrap(),
+            "Order(Zone zone,Item[2][] items,Person buyer)Asset(address token)Item(Asset asset,Person owner)\
+             Person(string name,Order[] orders)Zone(uint8 id)"
+        );
+        assert_eq!(
+            parsed.encode_type("Item").unwrap(),
+            "Item(Asset asset,Person owner)Asset(address token)Order(Zone zone,Item[2][] items,Person buyer)\
+             Person(string name,Order[] orders)Zone(uint8 id)"
+        );
+    }
+
+    // eth-sig-util's signTypedData_v4 example with arrays of structs and of addresses
+    #[test]
+    fn nested_structs_and_arrays() {
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": domain_type(),
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallets", "type": "address[]" },
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person[]" },
+                    { "name": "contents", "type": "string" },
+                ],
+                "Group": [
+                    { "name": "name", "type": "string" },
+                    { "name": "members", "type": "Person[]" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": domain(),
+            "message": {
+                "from": {
+                    "name": "Cow",
+                    "wallets": [
+                        "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+                        "0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF",
+                    ],
+                },
+                "to": [{
+                    "name": "Bob",
+                    "wallets": [
+                        "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+                        "0xB0BdaBea57B0BDABeA57b0bdABEA57b0BDabEa57",
+                        "0xB0B0b0b0b0b0B000000000000000000000000000",
+                    ],
+                }],
+                "contents": "Hello, Bob!",
+            },
+        });
+        let parsed = TypedData::parse(&typed_data).unwrap();
+        assert_eq!(
+            parsed.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person[] to,string contents)Person(string name,address[] wallets)"
+        );
+        assert_eq!(
+            hex::encode(parsed.hash_struct("Mail", parsed.message).unwrap()),
+            "eb4221181ff3f1a83ea7313993ca9218496e424604ba9492bb4052c03d5c3df8"
+        );
+        assert_eq!(
+            hex::encode(keccak(&typed_data_preimage(&typed_data).unwrap())),
+            "a85c2e2b118698e88db68a8105b794a8cc7cec074e89ef991cb4f5f533819cc2"
+        );
+
+        // Fixed-size arrays must have exactly their length
+        let mut wrong_length = typed_data.clone();
+        wrong_length["types"]["Person"][1]["type"] = "address[3]".into();
+        assert!(typed_data_preimage(&wrong_length).is_err());
+    }
+
+    #[test]
+    fn signed_integers_are_twos_complement() {
+        let word = |value: Value, bits, signed| encode_integer(&value, bits, signed).map(hex::encode);
+        let ones = "ff".repeat(32);
+
+        assert_eq!(word(json!(-1), 256, true).unwrap(), ones);
+        assert_eq!(word(json!("-1"), 8, true).unwrap(), ones);
+        assert_eq!(word(json!(-128), 8, true).unwrap(), format!("{}80", "ff".repeat(31)));
+        assert_eq!(word(json!(127), 8, true).unwrap(), format!("{}7f", "00".repeat(31)));
+        assert_eq!(
+            word(json!("-9223372036854775808"), 64, true).unwrap(),
+            format!("{}8000000000000000", "ff".repeat(24))
+        );
+        assert_eq!(word(json!("0xff"), 8, false).unwrap(), format!("{}ff", "00".repeat(31)));
+        assert_eq!(word(json!(format!("0x{}", ones)), 256, false).unwrap(), ones);
+        let min_int256 = "-57896044618658097711785492504343953926634992332820282019728792003956564819968";
+        assert_eq!(word(json!(min_int256), 256, true).unwrap(), format!("80{}", "00".repeat(31)));
+
+        assert!(word(json!(128), 8, true).is_err());
+        assert!(word(json!(-129), 8, true).is_err());
+        assert!(word(json!(256), 8, false).is_err());
+        assert!(word(json!(-1), 256, false).is_err());
+        assert!(word(json!(format!("0x1{}", "00".repeat(32))), 256, false).is_err());
+        assert!(word(json!("-"), 256, true).is_err());
+        assert!(word(json!("12a"), 256, false).is_err());
+    }
+
+    #[test]
+    fn integer_fields_hash_as_words() {
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }],
+                "Numbers": [
+                    { "name": "a", "type": "int8" },
+                    { "name": "b", "type": "int256" },
+                    { "name": "c", "type": "uint256" },
+                    { "name": "d", "type": "int64" },
+                    { "name": "grid", "type": "uint8[2][]" },
+                ],
+            },
+            "primaryType": "Numbers",
+            "domain": { "name": "ints" },
+            "message": { "a": -128, "b": "-1", "c": "0xff", "d": "-9223372036854775808", "grid": [[1, 2], [3, 4]] },
+        });
+        assert_eq!(
+            hex::encode(keccak(&typed_data_preimage(&typed_data).unwrap())),
+            "feb34f2a32789c61adc8b18d6310f770b9b46b0023924544208829623892d900"
+        );
+    }
+
+    #[test]
+    fn personal_messages_are_prefixed_with_their_length() {
+        assert_eq!(personal_message_preimage(b"Hello World"), b"\x19Ethereum Signed Message:\n11Hello World");
+        assert_eq!(
+            hex::encode(keccak(&personal_message_preimage(b"Hello World"))),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+        assert_eq!(personal_message_preimage(b""), b"\x19Ethereum Signed Message:\n0");
+    }
+}
@@ -0,0 +1,721 @@
+use anyhow::Result;
+use axum::{extract::Json, http::StatusCode, response::Json as ResponseJson};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Keccak256};
+
+// Ethereum transaction envelopes (EIP-2718); legacy transactions have no type byte
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TxType {
+    #[serde(alias = "0x0")]
+    Legacy,
+    #[serde(alias = "0x1")]
+    Eip2930,
+    #[serde(alias = "0x2")]
+    Eip1559,
+}
+
+impl TxType {
+    pub fn name(self) -> &'static str {
+        match self {
+            TxType::Legacy => "legacy",
+            TxType::Eip2930 => "eip2930",
+            TxType::Eip1559 => "eip1559",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AccessListItem {
+    pub address: String,
+    #[serde(default)]
+    pub storage_keys: Vec<String>,
+}
+
+// Unsigned transaction as sent by the client. Quantities are JSON numbers, decimal
+// strings or 0x-prefixed hex strings; wei amounts are stored as decimal strings.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EthTransaction {
+    #[serde(rename = "type")]
+    pub tx_type: Option<TxType>, // Inferred from the fee fields when omitted
+    #[serde(default, deserialize_with = "optional_quantity")]
+    pub chain_id: Option<u64>, // Required for typed transactions; EIP-155 replay protection for legacy ones
+    #[serde(deserialize_with = "quantity")]
+    pub nonce: u64,
+    #[serde(default, deserialize_with = "optional_quantity", serialize_with = "optional_decimal")]
+    pub gas_price: Option<u128>, // Legacy and EIP-2930
+    #[serde(default, deserialize_with = "optional_quantity", serialize_with = "optional_decimal")]
+    pub max_priority_fee_per_gas: Option<u128>, // EIP-1559
+    #[serde(default, deserialize_with = "optional_quantity", serialize_with = "optional_decimal")]
+    pub max_fee_per_gas: Option<u128>, // EIP-1559
+    #[serde(alias = "gas", deserialize_with = "quantity")]
+    pub gas_limit: u64,
+    pub to: Option<String>, // None creates a contract
+    #[serde(default, deserialize_with = "quantity", serialize_with = "decimal")]
+    pub value: u128, // In wei
+    #[serde(default, alias = "input")]
+    pub data: Option<String>, // Hex calldata
+    #[serde(default)]
+    pub access_list: Vec<AccessListItem>, // EIP-2930 and EIP-1559
+}
+
+#[derive(Deserialize)]
+pub struct SignTransactionRequest {
+    pub key_id: String,
+    pub path: Option<String>, // Optional derivation path, e.g. m/0/5
+    pub child_index: Option<u32>, // Optional: if None, use root key (0)
+    pub participants: Option<Vec<String>>, // Optional signer subset; if None, t random signers are chosen
+    pub transaction: EthTransaction,
+}
+
+#[derive(Serialize)]
+pub struct SignTransactionResponse {
+    pub success: bool,
+    pub message: String,
+    pub tx_type: Option<String>,
+    pub raw_transaction: String, // 0x-prefixed, ready for eth_sendRawTransaction
+    pub transaction_hash: String,
+    pub r: String,
+    pub s: String,
+    pub v: u64, // y-parity for typed transactions, 27/28 or EIP-155 value for legacy ones
+    pub signers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // Set when the transaction waits for approvals
+}
+
+impl SignTransactionResponse {
+    fn failure(message: String) -> Self {
+        Self {
+            success: false,
+            message,
+            tx_type: None,
+            raw_transaction: String::new(),
+            transaction_hash: String::new(),
+            r: String::new(),
+            s: String::new(),
+            v: 0,
+            signers: vec![],
+            request_id: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuantityRepr {
+    Number(u64),
+    Text(String),
+}
+
+fn parse_quantity<E: serde::de::Error>(repr: QuantityRepr) -> std::result::Result<u128, E> {
+    match repr {
+        QuantityRepr::Number(number) => Ok(number as u128),
+        QuantityRepr::Text(text) => {
+            let text = text.trim();
+            let parsed = match text.strip_prefix("0x") {
+                Some("") => Ok(0),
+                Some(digits) => u128::from_str_radix(digits, 16),
+                None => text.parse::<u128>(),
+            };
+            parsed.map_err(|_| E::custom(format!("invalid quantity '{}'", text)))
+        }
+    }
+}
+
+fn quantity<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u128>,
+{
+    let value = parse_quantity(QuantityRepr::deserialize(deserializer)?)?;
+    T::try_from(value).map_err(|_| serde::de::Error::custom(format!("quantity {} is too large", value)))
+}
+
+fn optional_quantity<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u128>,
+{
+    match Option::<QuantityRepr>::deserialize(deserializer)? {
+        Some(repr) => {
+            let value = parse_quantity(repr)?;
+            T::try_from(value)
+                .map(Some)
+                .map_err(|_| serde::de::Error::custom(format!("quantity {} is too large", value)))
+        }
+        None => Ok(None),
+    }
+}
+
+// Wei amounts can exceed what JSON numbers carry exactly
+fn decimal<S: Serializer>(value: &u128, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn optional_decimal<S: Serializer>(value: &Option<u128>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+// Recursive Length Prefix encoding, as far as transactions need it
+fn rlp_length_prefix(length: usize, offset: u8) -> Vec<u8> {
+    if length < 56 {
+        return vec![offset + length as u8];
+    }
+    let length_bytes = strip_leading_zeros(&(length as u64).to_be_bytes()).to_vec();
+    let mut prefix = vec![offset + 55 + length_bytes.len() as u8];
+    prefix.extend(length_bytes);
+    prefix
+}
+
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut encoded = rlp_length_prefix(bytes.len(), 0x80);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut encoded = rlp_length_prefix(payload.len(), 0xc0);
+    encoded.extend(payload);
+    encoded
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+// Integers are encoded big-endian without leading zeros; zero is the empty string
+fn rlp_uint(value: u128) -> Vec<u8> {
+    rlp_bytes(strip_leading_zeros(&value.to_be_bytes()))
+}
+
+fn rlp_scalar(bytes: &[u8]) -> Vec<u8> {
+    rlp_bytes(strip_leading_zeros(bytes))
+}
+
+fn decode_hex_field(field: &str, value: &str, expected_len: Option<usize>) -> Result<Vec<u8>> {
+    let digits = value.trim().strip_prefix("0x").unwrap_or(value.trim());
+    let bytes = hex::decode(digits).map_err(|_| anyhow::anyhow!("{} is not valid hex: '{}'", field, value))?;
+    if let Some(expected_len) = expected_len
+        && bytes.len() != expected_len
+    {
+        anyhow::bail!("{} must be {} bytes, got {}", field, expected_len, bytes.len());
+    }
+    Ok(bytes)
+}
+
+// Transaction fields checked and decoded, ready to be encoded
+struct PreparedTransaction {
+    tx_type: TxType,
+    chain_id: Option<u64>,
+    v_offset: u64, // v of a signature with even y; odd y adds one
+    fields: Vec<Vec<u8>>, // RLP items up to and including the access list
+}
+
+// First byte of v: 27 for legacy transactions, chain_id * 2 + 35 under EIP-155,
+// the bare y-parity for typed ones. None if v would not fit a u64.
+fn v_offset(tx_type: TxType, chain_id: Option<u64>) -> Option<u64> {
+    let offset = match (tx_type, chain_id) {
+        (TxType::Legacy, Some(chain_id)) => chain_id.checked_mul(2)?.checked_add(35)?,
+        (TxType::Legacy, None) => 27,
+        _ => 0,
+    };
+    offset.checked_add(1).map(|_| offset)
+}
+
+impl EthTransaction {
+    fn resolved_type(&self) -> TxType {
+        match self.tx_type {
+            Some(tx_type) => tx_type,
+            None if self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some() => TxType::Eip1559,
+            None if !self.access_list.is_empty() => TxType::Eip2930,
+            None => TxType::Legacy,
+        }
+    }
+
+    fn prepare(&self) -> Result<PreparedTransaction> {
+        let tx_type = self.resolved_type();
+        let to = match &self.to {
+            Some(to) if !to.trim().is_empty() => decode_hex_field("to", to, Some(20))?,
+            _ => vec![],
+        };
+        let data = match &self.data {
+            Some(data) => decode_hex_field("data", data, None)?,
+            None => vec![],
+        };
+
+        let fee_fields = match tx_type {
+            TxType::Legacy | TxType::Eip2930 => {
+                if self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some() {
+                    anyhow::bail!("{} transactions take gas_price, not EIP-1559 fees", tx_type.name());
+                }
+                let gas_price = self
+                    .gas_price
+                    .ok_or_else(|| anyhow::anyhow!("{} transactions need gas_price", tx_type.name()))?;
+                vec![rlp_uint(gas_price)]
+            }
+            TxType::Eip1559 => {
+                if self.gas_price.is_some() {
+                    anyhow::bail!("eip1559 transactions take max_fee_per_gas and max_priority_fee_per_gas, not gas_price");
+                }
+                let (Some(max_priority_fee), Some(max_fee)) = (self.max_priority_fee_per_gas, self.max_fee_per_gas) else {
+                    anyhow::bail!("eip1559 transactions need max_fee_per_gas and max_priority_fee_per_gas");
+                };
+                if max_priority_fee > max_fee {
+                    anyhow::bail!("max_priority_fee_per_gas exceeds max_fee_per_gas");
+                }
+                vec![rlp_uint(max_priority_fee), rlp_uint(max_fee)]
+            }
+        };
+
+        let mut fields = Vec::new();
+        if tx_type != TxType::Legacy {
+            let chain_id = self
+                .chain_id
+                .ok_or_else(|| anyhow::anyhow!("{} transactions need chain_id", tx_type.name()))?;
+            fields.push(rlp_uint(chain_id as u128));
+        }
+        fields.push(rlp_uint(self.nonce as u128));
+        fields.extend(fee_fields);
+        fields.push(rlp_uint(self.gas_limit as u128));
+        fields.push(rlp_bytes(&to));
+        fields.push(rlp_uint(self.value));
+        fields.push(rlp_bytes(&data));
+
+        if tx_type == TxType::Legacy {
+            if !self.access_list.is_empty() {
+                anyhow::bail!("legacy transactions can't carry an access list");
+            }
+        } else {
+            let mut access_list = Vec::with_capacity(self.access_list.len());
+            for item in &self.access_list {
+                let address = decode_hex_field("access_list address", &item.address, Some(20))?;
+                let storage_keys = item
+                    .storage_keys
+                    .iter()
+                    .map(|key| decode_hex_field("access_list storage key", key, Some(32)).map(|key| rlp_bytes(&key)))
+                    .collect::<Result<Vec<_>>>()?;
+                access_list.push(rlp_list(&[rlp_bytes(&address), rlp_list(&storage_keys)]));
+            }
+            fields.push(rlp_list(&access_list));
+        }
+
+        let v_offset = v_offset(tx_type, self.chain_id)
+            .ok_or_else(|| anyhow::anyhow!("chain_id {} is too large for EIP-155", self.chain_id.unwrap_or_default()))?;
+
+        Ok(PreparedTransaction { tx_type, chain_id: self.chain_id, v_offset, fields })
+    }
+}
+
+impl PreparedTransaction {
+    fn envelope(&self, payload: Vec<u8>) -> Vec<u8> {
+        match self.tx_type {
+            TxType::Legacy => payload,
+            TxType::Eip2930 => [vec![0x01], payload].concat(),
+            TxType::Eip1559 => [vec![0x02], payload].concat(),
+        }
+    }
+
+    // The bytes whose Keccak-256 hash is signed
+    fn signing_payload(&self) -> Vec<u8> {
+        let mut fields = self.fields.clone();
+        // EIP-155: legacy transactions commit to the chain ID through (chain_id, 0, 0)
+        if self.tx_type == TxType::Legacy
+            && let Some(chain_id) = self.chain_id
+        {
+            fields.extend([rlp_uint(chain_id as u128), rlp_uint(0), rlp_uint(0)]);
+        }
+        self.envelope(rlp_list(&fields))
+    }
+
+    // v_offset leaves room for the odd case, checked in prepare()
+    fn v(&self, y_odd: bool) -> u64 {
+        self.v_offset + y_odd as u64
+    }
+
+    fn signed(&self, v: u64, r: &[u8], s: &[u8]) -> Vec<u8> {
+        let mut fields = self.fields.clone();
+        fields.extend([rlp_uint(v as u128), rlp_scalar(r), rlp_scalar(s)]);
+        self.envelope(rlp_list(&fields))
+    }
+}
+
+// Signs an Ethereum transaction with a wallet key and returns it raw-encoded (POST)
+pub async fn sign_transaction(
+    auth: crate::BasicAuth,
+    Json(request): Json<SignTransactionRequest>,
+) -> (StatusCode, ResponseJson<SignTransactionResponse>) {
+    tracing::info!(
+        key_id = %request.key_id,
+        path = ?request.path,
+        tx_type = ?request.transaction.tx_type,
+        chain_id = ?request.transaction.chain_id,
+        nonce = request.transaction.nonce,
+        to = ?request.transaction.to,
+        "⛓️ Starting Ethereum transaction signing"
+    );
+    let start_time = std::time::Instant::now();
+
+    let key_path = match crate::sign::resolve_key_path(&auth, &request.key_id, request.path.as_deref(), request.child_index) {
+        Ok(key_path) => key_path,
+        Err(e) => {
+            tracing::error!(key_id = %request.key_id, error = %e, "❌ Invalid signing key selection");
+            let status = if crate::wallets::is_wallet_not_found(&e) {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            return (status, ResponseJson(SignTransactionResponse::failure(format!("Signing failed: {}", e))));
+        }
+    };
+
+    let prepared = match request.transaction.prepare() {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, error = %e, "⚠️ Invalid Ethereum transaction");
+            return (
+                StatusCode::BAD_REQUEST,
+                ResponseJson(SignTransactionResponse::failure(format!("Invalid transaction: {}", e))),
+            );
+        }
+    };
+    let payload = prepared.signing_payload();
+
+    let value = request.transaction.value.to_string();
+    let context = crate::policy::SigningContext {
+        key_id: &request.key_id,
+        path: &key_path,
+        format: crate::policy::MessageFormat::Hex,
+        destination: request.transaction.to.as_deref(),
+        value: Some(&value),
+    };
+    let evaluation = match crate::sign::check_policy(&auth, &context, &payload) {
+        Ok(evaluation) => evaluation,
+        Err(refusal) => {
+            return (
+                StatusCode::FORBIDDEN,
+                ResponseJson(SignTransactionResponse::failure(format!("Signing refused: {}", refusal))),
+            );
+        }
+    };
+    if evaluation.required_approvals > 0 {
+        let pending = crate::approvals::SigningRequest {
+            message: format!("0x{}", hex::encode(&payload)),
+            transaction: Some(crate::approvals::PendingTransaction::Ethereum { transaction: request.transaction.clone() }),
+            participants: request.participants.clone(),
+            destination: request.transaction.to.clone(),
+            value: Some(value),
+            ..crate::approvals::SigningRequest::new(&auth, &request.key_id, &key_path, &evaluation)
+        };
+        return match crate::approvals::submit_request(&auth, pending) {
+            Ok(pending) => (StatusCode::ACCEPTED, ResponseJson(SignTransactionResponse {
+                success: true,
+                tx_type: Some(prepared.tx_type.name().to_string()),
+                request_id: Some(pending.id.clone()),
+                ..SignTransactionResponse::failure(format!(
+                    "Signing request {} needs {} approvals before {}",
+                    pending.id, pending.required_approvals, pending.expires_at
+                ))
+            })),
+            Err(e) => {
+                tracing::error!(key_id = %request.key_id, error = %e, "❌ Failed to store signing request");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ResponseJson(SignTransactionResponse::failure(format!("Failed to store signing request: {}", e))),
+                )
+            }
+        };
+    }
+
+    match sign_payload(&request.key_id, &key_path, &payload, request.participants.as_deref()).await {
+        Ok((y_odd, r, s, signers)) => {
+            let v = prepared.v(y_odd);
+            let raw_transaction = prepared.signed(v, &r, &s);
+            let transaction_hash = Keccak256::digest(&raw_transaction);
+
+            tracing::info!(
+                key_id = %request.key_id,
+                path = %key_path,
+                tx_type = prepared.tx_type.name(),
+                transaction_hash = %hex::encode(transaction_hash),
+                v = v,
+                duration_ms = start_time.elapsed().as_millis(),
+                signers = ?signers,
+                "✅ Ethereum transaction signed"
+            );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "sign_transaction", "success")
+                    .key_id(&request.key_id)
+                    .path(&key_path)
+                    .message(&payload)
+                    .detail(format!("{} transaction 0x{}", prepared.tx_type.name(), hex::encode(transaction_hash))),
+            );
+            (StatusCode::OK, ResponseJson(SignTransactionResponse {
+                success: true,
+                message: format!("Signed {} transaction", prepared.tx_type.name()),
+                tx_type: Some(prepared.tx_type.name().to_string()),
+                raw_transaction: format!("0x{}", hex::encode(&raw_transaction)),
+                transaction_hash: format!("0x{}", hex::encode(transaction_hash)),
+                r: format!("0x{}", hex::encode(r)),
+                s: format!("0x{}", hex::encode(s)),
+                v,
+                signers,
+                request_id: None,
+            }))
+        }
+        Err(e) => {
+            tracing::error!(
+                key_id = %request.key_id,
+                error = %e,
+                duration_ms = start_time.elapsed().as_millis(),
+                "❌ Ethereum transaction signing failed"
+            );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "sign_transaction", "failed")
+                    .key_id(&request.key_id)
+                    .path(&key_path)
+                    .message(&payload)
+                    .detail(e.to_string()),
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(SignTransactionResponse::failure(format!("Signing failed: {}", e))),
+            )
+        }
+    }
+}
+
+// Signs the transaction of an approved signing request; the signature is r||s in hex
+pub async fn sign_approved(
+    request: &crate::approvals::SigningRequest,
+    transaction: &EthTransaction,
+) -> Result<crate::approvals::ApprovedSignature> {
+    let prepared = transaction.prepare()?;
+    let (y_odd, r, s, signers) =
+        sign_payload(&request.key_id, &request.path, &prepared.signing_payload(), request.participants.as_deref()).await?;
+    let raw_transaction = prepared.signed(prepared.v(y_odd), &r, &s);
+    Ok(crate::approvals::ApprovedSignature {
+        signature: Some(hex::encode([r, s].concat())),
+        recovery_id: Some(y_odd as u8),
+        signers,
+        signed_transaction: Some(format!("0x{}", hex::encode(raw_transaction))),
+    })
+}
+
+// Runs the TSS protocol over Keccak256(payload) and returns (y parity, r, s, signers)
+async fn sign_payload(
+    key_id: &str,
+    key_path: &str,
+    payload: &[u8],
+    participants: Option<&[String]>,
+) -> Result<(bool, Vec<u8>, Vec<u8>, Vec<String>)> {
+    let signed = crate::sign::run_tss_sign(key_id, crate::sign::SignTarget::Message(payload), key_path, participants).await?;
+    let (r, s) = signed.signature.split_bytes();
+    Ok((signed.recovery_id.is_y_odd(), r.to_vec(), s.to_vec(), signed.signers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    // A signed transaction: the request fields, its signature and the expected encoding
+    struct Fixture {
+        transaction: serde_json::Value,
+        y_odd: bool,
+        r: &'static str,
+        s: &'static str,
+        v: u64,
+        raw: &'static str,
+        hash: Option<&'static str>,
+        sender: &'static str,
+    }
+
+    fn prepare(transaction: &serde_json::Value) -> Result<PreparedTransaction> {
+        serde_json::from_value::<EthTransaction>(transaction.clone())?.prepare()
+    }
+
+    fn sender(prepared: &PreparedTransaction, fixture: &Fixture) -> String {
+        let signature = Signature::from_scalars(
+            <[u8; 32]>::try_from(hex::decode(fixture.r).unwrap()).unwrap(),
+            <[u8; 32]>::try_from(hex::decode(fixture.s).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let digest = Keccak256::digest(prepared.signing_payload());
+        let key = VerifyingKey::recover_from_prehash(&digest, &signature, RecoveryId::new(fixture.y_odd, false)).unwrap();
+        crate::addresses::KeyAddresses::from_public_key(key.to_encoded_point(false).as_bytes())
+            .unwrap()
+            .ethereum
+    }
+
+    fn check(fixture: Fixture) {
+        let prepared = prepare(&fixture.transaction).unwrap();
+        let v = prepared.v(fixture.y_odd);
+        assert_eq!(v, fixture.v);
+
+        let raw = prepared.signed(v, &hex::decode(fixture.r).unwrap(), &hex::decode(fixture.s).unwrap());
+        assert_eq!(hex::encode(&raw), fixture.raw);
+        if let Some(hash) = fixture.hash {
+            assert_eq!(hex::encode(Keccak256::digest(&raw)), hash);
+        }
+        // Recovering the known sender checks the signing payload too
+        assert!(sender(&prepared, &fixture).eq_ignore_ascii_case(fixture.sender));
+    }
+
+    // EIP-155 example: nonce 9, 20 gwei, 1 ether to 0x3535...35 on chain 1
+    #[test]
+    fn legacy_eip155_example() {
+        let transaction = serde_json::json!({
+            "type": "legacy",
+            "chain_id": 1,
+            "nonce": 9,
+            "gas_price": "20000000000",
+            "gas_limit": 21000,
+            "to": "0x3535353535353535353535353535353535353535",
+            "value": "1000000000000000000",
+        });
+        let prepared = prepare(&transaction).unwrap();
+        assert_eq!(
+            hex::encode(prepared.signing_payload()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(Keccak256::digest(prepared.signing_payload())),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+
+        check(Fixture {
+            transaction,
+            y_odd: false,
+            r: "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+            s: "67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+            v: 37,
+            raw: "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+            hash: None,
+            // Address of the example's private key 0x4646...46
+            sender: "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F",
+        });
+    }
+
+    // Mainnet transaction 0x280cde7c...6fc4
+    #[test]
+    fn legacy_eip155_mainnet_transaction() {
+        check(Fixture {
+            transaction: serde_json::json!({
+                "chain_id": 1,
+                "nonce": "0x078b",
+                "gas_price": "0x05d21dba00",
+                "gas": "0x022ef1",
+                "to": "0x7a250d5630b4cf539739df2c5dacb4c659f2488d",
+                "value": "0x0c46549a521b13d8",
+                "input": "0x7ff36ab50000000000000000000000000000000000000000000066ab5a608bd00a23f2fe000000000000000000000000000000000000000000000000000000000000008000000000000000000000000048c04ed5691981c42154c6167398f95e8f38a7ff00000000000000000000000000000000000000000000000000000000632ceac70000000000000000000000000000000000000000000000000000000000000002000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc20000000000000000000000006c6ee5e31d828de241282b9606c8e98ea48526e2",
+            }),
+            y_odd: false,
+            r: "c9077369501641a92ef7399ff81c21639ed4fd8fc69cb793cfa1dbfab342e10a",
+            s: "615facb2f1bcf3274a354cfe384a38d0cc008a11c2dd23a69111bc6930ba27a8",
+            v: 37,
+            raw: "f9015482078b8505d21dba0083022ef1947a250d5630b4cf539739df2c5dacb4c659f2488d880c46549a521b13d8b8e47ff36ab50000000000000000000000000000000000000000000066ab5a608bd00a23f2fe000000000000000000000000000000000000000000000000000000000000008000000000000000000000000048c04ed5691981c42154c6167398f95e8f38a7ff00000000000000000000000000000000000000000000000000000000632ceac70000000000000000000000000000000000000000000000000000000000000002000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc20000000000000000000000006c6ee5e31d828de241282b9606c8e98ea48526e225a0c9077369501641a92ef7399ff81c21639ed4fd8fc69cb793cfa1dbfab342e10aa0615facb2f1bcf3274a354cfe384a38d0cc008a11c2dd23a69111bc6930ba27a8",
+            hash: Some("280cde7cdefe4b188750e76c888f13bd05ce9a4d7767730feefe8a0e50ca6fc4"),
+            sender: "0xa12e1462d0ceD572f396F58B6E2D03894cD7C8a4",
+        });
+    }
+
+    // Mainnet EIP-2930 transaction with an access list
+    #[test]
+    fn eip2930_mainnet_transaction() {
+        check(Fixture {
+            transaction: serde_json::json!({
+                "type": "eip2930",
+                "chain_id": 1,
+                "nonce": 9215,
+                "gas_price": "43000000000",
+                "gas_limit": 1000000,
+                "to": "0x0000000000a8fb09af944ab3baf7a9b3e1ab29d8",
+                "value": 0,
+                "data": "0x200200001525000000000b69ffb300000000557b933a7c2c45672b610f8954a3deb39a51a8cae53ec727dbdeb9e2d5456c3be40cff031ab40a55724d5c9c618a2152e99a45649a3b8cf198321f46720b722f4ec38f99ba3bb1303258d2e816e6a95b25647e01bd0967c1b9599fa3521939871d1d0888",
+                "access_list": [
+                    { "address": "0x724d5c9c618a2152e99a45649a3b8cf198321f46" },
+                    { "address": "0x720b722f4ec38f99ba3bb1303258d2e816e6a95b" },
+                    { "address": "0x25647e01bd0967c1b9599fa3521939871d1d0888" },
+                ],
+            }),
+            y_odd: true,
+            r: "8323efae7b9993bd31a58da7924359d24b5504aa2b33194fcc5ae206e65d2e62",
+            s: "54ce201e3b4b5cd38eb17c56ee2f9111b2e164efcd57b3e70fa308a0a51f7014",
+            v: 1,
+            raw: "01f90126018223ff850a02ffee00830f4240940000000000a8fb09af944ab3baf7a9b3e1ab29d880b876200200001525000000000b69ffb300000000557b933a7c2c45672b610f8954a3deb39a51a8cae53ec727dbdeb9e2d5456c3be40cff031ab40a55724d5c9c618a2152e99a45649a3b8cf198321f46720b722f4ec38f99ba3bb1303258d2e816e6a95b25647e01bd0967c1b9599fa3521939871d1d0888f845d694724d5c9c618a2152e99a45649a3b8cf198321f46c0d694720b722f4ec38f99ba3bb1303258d2e816e6a95bc0d69425647e01bd0967c1b9599fa3521939871d1d0888c001a08323efae7b9993bd31a58da7924359d24b5504aa2b33194fcc5ae206e65d2e62a054ce201e3b4b5cd38eb17c56ee2f9111b2e164efcd57b3e70fa308a0a51f7014",
+            hash: None,
+            sender: "0xe9c790e8fde820ded558a4771b72eec916c04763",
+        });
+    }
+
+    // Mainnet transaction 0xce4dc6d7...ab31
+    #[test]
+    fn eip1559_mainnet_transaction() {
+        check(Fixture {
+            transaction: serde_json::json!({
+                "chain_id": 1,
+                "nonce": 2,
+                "max_priority_fee_per_gas": "0x3b9aca00",
+                "max_fee_per_gas": "0x029e7822d6",
+                "gas_limit": "0x98f0",
+                "to": "0xd9e1459a7a482635700cbc20bbaf52d495ab9c96",
+                "data": "0x1b55ba3a",
+            }),
+            y_odd: false,
+            r: "c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039",
+            s: "28ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8",
+            v: 0,
+            raw: "02f86f0102843b9aca0085029e7822d68298f094d9e1459a7a482635700cbc20bbaf52d495ab9c9680841b55ba3ac080a0c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039a028ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8",
+            hash: Some("ce4dc6d7a7549a98ee3b071b67e970879ff51b5b95d1c340bacd80fa1e1aab31"),
+            sender: "0x001e2b7dE757bA469a57bF6b23d982458a07eFcE",
+        });
+    }
+
+    #[test]
+    fn eip155_chain_ids_that_overflow_v_are_rejected() {
+        let transaction = |chain_id: u64| {
+            serde_json::json!({
+                "type": "legacy",
+                "chain_id": chain_id.to_string(),
+                "nonce": 0,
+                "gas_price": 1,
+                "gas_limit": 21000,
+            })
+        };
+        let largest = (u64::MAX - 36) / 2;
+        assert_eq!(prepare(&transaction(largest)).unwrap().v(true), u64::MAX - 1);
+        assert!(prepare(&transaction(largest + 1)).is_err());
+        assert!(prepare(&transaction(u64::MAX)).is_err());
+        // Typed transactions carry the chain ID as a field, not in v
+        let mut typed = transaction(u64::MAX);
+        typed["type"] = "eip2930".into();
+        assert_eq!(prepare(&typed).unwrap().v(true), 1);
+    }
+
+    // Pending signing requests store the transaction and sign it once approved
+    #[test]
+    fn stored_transactions_sign_the_same_payload() {
+        let transaction = serde_json::json!({
+            "chain_id": "0x1",
+            "nonce": 7,
+            "max_priority_fee_per_gas": "2000000000",
+            "max_fee_per_gas": 340282366920938463463374607431768211455u128.to_string(),
+            "gas": 21000,
+            "to": "0x3535353535353535353535353535353535353535",
+            "value": "0xde0b6b3a7640000de0b6b3a7640000",
+            "access_list": [{ "address": "0x3535353535353535353535353535353535353535", "storage_keys": [] }],
+        });
+        let parsed: EthTransaction = serde_json::from_value(transaction).unwrap();
+        let stored: EthTransaction = serde_json::from_str(&serde_json::to_string(&parsed).unwrap()).unwrap();
+        assert_eq!(stored.resolved_type(), TxType::Eip1559);
+        assert_eq!(stored.prepare().unwrap().signing_payload(), parsed.prepare().unwrap().signing_payload());
+    }
+}
@@ -1,93 +1,417 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-use axum::{extract::Json, response::Json as ResponseJson};
+use std::collections::{BTreeMap, HashMap};
+use axum::{extract::{Json, Query}, http::StatusCode, response::Json as ResponseJson};
 use anyhow::Result;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::addresses::KeyAddresses;
+use crate::session_store::{key_store, Record};
+
+/// Environment variable naming the BIP32 node the TSS root key stands in for (default `m`).
+///
+/// Threshold key shares cannot derive hardened children, so BIP44 layouts such as
+/// `m/44'/60'/0'/0/5` are served by treating the TSS root key as the hardened account
+/// node (`WAAS_HD_ROOT_PATH="m/44'/60'/0'"`) and deriving the remaining levels publicly.
+const HD_ROOT_PATH_ENV: &str = "WAAS_HD_ROOT_PATH";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DerivedKeyInfo {
-    pub child_index: u32,
+    #[serde(default)]
+    pub path: String, // Full derivation path, e.g. m/0/5
+    #[serde(default)]
+    pub parent_path: Option<String>, // None for the root key
+    #[serde(default)]
+    pub depth: u8,
+    pub child_index: u32, // Last path segment (0 for the root key)
     pub public_key_hex: String,
     pub created_at: String,
     pub label: Option<String>, // Optional user-friendly name
+    #[serde(default)]
+    pub chain_code_hex: Option<String>, // BIP32 chain code (missing for keys derived before BIP32 support)
+    #[serde(default)]
+    pub xpub: Option<String>, // Base58Check extended public key for watch-only wallets
+    #[serde(default)]
+    pub addresses: Option<KeyAddresses>, // Chain addresses (missing for keys stored before addresses were recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<DerivationScheme>, // How the key was derived from the root (None for the root key)
+}
+
+/// Derivation used for a key below the root; signing applies the matching tweak to the key shares.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivationScheme {
+    /// BIP32 CKDpub along the key's path.
+    Bip32,
+    /// `HMAC-SHA256(chain code, root key || index)` from before BIP32 support; direct children of the root only.
+    LegacyHmacSha256,
+}
+
+impl DerivedKeyInfo {
+    /// Scheme the key was derived with. Keys stored before the scheme was recorded are BIP32
+    /// keys when they carry a chain code, since the HMAC-SHA256 scheme never produced one.
+    pub fn derivation_scheme(&self) -> DerivationScheme {
+        match (self.scheme, &self.chain_code_hex) {
+            (Some(scheme), _) => scheme,
+            (None, Some(_)) => DerivationScheme::Bip32,
+            (None, None) => DerivationScheme::LegacyHmacSha256,
+        }
+    }
 }
 
+/// HD keys organised as a tree keyed by derivation path.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HdKeyStore {
-    pub root_key: Option<DerivedKeyInfo>,
-    pub derived_keys: HashMap<u32, DerivedKeyInfo>,
+    /// Wallet the keys belong to; implied by where the store is saved.
+    #[serde(skip)]
+    pub key_id: String,
+    pub root_path: String,
+    pub keys: BTreeMap<String, DerivedKeyInfo>,
+}
+
+/// Flat layout used before derivation paths were supported.
+#[derive(Deserialize)]
+struct LegacyHdKeyStore {
+    root_key: Option<DerivedKeyInfo>,
+    derived_keys: HashMap<u32, DerivedKeyInfo>,
+}
+
+/// A parsed BIP32 derivation path such as `m/44'/60'/0'/0/5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    pub fn root() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Parse `m/...` paths; hardened segments may be written as `44'`, `44h` or `44H`.
+    pub fn parse(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let mut segments = path.split('/');
+        
+        match segments.next() {
+            Some("m") | Some("M") => {},
+            _ => anyhow::bail!("Invalid derivation path '{}': paths must start with 'm'", path),
+        }
+        
+        let mut indices = Vec::new();
+        for segment in segments {
+            let (number, hardened) = match segment.strip_suffix(['\'', 'h', 'H']) {
+                Some(number) => (number, true),
+                None => (segment, false),
+            };
+            let index: u32 = number.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid derivation path '{}': bad segment '{}'", path, segment))?;
+            if index >= BIP32_HARDENED_OFFSET {
+                anyhow::bail!("Invalid derivation path '{}': segment '{}' must be below 2^31", path, segment);
+            }
+            indices.push(if hardened { index + BIP32_HARDENED_OFFSET } else { index });
+        }
+        
+        if indices.len() > u8::MAX as usize {
+            anyhow::bail!("Invalid derivation path '{}': BIP32 supports at most 255 levels", path);
+        }
+        
+        Ok(Self(indices))
+    }
+
+    pub fn segments(&self) -> &[u32] {
+        &self.0
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.0.len() as u8
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        self.0.split_last().map(|(_, parent)| Self(parent.to_vec()))
+    }
+
+    pub fn child(&self, index: u32) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(index);
+        Self(segments)
+    }
+
+    pub fn starts_with(&self, prefix: &DerivationPath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if *index >= BIP32_HARDENED_OFFSET {
+                write!(f, "/{}'", index - BIP32_HARDENED_OFFSET)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl HdKeyStore {
-    pub fn new() -> Self {
+    pub fn new(key_id: &str) -> Self {
         Self {
-            root_key: None,
-            derived_keys: HashMap::new(),
+            key_id: key_id.to_string(),
+            root_path: configured_root_path().to_string(),
+            keys: BTreeMap::new(),
         }
     }
 
-    pub fn add_root_key(&mut self, public_key_hex: String) {
-        self.root_key = Some(DerivedKeyInfo {
-            child_index: 0,
-            public_key_hex,
-            created_at: chrono::Utc::now().to_rfc3339(),
-            label: Some("Root Key".to_string()),
-        });
+    pub fn root_path(&self) -> DerivationPath {
+        DerivationPath::parse(&self.root_path).unwrap_or_else(|_| DerivationPath::root())
+    }
+
+    pub fn add_root_key(&mut self, public_key_hex: String, chain_code_hex: String, xpub: String) {
+        let root_path = self.root_path();
+        self.add_key(&root_path, public_key_hex, chain_code_hex, xpub, Some("Root Key".to_string()));
     }
 
-    pub fn add_derived_key(&mut self, child_index: u32, public_key_hex: String, label: Option<String>) {
-        self.derived_keys.insert(child_index, DerivedKeyInfo {
-            child_index,
+    pub fn add_key(&mut self, path: &DerivationPath, public_key_hex: String, chain_code_hex: String, xpub: String, label: Option<String>) {
+        let is_root = *path == self.root_path();
+        let addresses = key_addresses(path, &public_key_hex);
+        self.keys.insert(path.to_string(), DerivedKeyInfo {
+            path: path.to_string(),
+            parent_path: if is_root { None } else { path.parent().map(|parent| parent.to_string()) },
+            depth: path.depth(),
+            child_index: if is_root { 0 } else { path.segments().last().copied().unwrap_or(0) },
             public_key_hex,
             created_at: chrono::Utc::now().to_rfc3339(),
             label,
+            chain_code_hex: Some(chain_code_hex),
+            xpub: Some(xpub),
+            addresses,
+            scheme: (!is_root).then_some(DerivationScheme::Bip32),
         });
     }
 
-    pub fn remove_key(&mut self, child_index: u32) -> bool {
-        if child_index == 0 {
-            let had_root = self.root_key.is_some();
-            self.root_key = None;
-            had_root
-        } else {
-            self.derived_keys.remove(&child_index).is_some()
+    /// Record the scheme of keys stored before it was tracked; returns whether any were marked.
+    pub fn mark_derivation_schemes(&mut self) -> bool {
+        let mut marked = false;
+        for key in self.keys.values_mut().filter(|key| key.path != self.root_path && key.scheme.is_none()) {
+            key.scheme = Some(key.derivation_scheme());
+            marked = true;
         }
+        marked
     }
 
-    pub fn get_key(&self, child_index: u32) -> Option<&DerivedKeyInfo> {
-        if child_index == 0 {
-            self.root_key.as_ref()
-        } else {
-            self.derived_keys.get(&child_index)
+    /// Record addresses for keys stored before they were derived; returns whether any were added.
+    pub fn fill_missing_addresses(&mut self) -> bool {
+        let mut filled = false;
+        for key in self.keys.values_mut().filter(|key| key.addresses.is_none()) {
+            let Ok(path) = DerivationPath::parse(&key.path) else {
+                continue;
+            };
+            key.addresses = key_addresses(&path, &key.public_key_hex);
+            filled |= key.addresses.is_some();
         }
+        filled
     }
 
-    pub fn list_all_keys(&self) -> Vec<&DerivedKeyInfo> {
-        let mut keys = Vec::new();
-        if let Some(ref root) = self.root_key {
-            keys.push(root);
+    pub fn remove_key(&mut self, path: &str) -> bool {
+        self.keys.remove(path).is_some()
+    }
+
+    pub fn get_key(&self, path: &str) -> Option<&DerivedKeyInfo> {
+        self.keys.get(path)
+    }
+
+    pub fn root_key(&self) -> Option<&DerivedKeyInfo> {
+        self.keys.get(&self.root_path)
+    }
+
+    pub fn is_root(&self, path: &str) -> bool {
+        path == self.root_path
+    }
+
+    /// Canonical path for a request that names a key either by path or by a child index under the root.
+    pub fn resolve_path(&self, path: Option<&str>, child_index: Option<u32>) -> Result<String> {
+        match (path, child_index) {
+            (Some(path), _) => {
+                let path = DerivationPath::parse(path)?;
+                self.relative_segments(&path)?;
+                Ok(path.to_string())
+            },
+            (None, None) | (None, Some(0)) => Ok(self.root_path.clone()),
+            (None, Some(index)) => {
+                let path = self.root_path().child(index);
+                self.relative_segments(&path)?;
+                Ok(path.to_string())
+            },
         }
-        keys.extend(self.derived_keys.values());
-        keys.sort_by_key(|k| k.child_index);
+    }
+
+    /// Segments of `path` below the TSS root key, all of which must be non-hardened.
+    pub fn relative_segments(&self, path: &DerivationPath) -> Result<Vec<u32>> {
+        let root_path = self.root_path();
+        if !path.starts_with(&root_path) {
+            anyhow::bail!(
+                "Path {} is not below the TSS root key at {}. Set {} to the account node this wallet serves.",
+                path, root_path, HD_ROOT_PATH_ENV
+            );
+        }
+        
+        let relative = path.segments()[root_path.segments().len()..].to_vec();
+        if let Some(hardened) = relative.iter().find(|index| **index >= BIP32_HARDENED_OFFSET) {
+            anyhow::bail!(
+                "Hardened segment {}' in path {} is not supported: threshold key shares can only derive non-hardened children because no single party holds the parent private key. Configure {} so the TSS root key stands in for the hardened prefix (current root: {}).",
+                hardened - BIP32_HARDENED_OFFSET, path, HD_ROOT_PATH_ENV, root_path
+            );
+        }
+        
+        Ok(relative)
+    }
+
+    pub fn list_all_keys(&self) -> Vec<&DerivedKeyInfo> {
+        let mut keys: Vec<&DerivedKeyInfo> = self.keys.values().collect();
+        keys.sort_by_cached_key(|k| DerivationPath::parse(&k.path).map(|p| p.0).unwrap_or_default());
         keys
     }
+
+    /// The key at `path` and every stored key below it.
+    pub fn list_subtree(&self, path: &DerivationPath) -> Vec<&DerivedKeyInfo> {
+        self.list_all_keys()
+            .into_iter()
+            .filter(|k| DerivationPath::parse(&k.path).is_ok_and(|p| p.starts_with(path)))
+            .collect()
+    }
+
+    /// Lowest free, valid child index directly below `parent`.
+    fn next_child_index(&self, parent: &DerivationPath) -> Result<(DerivationPath, DerivedChildKey)> {
+        let mut next_index = if *parent == self.root_path() { 1u32 } else { 0u32 };
+        loop {
+            let path = parent.child(next_index);
+            if self.get_key(&path.to_string()).is_some() {
+                next_index += 1;
+                continue;
+            }
+            match derive_key_real(self, &path) {
+                Ok(child_key) => return Ok((path, child_key)),
+                Err(e) if e.downcast_ref::<InvalidChildKey>().is_some() => {
+                    tracing::warn!(
+                        path = %path,
+                        "⚠️ BIP32 derivation produced an invalid key, skipping to next index"
+                    );
+                    next_index += 1;
+                },
+                Err(e) => return Err(e),
+            }
+            if next_index >= BIP32_HARDENED_OFFSET {
+                anyhow::bail!("No free non-hardened child index left below {}", parent);
+            }
+        }
+    }
+}
+
+fn key_addresses(path: &DerivationPath, public_key_hex: &str) -> Option<KeyAddresses> {
+    match KeyAddresses::from_public_key_hex(public_key_hex) {
+        Ok(addresses) => Some(addresses),
+        Err(e) => {
+            tracing::warn!(
+                path = %path,
+                error = %e,
+                "⚠️ Could not compute addresses for HD key"
+            );
+            None
+        }
+    }
+}
+
+fn configured_root_path() -> DerivationPath {
+    match std::env::var(HD_ROOT_PATH_ENV) {
+        Ok(path) => DerivationPath::parse(&path).unwrap_or_else(|e| {
+            tracing::warn!(
+                error = %e,
+                env = HD_ROOT_PATH_ENV,
+                "⚠️ Ignoring invalid HD root path, using m"
+            );
+            DerivationPath::root()
+        }),
+        Err(_) => DerivationPath::root(),
+    }
 }
 
 // Storage functions
-pub fn load_hd_key_store() -> Result<HdKeyStore> {
-    use std::fs;
+pub fn load_hd_key_store(key_id: &str) -> Result<HdKeyStore> {
+    let Some(bytes) = key_store()?.get(key_id, Record::HdKeys)? else {
+        return Ok(HdKeyStore::new(key_id));
+    };
+    let data = String::from_utf8(bytes)?;
     
-    if let Ok(data) = fs::read_to_string("hd_keys.json") {
-        Ok(serde_json::from_str(&data)?)
-    } else {
-        Ok(HdKeyStore::new())
+    if let Ok(mut store) = serde_json::from_str::<HdKeyStore>(&data) {
+        store.key_id = key_id.to_string();
+        return Ok(store);
+    }
+    
+    // Convert the flat child-index layout into a path-keyed tree; migrate_legacy_hd_key_stores
+    // saves the result at startup
+    let legacy: LegacyHdKeyStore = serde_json::from_str(&data)?;
+    let mut store = HdKeyStore::new(key_id);
+    let root_path = store.root_path();
+    
+    tracing::info!(
+        legacy_keys = legacy.derived_keys.len(),
+        root_path = %root_path,
+        "🔄 Migrating flat HD key store to derivation paths"
+    );
+    
+    let legacy_keys = legacy.root_key
+        .map(|root| (root_path.clone(), root))
+        .into_iter()
+        .chain(legacy.derived_keys.into_values().map(|key| (root_path.child(key.child_index), key)));
+    for (path, mut key) in legacy_keys {
+        let is_root = path == root_path;
+        key.path = path.to_string();
+        key.parent_path = if is_root { None } else { path.parent().map(|parent| parent.to_string()) };
+        key.depth = path.depth();
+        // Depth and parent fingerprint may differ under the new root, so re-derive on next use
+        key.xpub = None;
+        if !is_root {
+            key.scheme = Some(key.derivation_scheme());
+        }
+        store.keys.insert(path.to_string(), key);
+    }
+    
+    Ok(store)
+}
+
+/// Startup migration of HD key stores still in the flat child-index layout. Each converted
+/// store is saved before the `public_key_child_<index>.bin` files the old layout kept in
+/// `legacy_dir` are removed, so a failed save leaves the legacy data untouched.
+pub fn migrate_legacy_hd_key_stores(legacy_dir: &std::path::Path) -> Result<()> {
+    let keys = key_store()?;
+    for key_id in keys.key_ids()? {
+        let Some(bytes) = keys.get(&key_id, Record::HdKeys)? else {
+            continue;
+        };
+        if serde_json::from_slice::<HdKeyStore>(&bytes).is_ok() {
+            continue;
+        }
+
+        let store = load_hd_key_store(&key_id)
+            .map_err(|e| anyhow::anyhow!("Failed to read HD key store of wallet {}: {}", key_id, e))?;
+        save_hd_key_store(&store)
+            .map_err(|e| anyhow::anyhow!("Failed to save migrated HD key store of wallet {}: {}", key_id, e))?;
+        tracing::info!(key_id = %key_id, keys = store.keys.len(), "✅ Saved HD key store with derivation paths");
+
+        // The public keys are kept in the store, so the old per-child files are redundant
+        for key in store.keys.values().filter(|key| key.parent_path.is_some()) {
+            let file = legacy_dir.join(format!("public_key_child_{}.bin", key.child_index));
+            match std::fs::remove_file(&file) {
+                Ok(()) => tracing::debug!(file = %file.display(), "🗑️ Removed legacy child public key file"),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => anyhow::bail!("Failed to remove legacy child public key file {}: {}", file.display(), e),
+            }
+        }
     }
+    Ok(())
 }
 
 pub fn save_hd_key_store(store: &HdKeyStore) -> Result<()> {
-    use std::fs;
-    
     let data = serde_json::to_string_pretty(store)?;
-    fs::write("hd_keys.json", data)?;
+    key_store()?.put(&store.key_id, Record::HdKeys, data.as_bytes())?;
     Ok(())
 }
 
@@ -96,54 +420,84 @@ pub fn save_hd_key_store(store: &HdKeyStore) -> Result<()> {
 pub struct DeriveKeyResponse {
     pub success: bool,
     pub message: String,
+    pub path: Option<String>,
     pub child_index: Option<u32>,
     pub public_key: Option<String>,
+    pub chain_code: Option<String>,
+    pub xpub: Option<String>,
     pub label: Option<String>,
+    pub addresses: Option<KeyAddresses>,
 }
 
 #[derive(Serialize)]
 pub struct ListKeysResponse {
     pub success: bool,
+    pub root_path: Option<String>,
     pub keys: Vec<DerivedKeyInfo>,
 }
 
+#[derive(Deserialize)]
+pub struct ListKeysQuery {
+    pub key_id: String,
+    pub path: Option<String>, // If set, only list this key and its descendants
+}
+
 #[derive(Deserialize)]
 pub struct DeriveKeyRequest {
-    pub child_index: Option<u32>, // If None, auto-generate next available
+    pub key_id: String,
+    pub path: Option<String>, // Full derivation path, e.g. m/0/5
+    pub child_index: Option<u32>, // Shorthand for a direct child of the root key
+    pub parent_path: Option<String>, // If neither is set, auto-generate next available below this parent (default: root)
     pub label: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct DeleteKeyRequest {
-    pub child_index: u32,
+    pub key_id: String,
+    pub path: Option<String>,
+    pub child_index: Option<u32>,
 }
 
 #[derive(Serialize)]
 pub struct DeleteKeyResponse {
     pub success: bool,
     pub message: String,
+    pub deleted_path: Option<String>,
     pub deleted_child_index: Option<u32>,
 }
 
 // Handler functions for API endpoints
-pub async fn derive_key(Json(request): Json<DeriveKeyRequest>) -> ResponseJson<DeriveKeyResponse> {
+pub async fn derive_key(
+    auth: crate::BasicAuth,
+    Json(request): Json<DeriveKeyRequest>,
+) -> Result<ResponseJson<DeriveKeyResponse>, (StatusCode, ResponseJson<DeriveKeyResponse>)> {
     tracing::info!(
+        key_id = %request.key_id,
+        requested_path = ?request.path,
         requested_child_index = ?request.child_index,
+        parent_path = ?request.parent_path,
         label = ?request.label,
         "🔑 Starting child key derivation"
     );
 
     let start_time = std::time::Instant::now();
+    let key_id = request.key_id.clone();
+    let requested_path = request.path.clone();
     
-    match derive_child_key_impl(request.child_index, request.label).await {
+    match derive_child_key_impl(&auth, request).await {
         Ok(response) => {
             let duration = start_time.elapsed();
             tracing::info!(
-                child_index = ?response.child_index,
+                path = ?response.path,
                 duration_ms = duration.as_millis(),
                 "✅ Child key derivation completed successfully"
             );
-            ResponseJson(response)
+            let mut event = crate::audit::AuditEvent::new(&auth, "derive", "success").key_id(&key_id);
+            if let Some(path) = &response.path {
+                event = event.path(path);
+            }
+            crate::audit::record(event);
+            Ok(ResponseJson(response))
         },
         Err(e) => {
             let duration = start_time.elapsed();
@@ -152,259 +506,644 @@ pub async fn derive_key(Json(request): Json<DeriveKeyRequest>) -> ResponseJson<D
                 duration_ms = duration.as_millis(),
                 "❌ Child key derivation failed"
             );
-            ResponseJson(DeriveKeyResponse {
+            let mut event = crate::audit::AuditEvent::new(&auth, "derive", "failed")
+                .key_id(&key_id)
+                .detail(e.to_string());
+            if let Some(path) = &requested_path {
+                event = event.path(path);
+            }
+            crate::audit::record(event);
+            let response = ResponseJson(DeriveKeyResponse {
                 success: false,
                 message: format!("Key derivation failed: {}", e),
+                path: None,
                 child_index: None,
                 public_key: None,
+                chain_code: None,
+                xpub: None,
                 label: None,
-            })
+                addresses: None,
+            });
+            if crate::wallets::is_wallet_not_found(&e) {
+                return Err((StatusCode::NOT_FOUND, response));
+            }
+            Ok(response)
         }
     }
 }
 
-pub async fn list_keys(_auth: crate::BasicAuth) -> ResponseJson<ListKeysResponse> {
-    tracing::debug!("📋 Listing all derived keys");
+pub async fn list_keys(
+    auth: crate::BasicAuth,
+    Query(query): Query<ListKeysQuery>,
+) -> Result<ResponseJson<ListKeysResponse>, (StatusCode, ResponseJson<ListKeysResponse>)> {
+    tracing::debug!(
+        key_id = %query.key_id,
+        path = ?query.path,
+        "📋 Listing all derived keys"
+    );
     
-    match load_hd_key_store() {
-        Ok(store) => {
-            let keys: Vec<DerivedKeyInfo> = store.list_all_keys().into_iter().cloned().collect();
+    match list_keys_impl(&auth, &query.key_id, query.path.as_deref()) {
+        Ok((root_path, keys)) => {
             tracing::info!(
                 total_keys = keys.len(),
                 "📋 Retrieved key list successfully"
             );
-            ResponseJson(ListKeysResponse {
+            Ok(ResponseJson(ListKeysResponse {
                 success: true,
+                root_path: Some(root_path),
                 keys,
-            })
+            }))
         },
         Err(e) => {
             tracing::error!(
                 error = %e,
                 "❌ Failed to load HD key store"
             );
-            ResponseJson(ListKeysResponse {
+            let response = ResponseJson(ListKeysResponse {
                 success: false,
+                root_path: None,
                 keys: vec![],
-            })
+            });
+            if crate::wallets::is_wallet_not_found(&e) {
+                return Err((StatusCode::NOT_FOUND, response));
+            }
+            Ok(response)
         }
     }
 }
 
-pub async fn delete_child_key(Json(request): Json<DeleteKeyRequest>) -> ResponseJson<DeleteKeyResponse> {
+pub async fn delete_child_key(
+    auth: crate::BasicAuth,
+    Json(request): Json<DeleteKeyRequest>,
+) -> Result<ResponseJson<DeleteKeyResponse>, (StatusCode, ResponseJson<DeleteKeyResponse>)> {
     tracing::info!(
-        child_index = request.child_index,
+        key_id = %request.key_id,
+        path = ?request.path,
+        child_index = ?request.child_index,
         "🗑️ Deleting child key"
     );
 
-    match delete_child_key_impl(request.child_index) {
-        Ok(_) => {
+    match delete_child_key_impl(&auth, &request.key_id, request.path.as_deref(), request.child_index) {
+        Ok(deleted) => {
             tracing::info!(
-                child_index = request.child_index,
+                path = %deleted.path,
                 "✅ Child key deleted successfully"
             );
-            ResponseJson(DeleteKeyResponse {
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "delete_child_key", "success")
+                    .key_id(&request.key_id)
+                    .path(&deleted.path),
+            );
+            Ok(ResponseJson(DeleteKeyResponse {
                 success: true,
-                message: format!("Child key {} deleted successfully", request.child_index),
-                deleted_child_index: Some(request.child_index),
-            })
+                message: format!("Child key {} deleted successfully", deleted.path),
+                deleted_path: Some(deleted.path),
+                deleted_child_index: Some(deleted.child_index),
+            }))
         },
         Err(e) => {
             tracing::error!(
-                child_index = request.child_index,
+                path = ?request.path,
+                child_index = ?request.child_index,
                 error = %e,
                 "❌ Failed to delete child key"
             );
-            ResponseJson(DeleteKeyResponse {
+            let mut event = crate::audit::AuditEvent::new(&auth, "delete_child_key", "failed")
+                .key_id(&request.key_id)
+                .detail(e.to_string());
+            if let Some(path) = &request.path {
+                event = event.path(path);
+            }
+            crate::audit::record(event);
+            let response = ResponseJson(DeleteKeyResponse {
                 success: false,
                 message: format!("Failed to delete child key: {}", e),
+                deleted_path: None,
                 deleted_child_index: None,
-            })
+            });
+            if crate::wallets::is_wallet_not_found(&e) {
+                return Err((StatusCode::NOT_FOUND, response));
+            }
+            Ok(response)
         }
     }
 }
 
 // Implementation functions
-async fn derive_child_key_impl(requested_index: Option<u32>, label: Option<String>) -> Result<DeriveKeyResponse> {
+async fn derive_child_key_impl(auth: &crate::BasicAuth, request: DeriveKeyRequest) -> Result<DeriveKeyResponse> {
     // Check if root keygen exists
-    if !crate::sign::is_keygen_completed() {
+    crate::wallets::authorize(auth, &request.key_id)?;
+    if !crate::sign::is_keygen_completed(&request.key_id) {
         anyhow::bail!("No root key found. Please generate keys first using the keygen button.");
     }
 
     // Load HD key store
-    let mut store = load_hd_key_store()?;
+    let mut store = load_hd_key_store(&request.key_id)?;
+    upgrade_legacy_keys(&mut store)?;
+    ensure_root_key(&mut store)?;
+    store.fill_missing_addresses();
     
-    // Determine child index
-    let child_index = match requested_index {
-        Some(index) => {
-            if index == 0 {
-                anyhow::bail!("Child index 0 is reserved for the root key");
-            }
-            if store.get_key(index).is_some() {
-                anyhow::bail!("Child key with index {} already exists", index);
-            }
-            index
-        },
-        None => {
-            // Auto-generate next available index
-            let existing_indices: Vec<u32> = store.derived_keys.keys().cloned().collect();
-            let mut next_index = 1u32;
-            while existing_indices.contains(&next_index) {
-                next_index += 1;
-            }
-            next_index
+    // Determine the target path and derive it with BIP32 CKDpub
+    let (path, child_key) = if request.path.is_some() || request.child_index.is_some() {
+        let path = DerivationPath::parse(&store.resolve_path(request.path.as_deref(), request.child_index)?)?;
+        if store.is_root(&path.to_string()) {
+            anyhow::bail!("Path {} is reserved for the root key", path);
+        }
+        if store.get_key(&path.to_string()).is_some() {
+            anyhow::bail!("Child key with path {} already exists", path);
         }
+        let child_key = derive_key_real(&store, &path)?;
+        (path, child_key)
+    } else {
+        let parent = match request.parent_path.as_deref() {
+            Some(parent) => DerivationPath::parse(parent)?,
+            None => store.root_path(),
+        };
+        store.relative_segments(&parent)?;
+        store.next_child_index(&parent)?
     };
 
-    // Real HD key derivation using cryptographic methods
-    let (public_key_hex, derived_key_bytes) = derive_child_key_real(child_index)?;
-    
-    // Store the actual derived public key for verification
-    store_child_public_key(child_index, &derived_key_bytes)?;
+    let public_key_hex = hex::encode(&child_key.public_key);
+    let chain_code_hex = hex::encode(child_key.chain_code);
+    let child_index = path.segments().last().copied().unwrap_or(0);
     
     // Add to store
-    store.add_derived_key(child_index, public_key_hex.clone(), label.clone());
+    store.add_key(&path, public_key_hex.clone(), chain_code_hex.clone(), child_key.xpub.clone(), request.label.clone());
     save_hd_key_store(&store)?;
-
-    // Also initialize root key if not present
-    if store.root_key.is_none() {
-        let root_public_key = get_root_public_key()?;
-        store.add_root_key(root_public_key);
-        save_hd_key_store(&store)?;
-    }
+    let addresses = store.get_key(&path.to_string()).and_then(|key| key.addresses.clone());
 
     Ok(DeriveKeyResponse {
         success: true,
-        message: format!("Child key {} derived successfully", child_index),
+        message: format!("Child key {} derived successfully", path),
+        path: Some(path.to_string()),
         child_index: Some(child_index),
         public_key: Some(public_key_hex),
-        label,
+        chain_code: Some(chain_code_hex),
+        xpub: Some(child_key.xpub),
+        label: request.label,
+        addresses,
     })
 }
 
-fn delete_child_key_impl(child_index: u32) -> Result<()> {
-    let mut store = load_hd_key_store()?;
+fn list_keys_impl(auth: &crate::BasicAuth, key_id: &str, path: Option<&str>) -> Result<(String, Vec<DerivedKeyInfo>)> {
+    crate::wallets::authorize(auth, key_id)?;
+    let mut store = load_hd_key_store(key_id)?;
+    let mut changed = false;
     
-    if child_index == 0 {
-        anyhow::bail!("Cannot delete root key using this endpoint. Use the main delete_key endpoint instead.");
+    if crate::sign::is_keygen_completed(key_id) {
+        match upgrade_legacy_keys(&mut store) {
+            Ok(upgraded) => changed = upgraded,
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "⚠️ Failed to upgrade legacy HD keys to BIP32 derivation"
+                );
+            }
+        }
     }
+    changed |= store.fill_missing_addresses();
     
-    if !store.remove_key(child_index) {
-        anyhow::bail!("Child key with index {} not found", child_index);
+    if changed && let Err(e) = save_hd_key_store(&store) {
+        tracing::warn!(
+            error = %e,
+            "⚠️ Failed to persist upgraded HD key store"
+        );
     }
     
-    save_hd_key_store(&store)?;
+    let keys = match path {
+        Some(path) => store.list_subtree(&DerivationPath::parse(path)?),
+        None => store.list_all_keys(),
+    };
+    Ok((store.root_path.clone(), keys.into_iter().cloned().collect()))
+}
+
+fn delete_child_key_impl(
+    auth: &crate::BasicAuth,
+    key_id: &str,
+    path: Option<&str>,
+    child_index: Option<u32>,
+) -> Result<DerivedKeyInfo> {
+    if path.is_none() && child_index.is_none() {
+        anyhow::bail!("Specify the path or child index of the key to delete");
+    }
     
-    // Also delete any associated storage files for this child key
-    use std::fs;
-    let _ = fs::remove_file(format!("public_key_child_{}.bin", child_index));
+    crate::wallets::authorize(auth, key_id)?;
+    let mut store = load_hd_key_store(key_id)?;
+    let path = store.resolve_path(path, child_index)?;
     
-    Ok(())
+    if store.is_root(&path) {
+        anyhow::bail!("Cannot delete root key using this endpoint. Use the main delete_key endpoint instead.");
+    }
+    
+    let Some(deleted) = store.get_key(&path).cloned() else {
+        anyhow::bail!("Child key with path {} not found", path);
+    };
+    store.remove_key(&path);
+    
+    save_hd_key_store(&store)?;
+    
+    Ok(deleted)
 }
 
-// Helper functions
-fn derive_child_key_real(child_index: u32) -> Result<(String, Vec<u8>)> {
-    // Real HD key derivation using HMAC-based key derivation
+// BIP32 public derivation (CKDpub)
+
+/// First hardened child index (2^31); CKDpub is only defined below it.
+pub const BIP32_HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Version bytes for mainnet extended public keys (`xpub...`).
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+
+/// Output of a single CKDpub step.
+#[derive(Debug, Clone)]
+pub struct Bip32ChildKey {
+    pub public_key: Vec<u8>,  // Compressed SEC1 child public key
+    pub chain_code: [u8; 32], // I_R
+    pub tweak: [u8; 32],      // I_L, the additive tweak applied to the parent key
+}
+
+/// BIP32 says to skip an index when I_L >= n or the child point is at infinity.
+#[derive(Debug)]
+pub struct InvalidChildKey(pub u32);
+
+impl std::fmt::Display for InvalidChildKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Child index {} yields an invalid key, proceed with the next index", self.0)
+    }
+}
+
+impl std::error::Error for InvalidChildKey {}
+
+/// BIP32 CKDpub: derive the non-hardened child `index` of an extended public key.
+pub fn ckd_pub(parent_public_key: &[u8], parent_chain_code: &[u8; 32], index: u32) -> Result<Bip32ChildKey> {
     use hmac::{Hmac, Mac};
-    use sha2::Sha256;
+    use sha2::Sha512;
+    use k256::{PublicKey, Scalar, ProjectivePoint};
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+    use k256::elliptic_curve::PrimeField;
+
+    if index >= BIP32_HARDENED_OFFSET {
+        anyhow::bail!("Child index {} is hardened; public derivation only supports indices below 2^31", index);
+    }
+
+    let parent = PublicKey::from_sec1_bytes(parent_public_key)
+        .map_err(|e| anyhow::anyhow!("Failed to parse parent public key: {}", e))?;
+
+    // I = HMAC-SHA512(Key = c_par, Data = ser_P(K_par) || ser_32(i))
+    type HmacSha512 = Hmac<Sha512>;
+    let mut mac = HmacSha512::new_from_slice(parent_chain_code)
+        .map_err(|_| anyhow::anyhow!("Failed to create HMAC from chain code"))?;
+    mac.update(parent.to_encoded_point(true).as_bytes());
+    mac.update(&index.to_be_bytes());
+    let i = mac.finalize().into_bytes();
+
+    let mut tweak = [0u8; 32];
+    tweak.copy_from_slice(&i[..32]);
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&i[32..]);
+
+    // parse_256(I_L) must be lower than the curve order
+    let scalar: Scalar = Option::from(Scalar::from_repr(tweak.into()))
+        .ok_or(InvalidChildKey(index))?;
+
+    // K_i = point(parse_256(I_L)) + K_par, which must not be the point at infinity
+    let child_point = ProjectivePoint::from(*parent.as_affine()) + ProjectivePoint::GENERATOR * scalar;
+    let child = PublicKey::from_affine(child_point.to_affine())
+        .map_err(|_| InvalidChildKey(index))?;
+
+    Ok(Bip32ChildKey {
+        public_key: child.to_encoded_point(true).as_bytes().to_vec(),
+        chain_code,
+        tweak,
+    })
+}
+
+/// First four bytes of HASH160(public key), used as the parent fingerprint in xpubs.
+pub fn key_fingerprint(public_key: &[u8]) -> [u8; 4] {
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+
+    let hash160 = Ripemd160::digest(Sha256::digest(public_key));
+    let mut fingerprint = [0u8; 4];
+    fingerprint.copy_from_slice(&hash160[..4]);
+    fingerprint
+}
+
+/// Serialize an extended public key as a Base58Check `xpub` string.
+pub fn encode_xpub(
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: &[u8; 32],
+    public_key: &[u8],
+) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut payload = Vec::with_capacity(82);
+    payload.extend_from_slice(&XPUB_VERSION);
+    payload.push(depth);
+    payload.extend_from_slice(&parent_fingerprint);
+    payload.extend_from_slice(&child_number.to_be_bytes());
+    payload.extend_from_slice(chain_code);
+    payload.extend_from_slice(public_key);
+
+    // Base58Check: append the first four bytes of SHA256(SHA256(payload))
+    let checksum = Sha256::digest(Sha256::digest(&payload));
+    payload.extend_from_slice(&checksum[..4]);
+    bs58::encode(payload).into_string()
+}
+
+/// Compute the additive tweak for a key below the root: the sum of I_L along the path.
+///
+/// The derived public key is `root_pk + tweak * G`, so signing with the root key shares
+/// shifted by `tweak` yields signatures that verify against the derived public key.
+/// Returns `None` for the root key itself.
+pub fn compute_path_tweak(root_public_key: &[u8], chain_code: &[u8; 32], segments: &[u32]) -> Result<Option<[u8; 32]>> {
+    if segments.is_empty() {
+        return Ok(None);
+    }
+    
+    let derivation = derive_along_path(root_public_key, chain_code, segments)?;
     
     tracing::debug!(
-        child_index = child_index,
-        "🔑 Starting real HD key derivation"
+        depth = segments.len(),
+        "🧮 Computed path tweak using BIP32 CKDpub"
     );
     
-    // Get root public key and chain code from keygen essentials
-    let (root_public_key, chain_code) = get_root_key_and_chain_code()?;
+    Ok(Some(derivation.tweak))
+}
+
+/// Result of applying CKDpub for every segment of a relative path.
+struct PathDerivation {
+    public_key: Vec<u8>,
+    chain_code: [u8; 32],
+    parent_public_key: Option<Vec<u8>>,
+    tweak: [u8; 32],
+}
+
+fn derive_along_path(root_public_key: &[u8], root_chain_code: &[u8; 32], segments: &[u32]) -> Result<PathDerivation> {
+    use k256::Scalar;
+    use k256::elliptic_curve::PrimeField;
     
+    let mut public_key = root_public_key.to_vec();
+    let mut chain_code = *root_chain_code;
+    let mut parent_public_key = None;
+    let mut tweak = Scalar::ZERO;
+    
+    for index in segments {
+        let child_key = ckd_pub(&public_key, &chain_code, *index)?;
+        // ckd_pub already rejected I_L >= n, so this conversion cannot fail
+        let step: Scalar = Option::from(Scalar::from_repr(child_key.tweak.into()))
+            .ok_or(InvalidChildKey(*index))?;
+        tweak += step;
+        parent_public_key = Some(std::mem::replace(&mut public_key, child_key.public_key));
+        chain_code = child_key.chain_code;
+    }
+    
+    Ok(PathDerivation {
+        public_key,
+        chain_code,
+        parent_public_key,
+        tweak: tweak.to_bytes().into(),
+    })
+}
+
+/// A derived key together with its serialized xpub.
+struct DerivedChildKey {
+    public_key: Vec<u8>,
+    chain_code: [u8; 32],
+    xpub: String,
+}
+
+// Helper functions
+fn derive_key_real(store: &HdKeyStore, path: &DerivationPath) -> Result<DerivedChildKey> {
     tracing::debug!(
-        root_key_size = root_public_key.len(),
-        chain_code_size = chain_code.len(),
-        "📂 Loaded root key material for derivation"
+        path = %path,
+        "🔑 Starting BIP32 HD key derivation"
     );
     
-    // Create HMAC key from chain code
-    type HmacSha256 = Hmac<Sha256>;
-    let mut mac = HmacSha256::new_from_slice(&chain_code)
-        .map_err(|_| anyhow::anyhow!("Failed to create HMAC from chain code"))?;
-    
-    // Add public key and child index to HMAC
-    mac.update(&root_public_key);
-    mac.update(&child_index.to_be_bytes());
+    let segments = store.relative_segments(path)?;
     
-    // Compute the derived key material
-    let derived_material = mac.finalize().into_bytes();
+    // Get root public key and chain code from keygen essentials
+    let (root_public_key, chain_code) = get_root_key_and_chain_code(&store.key_id)?;
     
     tracing::debug!(
-        derived_material_size = derived_material.len(),
-        "🧮 Computed derived key material using HMAC-SHA256"
+        root_key_size = root_public_key.len(),
+        chain_code_size = chain_code.len(),
+        "📂 Loaded root key material for derivation"
     );
     
-    // Split derived material: first 32 bytes for key, remaining for new chain code
-    let key_bytes = &derived_material[..32];
-    
-    // For secp256k1, we need to ensure the key is a valid scalar
-    // Convert to a proper public key by deriving from the root key
-    let derived_public_key = derive_public_key_from_material(&root_public_key, key_bytes)?;
+    // The same tweak is applied to the key shares when signing with this key
+    let derivation = derive_along_path(&root_public_key, &chain_code, &segments)?;
     
-    let public_key_hex = hex::encode(&derived_public_key);
+    // The root's own parent is above the TSS key and unknown, so its fingerprint is zero
+    let parent_fingerprint = derivation.parent_public_key
+        .as_deref()
+        .map(key_fingerprint)
+        .unwrap_or([0u8; 4]);
+    let child_number = path.segments().last().copied().unwrap_or(0);
+    let xpub = encode_xpub(path.depth(), parent_fingerprint, child_number, &derivation.chain_code, &derivation.public_key);
     
     tracing::info!(
-        child_index = child_index,
-        public_key_hex = %public_key_hex[..16],
+        path = %path,
+        public_key_hex = %hex::encode(&derivation.public_key[..8]),
         "✅ Successfully derived child key (showing first 16 hex chars)"
     );
     
-    Ok((public_key_hex, derived_public_key))
+    Ok(DerivedChildKey {
+        public_key: derivation.public_key,
+        chain_code: derivation.chain_code,
+        xpub,
+    })
 }
 
-fn derive_public_key_from_material(root_key_bytes: &[u8], key_material: &[u8]) -> Result<Vec<u8>> {
-    use k256::{PublicKey, Scalar, ProjectivePoint};
-    use k256::elliptic_curve::sec1::ToEncodedPoint;
-    use k256::elliptic_curve::PrimeField;
-    
-    tracing::debug!("🔄 Deriving public key from key material");
+/// Record the root key in the store if it is not there yet.
+fn ensure_root_key(store: &mut HdKeyStore) -> Result<()> {
+    if store.root_key().is_some() {
+        return Ok(());
+    }
     
-    // Parse root public key
-    let root_public_key = PublicKey::from_sec1_bytes(root_key_bytes)
-        .map_err(|e| anyhow::anyhow!("Failed to parse root public key: {}", e))?;
+    let root_key = derive_key_real(store, &store.root_path())?;
+    store.add_root_key(hex::encode(&root_key.public_key), hex::encode(root_key.chain_code), root_key.xpub);
+    Ok(())
+}
+
+/// Complete the metadata of keys recorded before BIP32 derivation paths were introduced.
+///
+/// Stored keys are never re-keyed or removed: a key only gains its chain code and xpub when
+/// re-deriving its path reproduces the recorded public key, and keys from the HMAC-SHA256
+/// scheme keep their public key and are signed under that scheme.
+fn upgrade_legacy_keys(store: &mut HdKeyStore) -> Result<bool> {
+    let mut changed = store.mark_derivation_schemes();
+    let pending_paths: Vec<String> = store.keys
+        .values()
+        .filter(|key| key.xpub.is_none())
+        .filter(|key| store.is_root(&key.path) || key.derivation_scheme() == DerivationScheme::Bip32)
+        .map(|key| key.path.clone())
+        .collect();
     
-    // Create scalar from key material (mod curve order)
-    let scalar_bytes: [u8; 32] = key_material.try_into()
-        .map_err(|_| anyhow::anyhow!("Key material must be 32 bytes"))?;
+    if pending_paths.is_empty() {
+        return Ok(changed);
+    }
     
-    // Create scalar from bytes using PrimeField trait
-    let scalar = Scalar::from_repr(scalar_bytes.into())
-        .into_option()
-        .ok_or_else(|| anyhow::anyhow!("Invalid scalar from key material"))?;
+    tracing::info!(
+        pending_keys = pending_paths.len(),
+        "🔄 Recording BIP32 metadata for keys stored without an xpub"
+    );
     
-    // Convert root public key to projective point
-    let root_point = ProjectivePoint::from(*root_public_key.as_affine());
+    for path in pending_paths {
+        let derived = DerivationPath::parse(&path).and_then(|parsed| derive_key_real(store, &parsed));
+        let Some(key) = store.keys.get_mut(&path) else {
+            continue;
+        };
+        match derived {
+            Ok(child_key) if hex::encode(&child_key.public_key).eq_ignore_ascii_case(&key.public_key_hex) => {
+                key.chain_code_hex = Some(hex::encode(child_key.chain_code));
+                key.xpub = Some(child_key.xpub);
+                changed = true;
+            },
+            Ok(_) => {
+                tracing::warn!(
+                    path = %path,
+                    "⚠️ Stored key does not match its BIP32 derivation, keeping it unchanged"
+                );
+            },
+            Err(e) if e.downcast_ref::<InvalidChildKey>().is_some() => {
+                tracing::warn!(
+                    path = %path,
+                    "⚠️ Stored key is invalid under BIP32, keeping it unchanged"
+                );
+            },
+            Err(e) => return Err(e),
+        }
+    }
     
-    // Derive child public key: child_pk = root_pk + scalar * G
-    let derived_point = root_point + (ProjectivePoint::GENERATOR * scalar);
+    Ok(changed)
+}
+
+/// Additive tweak that shifts the root key shares onto the key at `path`, following the key's
+/// recorded derivation scheme. Refuses keys whose recorded public key the tweak does not
+/// reproduce. Returns `None` for the root key.
+pub fn signing_tweak(
+    store: &HdKeyStore,
+    path: &str,
+    root_public_key: &[u8],
+    chain_code: &[u8; 32],
+) -> Result<Option<[u8; 32]>> {
+    if store.is_root(path) {
+        return Ok(None);
+    }
+    let key = store.get_key(path)
+        .ok_or_else(|| anyhow::anyhow!("Child key {} not found in HD key store. Please derive it first.", path))?;
+    let parsed = DerivationPath::parse(path)?;
     
-    // Convert back to affine and then to public key
-    let derived_affine = derived_point.to_affine();
-    let derived_public_key = PublicKey::from_affine(derived_affine)
-        .map_err(|e| anyhow::anyhow!("Failed to create public key from derived point: {}", e))?;
+    let scheme = key.derivation_scheme();
+    let tweak = match scheme {
+        DerivationScheme::Bip32 => {
+            let segments = store.relative_segments(&parsed)?;
+            compute_path_tweak(root_public_key, chain_code, &segments)?
+                .ok_or_else(|| anyhow::anyhow!("Key {} has an empty path below the root key", path))?
+        },
+        DerivationScheme::LegacyHmacSha256 => {
+            if parsed.parent() != Some(store.root_path()) {
+                anyhow::bail!("Legacy key {} is not a direct child of the root key {}", path, store.root_path);
+            }
+            legacy_child_tweak(root_public_key, chain_code, key.child_index)?
+        },
+    };
     
-    // Convert to SEC1 bytes
-    let derived_bytes = derived_public_key.to_encoded_point(true).as_bytes().to_vec();
+    if tweaked_public_key(root_public_key, &tweak)? != hex::decode(&key.public_key_hex)? {
+        anyhow::bail!(
+            "Key {} does not match its recorded {:?} derivation from the root key, refusing to sign with it",
+            path, scheme
+        );
+    }
     
     tracing::debug!(
-        derived_key_size = derived_bytes.len(),
-        "✅ Derived public key computed successfully"
+        path = %path,
+        scheme = ?scheme,
+        "🧮 Computed signing tweak for HD key"
     );
     
-    Ok(derived_bytes)
+    Ok(Some(tweak))
 }
 
-fn get_root_key_and_chain_code() -> Result<(Vec<u8>, [u8; 32])> {
+/// Tweak of the pre-BIP32 scheme: the first 32 bytes of HMAC-SHA256(chain code, root key || index).
+fn legacy_child_tweak(root_public_key: &[u8], chain_code: &[u8; 32], child_index: u32) -> Result<[u8; 32]> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = HmacSha256::new_from_slice(chain_code)
+        .map_err(|_| anyhow::anyhow!("Failed to create HMAC from chain code"))?;
+    mac.update(root_public_key);
+    mac.update(&child_index.to_be_bytes());
+    
+    let mut tweak = [0u8; 32];
+    tweak.copy_from_slice(&mac.finalize().into_bytes()[..32]);
+    Ok(tweak)
+}
+
+/// Compressed SEC1 encoding of `root_pk + tweak * G`.
+fn tweaked_public_key(root_public_key: &[u8], tweak: &[u8; 32]) -> Result<Vec<u8>> {
+    use k256::{PublicKey, Scalar, ProjectivePoint};
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+    use k256::elliptic_curve::PrimeField;
+    
+    let root = PublicKey::from_sec1_bytes(root_public_key)
+        .map_err(|e| anyhow::anyhow!("Failed to parse root public key: {}", e))?;
+    let scalar: Scalar = Option::from(Scalar::from_repr((*tweak).into()))
+        .ok_or_else(|| anyhow::anyhow!("Tweak is not below the curve order"))?;
+    let point = ProjectivePoint::from(*root.as_affine()) + ProjectivePoint::GENERATOR * scalar;
+    let public_key = PublicKey::from_affine(point.to_affine())
+        .map_err(|_| anyhow::anyhow!("Tweaked key is the point at infinity"))?;
+    Ok(public_key.to_encoded_point(true).as_bytes().to_vec())
+}
+
+/// First key in the caller's wallets for which `matches` returns a value, with its wallet ID.
+/// Wallets that have not derived any keys yet are searched by their root key.
+pub fn find_accessible_key<T>(
+    auth: &crate::BasicAuth,
+    matches: impl Fn(&DerivedKeyInfo) -> Option<T>,
+) -> Result<Option<(String, DerivedKeyInfo, T)>> {
+    for wallet in crate::wallets::accessible_wallets(auth)? {
+        let store = load_hd_key_store(&wallet.key_id)?;
+        let root_candidate = store.root_key().is_none().then(|| DerivedKeyInfo {
+            path: store.root_path.clone(),
+            public_key_hex: wallet.public_key.clone(),
+            label: wallet.name.clone(),
+            ..DerivedKeyInfo::default()
+        });
+
+        for key in store.keys.values().chain(root_candidate.iter()) {
+            if let Some(found) = matches(key) {
+                return Ok(Some((wallet.key_id.clone(), key.clone(), found)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Public key recorded for `path`, for verifying signatures made with that key.
+pub fn load_key_public_key(key_id: &str, path: &str) -> Result<Option<Vec<u8>>> {
+    let store = load_hd_key_store(key_id)?;
+    match store.get_key(path) {
+        Some(key_info) => {
+            tracing::debug!(
+                path = %path,
+                "📂 Loaded public key from HD key store"
+            );
+            Ok(Some(hex::decode(&key_info.public_key_hex)?))
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_root_key_and_chain_code(key_id: &str) -> Result<(Vec<u8>, [u8; 32])> {
     tracing::debug!("📂 Loading root key and chain code from keygen result");
     
     // Load the complete keygen result from storage
     use crate::sign::load_keygen_outputs;
-    let (_configs, keygen_result) = load_keygen_outputs()?;
+    let (_configs, keygen_result) = load_keygen_outputs(key_id)?;
     
     // Extract root key material from the first keygen output
     let first_keygen_output = keygen_result.keygen_outputs.values().next()
@@ -424,44 +1163,187 @@ fn get_root_key_and_chain_code() -> Result<(Vec<u8>, [u8; 32])> {
     Ok((public_key_bytes, chain_code))
 }
 
-fn store_child_public_key(child_index: u32, key_bytes: &[u8]) -> Result<()> {
-    use std::fs;
-    
-    let filename = format!("public_key_child_{}.bin", child_index);
-    
-    tracing::debug!(
-        child_index = child_index,
-        key_size = key_bytes.len(),
-        filename = %filename,
-        "💾 Storing derived child public key to file"
-    );
-    
-    fs::write(&filename, key_bytes)
-        .map_err(|e| anyhow::anyhow!("Failed to store child public key {}: {}", child_index, e))?;
-    
-    tracing::info!(
-        child_index = child_index,
-        filename = %filename,
-        "✅ Child public key stored successfully"
-    );
-    
-    Ok(())
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn get_root_public_key() -> Result<String> {
-    use std::fs;
-    
-    // Try to load from public_key.bin first
-    if let Ok(bytes) = fs::read("public_key.bin") {
-        return Ok(hex::encode(bytes));
+    const VECTOR_1_MASTER: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+    const VECTOR_1_M_0H: &str = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+    const VECTOR_1_M_0H_1: &str = "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ";
+    const VECTOR_1_M_0H_1_2H: &str = "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5";
+    const VECTOR_1_M_0H_1_2H_2: &str = "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV";
+    const VECTOR_1_M_0H_1_2H_2_1000000000: &str = "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy";
+    const VECTOR_2_MASTER: &str = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB";
+    const VECTOR_2_M_0: &str = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH";
+
+    struct Xpub {
+        depth: u8,
+        parent_fingerprint: [u8; 4],
+        child_number: u32,
+        chain_code: [u8; 32],
+        public_key: Vec<u8>,
     }
-    
-    // Fallback: try to get from stored keygen result
-    if let Ok(_) = fs::metadata("keygen_result.json") {
-        // Get the public key from keygen result
-        let (public_key_bytes, _chain_code) = get_root_key_and_chain_code()?;
-        return Ok(hex::encode(&public_key_bytes));
+
+    fn decode_xpub(xpub: &str) -> Xpub {
+        let bytes = bs58::decode(xpub).into_vec().unwrap();
+        assert_eq!(bytes.len(), 82);
+        assert_eq!(bytes[..4], XPUB_VERSION);
+        Xpub {
+            depth: bytes[4],
+            parent_fingerprint: bytes[5..9].try_into().unwrap(),
+            child_number: u32::from_be_bytes(bytes[9..13].try_into().unwrap()),
+            chain_code: bytes[13..45].try_into().unwrap(),
+            public_key: bytes[45..78].to_vec(),
+        }
+    }
+
+    #[test]
+    fn encode_xpub_round_trips_vector_keys() {
+        for xpub in [
+            VECTOR_1_MASTER,
+            VECTOR_1_M_0H,
+            VECTOR_1_M_0H_1,
+            VECTOR_1_M_0H_1_2H,
+            VECTOR_1_M_0H_1_2H_2,
+            VECTOR_1_M_0H_1_2H_2_1000000000,
+            VECTOR_2_MASTER,
+            VECTOR_2_M_0,
+        ] {
+            let key = decode_xpub(xpub);
+            let encoded = encode_xpub(key.depth, key.parent_fingerprint, key.child_number, &key.chain_code, &key.public_key);
+            assert_eq!(encoded, xpub);
+        }
+    }
+
+    #[test]
+    fn ckd_pub_matches_bip32_vectors() {
+        let steps = [
+            (VECTOR_1_M_0H, 1, VECTOR_1_M_0H_1),
+            (VECTOR_1_M_0H_1_2H, 2, VECTOR_1_M_0H_1_2H_2),
+            (VECTOR_1_M_0H_1_2H_2, 1_000_000_000, VECTOR_1_M_0H_1_2H_2_1000000000),
+            (VECTOR_2_MASTER, 0, VECTOR_2_M_0),
+        ];
+        for (parent_xpub, index, child_xpub) in steps {
+            let parent = decode_xpub(parent_xpub);
+            let expected = decode_xpub(child_xpub);
+            let child = ckd_pub(&parent.public_key, &parent.chain_code, index).unwrap();
+            assert_eq!(child.public_key, expected.public_key);
+            assert_eq!(child.chain_code, expected.chain_code);
+
+            let encoded = encode_xpub(
+                parent.depth + 1,
+                key_fingerprint(&parent.public_key),
+                index,
+                &child.chain_code,
+                &child.public_key,
+            );
+            assert_eq!(encoded, child_xpub);
+        }
+    }
+
+    #[test]
+    fn ckd_pub_rejects_hardened_indices() {
+        let master = decode_xpub(VECTOR_2_MASTER);
+        assert!(ckd_pub(&master.public_key, &master.chain_code, BIP32_HARDENED_OFFSET).is_err());
+    }
+
+    #[test]
+    fn compute_path_tweak_shifts_root_onto_vector_keys() {
+        // M/0H/1/2H stands in for a TSS root key; /2/1000000000 is derived publicly below it
+        let root = decode_xpub(VECTOR_1_M_0H_1_2H);
+        let tweak = compute_path_tweak(&root.public_key, &root.chain_code, &[2, 1_000_000_000]).unwrap().unwrap();
+        let expected = decode_xpub(VECTOR_1_M_0H_1_2H_2_1000000000);
+        assert_eq!(tweaked_public_key(&root.public_key, &tweak).unwrap(), expected.public_key);
+
+        let master = decode_xpub(VECTOR_2_MASTER);
+        let tweak = compute_path_tweak(&master.public_key, &master.chain_code, &[0]).unwrap().unwrap();
+        let child = ckd_pub(&master.public_key, &master.chain_code, 0).unwrap();
+        assert_eq!(tweak, child.tweak);
+        assert_eq!(tweaked_public_key(&master.public_key, &tweak).unwrap(), decode_xpub(VECTOR_2_M_0).public_key);
+
+        assert!(compute_path_tweak(&master.public_key, &master.chain_code, &[]).unwrap().is_none());
+    }
+
+    fn legacy_key(path: &DerivationPath, public_key: &[u8]) -> DerivedKeyInfo {
+        DerivedKeyInfo {
+            path: path.to_string(),
+            parent_path: path.parent().map(|parent| parent.to_string()),
+            depth: path.depth(),
+            child_index: *path.segments().last().unwrap(),
+            public_key_hex: hex::encode(public_key),
+            ..DerivedKeyInfo::default()
+        }
+    }
+
+    #[test]
+    fn legacy_keys_keep_their_own_derivation() {
+        let master = decode_xpub(VECTOR_2_MASTER);
+        let mut store = HdKeyStore::new("legacy-test");
+        let root_path = store.root_path();
+
+        let legacy_path = root_path.child(7);
+        let legacy_tweak = legacy_child_tweak(&master.public_key, &master.chain_code, 7).unwrap();
+        let legacy_public_key = tweaked_public_key(&master.public_key, &legacy_tweak).unwrap();
+        store.keys.insert(legacy_path.to_string(), legacy_key(&legacy_path, &legacy_public_key));
+
+        let bip32_path = root_path.child(0);
+        let child = ckd_pub(&master.public_key, &master.chain_code, 0).unwrap();
+        store.add_key(&bip32_path, hex::encode(&child.public_key), hex::encode(child.chain_code), VECTOR_2_M_0.to_string(), None);
+
+        assert!(store.mark_derivation_schemes());
+        assert!(!store.mark_derivation_schemes());
+        let legacy = store.get_key(&legacy_path.to_string()).unwrap();
+        assert_eq!(legacy.scheme, Some(DerivationScheme::LegacyHmacSha256));
+        assert_eq!(legacy.public_key_hex, hex::encode(&legacy_public_key));
+        assert_eq!(store.get_key(&bip32_path.to_string()).unwrap().scheme, Some(DerivationScheme::Bip32));
+
+        let signing = |path: &DerivationPath| signing_tweak(&store, &path.to_string(), &master.public_key, &master.chain_code);
+        assert_eq!(signing(&legacy_path).unwrap(), Some(legacy_tweak));
+        assert_eq!(signing(&bip32_path).unwrap(), Some(child.tweak));
+        assert_eq!(signing(&root_path).unwrap(), None);
+    }
+
+    #[test]
+    fn legacy_stores_are_saved_before_their_child_files_are_removed() {
+        crate::session_store::init_test_key_store();
+        crate::master_key::init_test_master_key();
+        let key_id = format!("hd-migration-{}", std::process::id());
+        let legacy_dir = std::env::temp_dir().join(format!("waas-hd-legacy-{}", std::process::id()));
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::write(legacy_dir.join("public_key_child_4.bin"), b"legacy").unwrap();
+
+        let master = decode_xpub(VECTOR_2_MASTER);
+        let child = |index: u32| serde_json::json!({
+            "child_index": index,
+            "public_key_hex": hex::encode(&master.public_key),
+            "created_at": "2024-01-01T00:00:00Z",
+            "label": null,
+        });
+        let legacy = serde_json::json!({ "root_key": child(0), "derived_keys": { "4": child(4) } });
+        key_store().unwrap().put(&key_id, Record::HdKeys, legacy.to_string().as_bytes()).unwrap();
+
+        // Reading converts in memory only
+        let loaded = load_hd_key_store(&key_id).unwrap();
+        assert!(loaded.get_key(&loaded.root_path().child(4).to_string()).is_some());
+        assert!(legacy_dir.join("public_key_child_4.bin").exists());
+
+        migrate_legacy_hd_key_stores(&legacy_dir).unwrap();
+        let saved = key_store().unwrap().get(&key_id, Record::HdKeys).unwrap().unwrap();
+        let saved: HdKeyStore = serde_json::from_slice(&saved).unwrap();
+        assert_eq!(saved.keys.len(), 2);
+        assert!(!legacy_dir.join("public_key_child_4.bin").exists());
+
+        // Already migrated stores are left alone
+        migrate_legacy_hd_key_stores(&legacy_dir).unwrap();
+    }
+
+    #[test]
+    fn signing_refuses_keys_that_do_not_match_their_scheme() {
+        let master = decode_xpub(VECTOR_2_MASTER);
+        let mut store = HdKeyStore::new("mismatch-test");
+        let path = store.root_path().child(3);
+        store.keys.insert(path.to_string(), legacy_key(&path, &master.public_key));
+
+        assert!(signing_tweak(&store, &path.to_string(), &master.public_key, &master.chain_code).is_err());
     }
-    
-    anyhow::bail!("No root public key found")
 }
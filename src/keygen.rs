@@ -1,56 +1,81 @@
-use axum::{response::Json, response::IntoResponse, http::StatusCode};
+use axum::{extract::Query, response::Json, response::IntoResponse, http::StatusCode};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use tss_ecdsa::{
     curve::{CurveTrait, TestCurve},
     keygen::KeygenParticipant,
-    messages::Message,
+    tshare::TshareParticipant,
     ParticipantConfig, ParticipantIdentifier, ProtocolParticipant, Participant, Identifier,
 };
 
 
-const NUMBER_OF_WORKERS: usize = 3;
+pub const DEFAULT_NUMBER_OF_WORKERS: usize = 3;
+pub const DEFAULT_THRESHOLD: usize = 2;
+const MAX_NUMBER_OF_WORKERS: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct KeygenRequest {
+    pub n: Option<usize>,
+    pub t: Option<usize>,
+    pub name: Option<String>, // Optional human-readable wallet name
+    pub owner: Option<String>, // User the wallet is provisioned for; defaults to the caller
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct KeygenResponse {
+    #[serde(default)]
+    pub key_id: String,
+    #[serde(default)]
+    pub name: Option<String>,
     pub public_key: String,
     pub private_key_share: String,
     pub rid: String,
     pub chain_code: String,
     pub message: String,
     pub participants: Vec<String>,
+    #[serde(default)]
+    pub threshold: usize,
 }
 
+pub type KeygenOutputs<C> = HashMap<ParticipantIdentifier, <KeygenParticipant<C> as ProtocolParticipant>::Output>;
+
 // KeygenHelperOutput struct to match the one in your fork
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeygenHelperOutput<C: CurveTrait> {
     #[serde(bound(deserialize = "C: CurveTrait"))]
     pub keygen_outputs: HashMap<ParticipantIdentifier, <KeygenParticipant<C> as ProtocolParticipant>::Output>,
+    // Signing threshold t; None for keys generated before t-of-n support (n-of-n)
+    #[serde(default)]
+    pub threshold: Option<usize>,
+    // Shamir shares from the tshare protocol; None for legacy n-of-n keys
+    #[serde(default = "Option::default", bound(deserialize = "C: CurveTrait"))]
+    pub tshare_outputs: Option<HashMap<ParticipantIdentifier, <TshareParticipant<C> as ProtocolParticipant>::Output>>,
+}
+
+impl<C: CurveTrait> KeygenHelperOutput<C> {
+    // Number of participants needed to produce a signature
+    pub fn threshold(&self) -> usize {
+        self.threshold.unwrap_or(self.keygen_outputs.len())
+    }
 }
 
 // Keygen helper function from your fork
 pub fn keygen_helper<C: CurveTrait>(
     configs: Vec<ParticipantConfig>,
-    mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>>,
     mut rng: StdRng,
 ) -> anyhow::Result<KeygenHelperOutput<C>> {
-    let quorum_size = configs.len();
-    
     tracing::debug!(
-        quorum_size = quorum_size,
+        quorum_size = configs.len(),
         "🔧 Setting up keygen participants"
     );
     
     // Set up keygen participants
     let keygen_sid = Identifier::random(&mut rng);
     let mut keygen_quorum = configs
-        .clone()
         .into_iter()
-        .map(|config| {
-            Participant::<KeygenParticipant<C>>::from_config(config, keygen_sid, ()).unwrap()
-        })
-        .collect::<Vec<_>>();
+        .map(|config| Participant::<KeygenParticipant<C>>::from_config(config, keygen_sid, ()))
+        .collect::<Result<Vec<_>, _>>()?;
         
     tracing::debug!(
         session_id = %keygen_sid,
@@ -58,145 +83,104 @@ pub fn keygen_helper<C: CurveTrait>(
         "✅ Keygen participants initialized"
     );
 
-    let mut keygen_outputs: HashMap<
-        ParticipantIdentifier,
-        <KeygenParticipant<C> as ProtocolParticipant>::Output,
-    > = HashMap::new();
-
-    // Initialize keygen for all participants
-    tracing::debug!("📨 Initializing keygen messages for all participants");
-    for participant in &keygen_quorum {
-        let inbox = inboxes.get_mut(&participant.id()).unwrap();
-        inbox.push(participant.initialize_message()?);
-    }
-    tracing::debug!("✅ Initial messages sent to all participants");
-
     // Run keygen until all parties have outputs
     tracing::debug!("🔄 Starting keygen message exchange protocol");
     let exchange_start = std::time::Instant::now();
-    let mut round_count = 0;
-    
-    while keygen_outputs.len() < quorum_size {
-        let output = process_random_message(&mut keygen_quorum, &mut inboxes, &mut rng)?;
-
-        if let Some((pid, output)) = output {
-            round_count += 1;
-            tracing::trace!(
-                participant_id = %pid,
-                round = round_count,
-                outputs_collected = keygen_outputs.len() + 1,
-                total_required = quorum_size,
-                "📨 Collected keygen output from participant"
-            );
-            // Save the output, and make sure this participant didn't already return an
-            // output.
-            assert!(keygen_outputs.insert(pid, output).is_none());
-        }
-    }
+    let keygen_outputs = crate::protocol::run_protocol("keygen", &mut keygen_quorum, &mut rng)?;
     
     tracing::info!(
         exchange_duration_ms = exchange_start.elapsed().as_millis(),
-        total_rounds = round_count,
         outputs_collected = keygen_outputs.len(),
         "✅ Keygen message exchange completed"
     );
 
-    // Keygen is done! Make sure there are no more messages.
-    assert!(inboxes_are_empty(&inboxes));
-
-    Ok(KeygenHelperOutput { keygen_outputs })
+    Ok(KeygenHelperOutput {
+        keygen_outputs,
+        threshold: None,
+        tshare_outputs: None,
+    })
 }
 
-// Helper functions used by keygen_helper
-fn process_random_message<C: CurveTrait>(
-    quorum: &mut [Participant<KeygenParticipant<C>>],
-    inboxes: &mut HashMap<ParticipantIdentifier, Vec<Message>>,
-    rng: &mut StdRng,
-) -> anyhow::Result<Option<(ParticipantIdentifier, <KeygenParticipant<C> as ProtocolParticipant>::Output)>> {
-    use rand::seq::SliceRandom;
-    
-    // Get all non-empty inboxes
-    let non_empty_inboxes: Vec<ParticipantIdentifier> = inboxes
-        .iter()
-        .filter(|(_, messages)| !messages.is_empty())
-        .map(|(pid, _)| *pid)
-        .collect();
+// Main keygen endpoint, creating a new wallet with a generated key ID (POST)
+pub async fn keygen(
+    auth: crate::BasicAuth,
+    request: Option<Json<KeygenRequest>>,
+) -> impl IntoResponse {
+    let Json(request) = request.unwrap_or_default();
+    let n = request.n.unwrap_or(DEFAULT_NUMBER_OF_WORKERS);
+    let t = request.t.unwrap_or(DEFAULT_THRESHOLD.min(n));
+    let key_id = crate::wallets::new_key_id();
+    let owner = request.owner.clone().unwrap_or_else(|| auth.username.clone());
 
-    if non_empty_inboxes.is_empty() {
-        return Ok(None);
-    }
-
-    // Pick a random participant with messages
-    let selected_pid = *non_empty_inboxes.choose(rng).unwrap();
-    let message = inboxes.get_mut(&selected_pid).unwrap().remove(0);
-
-    // Find the participant and process the message
-    let participant = quorum
-        .iter_mut()
-        .find(|p| p.id() == selected_pid)
-        .unwrap();
-
-    let (output, new_messages) = participant.process_single_message(&message, rng)?;
-
-    // Deliver new messages to their recipients
-    for msg in new_messages {
-        let recipient = msg.to();
-        if let Some(inbox) = inboxes.get_mut(&recipient) {
-            inbox.push(msg);
-        }
-    }
-
-    match output {
-        Some(output) => Ok(Some((selected_pid, output))),
-        None => Ok(None),
-    }
-}
-
-fn inboxes_are_empty(inboxes: &HashMap<ParticipantIdentifier, Vec<Message>>) -> bool {
-    inboxes.values().all(|messages| messages.is_empty())
-}
-
-// Main keygen endpoint for generating new keys (POST)
-pub async fn keygen(_auth: crate::BasicAuth) -> impl IntoResponse {
-    tracing::info!("🔑 Starting TSS key generation protocol");
+    tracing::info!(
+        key_id = %key_id,
+        owner = %owner,
+        requested_by = %auth.username,
+        n = n,
+        t = t,
+        name = ?request.name,
+        "🔑 Starting TSS key generation protocol"
+    );
     let start_time = std::time::Instant::now();
     
-    match run_tss_keygen().await {
+    match run_tss_keygen(&key_id, &owner, request.name.clone(), n, t).await {
         Ok(response) => {
             let duration = start_time.elapsed();
             tracing::info!(
+                key_id = %response.key_id,
                 participants = response.participants.len(),
                 public_key_preview = %response.public_key.get(..16.min(response.public_key.len())).unwrap_or(""),
                 duration_ms = duration.as_millis(),
                 "✅ TSS key generation completed successfully"
             );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "keygen", "success")
+                    .key_id(&response.key_id)
+                    .detail(format!("{}-of-{} wallet owned by {}", t, n, owner)),
+            );
             Json(response)
         },
         Err(e) => {
             let duration = start_time.elapsed();
             tracing::error!(
+                key_id = %key_id,
                 error = %e,
                 duration_ms = duration.as_millis(),
                 "❌ TSS key generation failed"
             );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "keygen", "failed")
+                    .key_id(&key_id)
+                    .detail(e.to_string()),
+            );
+            // Don't leave a half-created wallet behind
+            if let Err(cleanup_error) = crate::wallets::delete_wallet_records(&key_id) {
+                tracing::warn!(key_id = %key_id, error = %cleanup_error, "⚠️ Failed to clean up after failed keygen");
+            }
             Json(KeygenResponse {
+                key_id: String::new(),
+                name: None,
                 public_key: "error".to_string(),
                 private_key_share: "error".to_string(),
                 rid: "error".to_string(),
                 chain_code: "error".to_string(),
                 message: format!("Key generation failed: {}", e),
                 participants: vec![],
+                threshold: 0,
             })
         }
     }
 }
 
-// Check for existing keys endpoint (GET)
-pub async fn check_keygen(_auth: crate::BasicAuth) -> impl IntoResponse {
-    tracing::info!("🔍 Checking for existing TSS keys");
+// Check for an existing wallet endpoint (GET)
+pub async fn check_keygen(
+    auth: crate::BasicAuth,
+    Query(query): Query<crate::wallets::WalletQuery>,
+) -> impl IntoResponse {
+    tracing::info!(key_id = %query.key_id, "🔍 Checking for existing TSS keys");
     let start_time = std::time::Instant::now();
     
-    match check_existing_keys().await {
+    match check_existing_keys(&auth, &query.key_id).await {
         Ok(response) => {
             let duration = start_time.elapsed();
             tracing::info!(
@@ -216,55 +200,49 @@ pub async fn check_keygen(_auth: crate::BasicAuth) -> impl IntoResponse {
             );
             // Return 404 to indicate no keys exist
             (StatusCode::NOT_FOUND, Json(KeygenResponse {
+                key_id: query.key_id,
+                name: None,
                 public_key: "".to_string(),
                 private_key_share: "".to_string(),
                 rid: "".to_string(),
                 chain_code: "".to_string(),
                 message: "No existing keys found".to_string(),
                 participants: vec![],
+                threshold: 0,
             }))
         }
     }
 }
 
-async fn run_tss_keygen() -> anyhow::Result<KeygenResponse> {
-    let num_workers = NUMBER_OF_WORKERS;
-    
-    tracing::debug!(
-        participants = num_workers,
-        threshold = 2,
-        "🚀 Initializing TSS keygen participants"
-    );
-    
-    // Generate participant configurations
-    let mut rng = StdRng::from_entropy();
-    let configs = ParticipantConfig::random_quorum(num_workers, &mut rng)?;
-    
-    tracing::debug!(
-        configs_generated = configs.len(),
-        "✅ Participant configurations generated"
-    );
+async fn run_tss_keygen(
+    key_id: &str,
+    owner: &str,
+    name: Option<String>,
+    n: usize,
+    t: usize,
+) -> anyhow::Result<KeygenResponse> {
+    if let Some(name) = name.as_deref() {
+        crate::wallets::validate_wallet_name(name)?;
+    }
+    if crate::users::find_user(owner)?.is_none() {
+        anyhow::bail!("Owner {} is not a known user", owner);
+    }
 
-    // Initialize empty inboxes for all participants
-    let inboxes: HashMap<ParticipantIdentifier, Vec<Message>> = configs
-        .iter()
-        .map(|config| (config.id(), Vec::new()))
-        .collect();
+    let (configs, keygen_result) = generate_threshold_key(key_id, n, t)?;
 
-    tracing::debug!("📋 Running TSS keygen protocol");
-    let protocol_start = std::time::Instant::now();
-    
-    // Call keygen_helper with the configs and inboxes
-    let keygen_result = keygen_helper::<TestCurve>(configs.clone(), inboxes, rng)?;
-    
-    tracing::info!(
-        protocol_duration_ms = protocol_start.elapsed().as_millis(),
-        outputs_generated = keygen_result.keygen_outputs.len(),
-        "✅ TSS keygen protocol completed"
-    );
+    // Store complete keygen result and configs in the new wallet
+    crate::sign::store_keygen_outputs(key_id, &configs, &keygen_result)?;
 
-    // Store complete keygen result and configs to filesystem
-    crate::sign::store_keygen_outputs(&configs, &keygen_result)?;
+    // The wallet record goes last: a wallet is listed only once its key is complete
+    crate::wallets::save_wallet(&crate::wallets::WalletInfo {
+        key_id: key_id.to_string(),
+        name: name.clone(),
+        public_key: root_public_key_hex(&keygen_result)?,
+        threshold: t,
+        participants: n,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        owner: Some(owner.to_string()),
+    })?;
 
     // Extract the first participant's output for response
     let first_participant_id = configs[0].id();
@@ -279,33 +257,37 @@ async fn run_tss_keygen() -> anyhow::Result<KeygenResponse> {
         let chain_code = hex::encode(output.chain_code());
         
         Ok(KeygenResponse {
+            key_id: key_id.to_string(),
+            name,
             public_key,
             private_key_share,
             rid,
             chain_code,
-            message: "TSS Key generation completed successfully".to_string(),
+            message: format!("TSS {}-of-{} key generation completed successfully", t, n),
             participants: configs
                 .iter()
                 .map(|config| format!("{:?}", config.id()))
                 .collect(),
+            threshold: t,
         })
     } else {
         anyhow::bail!("No keygen output found for first participant");
     }
 }
 
-async fn check_existing_keys() -> anyhow::Result<KeygenResponse> {
-    tracing::debug!("🔍 Checking for existing keygen data");
+async fn check_existing_keys(auth: &crate::BasicAuth, key_id: &str) -> anyhow::Result<KeygenResponse> {
+    tracing::debug!(key_id = %key_id, "🔍 Checking for existing keygen data");
     
     // Check if keygen has been completed before
-    if !is_keygen_completed() {
+    let wallet = crate::wallets::authorize(auth, key_id)?;
+    if !crate::sign::is_keygen_completed(key_id) {
         anyhow::bail!("No existing keygen found");
     }
     
     tracing::debug!("📂 Loading existing keygen data from storage");
     
     // Load the full keygen result and configs using the shared function
-    let (configs, keygen_result) = crate::sign::load_keygen_outputs()?;
+    let (configs, keygen_result) = crate::sign::load_keygen_outputs(key_id)?;
     
     // Extract the first participant's output for response
     let first_participant_id = configs[0].id();
@@ -323,24 +305,319 @@ async fn check_existing_keys() -> anyhow::Result<KeygenResponse> {
         );
         
         Ok(KeygenResponse {
+            key_id: wallet.key_id,
+            name: wallet.name,
             public_key,
             private_key_share: "[stored securely - not displayed in check mode]".to_string(),
             rid: "[stored securely - not displayed in check mode]".to_string(),
             chain_code,
-            message: "Existing TSS keys found in local storage".to_string(),
+            message: format!(
+                "Existing {}-of-{} TSS keys found in local storage",
+                keygen_result.threshold(),
+                configs.len()
+            ),
             participants: configs
                 .iter()
                 .map(|config| format!("{:?}", config.id()))
                 .collect(),
+            threshold: keygen_result.threshold(),
         })
     } else {
         anyhow::bail!("No keygen output found for first participant");
     }
 }
 
-// Use the shared keygen completion check from sign.rs
-fn is_keygen_completed() -> bool {
-    crate::sign::is_keygen_completed()
+// Runs keygen among n participants and re-shares the result so that any t of them can sign
+pub fn generate_threshold_key(
+    key_id: &str,
+    n: usize,
+    t: usize,
+) -> anyhow::Result<(Vec<ParticipantConfig>, KeygenHelperOutput<TestCurve>)> {
+    if !(2..=MAX_NUMBER_OF_WORKERS).contains(&n) {
+        anyhow::bail!("Number of participants must be between 2 and {}, got {}", MAX_NUMBER_OF_WORKERS, n);
+    }
+    if t == 0 || t > n {
+        anyhow::bail!("Threshold must be between 1 and the number of participants ({}), got {}", n, t);
+    }
+
+    tracing::debug!(
+        participants = n,
+        threshold = t,
+        "🚀 Initializing TSS keygen participants"
+    );
+    
+    // Generate participant configurations
+    let mut rng = StdRng::from_entropy();
+    let configs = ParticipantConfig::random_quorum(n, &mut rng)?;
+    
+    tracing::debug!(
+        configs_generated = configs.len(),
+        "✅ Participant configurations generated"
+    );
+
+    tracing::debug!("📋 Running TSS keygen protocol");
+    let protocol_start = std::time::Instant::now();
+    
+    let mut keygen_result = keygen_helper::<TestCurve>(configs.clone(), StdRng::from_rng(&mut rng)?)?;
+    
+    tracing::info!(
+        protocol_duration_ms = protocol_start.elapsed().as_millis(),
+        outputs_generated = keygen_result.keygen_outputs.len(),
+        "✅ TSS keygen protocol completed"
+    );
+
+    // The tshare protocol needs auxinfo (Paillier keys) for its encrypted share delivery
+    tracing::debug!("📋 Running auxinfo for threshold re-sharing");
+    let auxinfo_result = crate::auxinfo::auxinfo_helper::<TestCurve>(configs.clone(), StdRng::from_rng(&mut rng)?)?;
+
+    // Keep the full-quorum auxinfo so later sessions among all n participants can reuse it
+    let public_key = root_public_key_hex(&keygen_result)?;
+    crate::auxinfo_cache::store_auxinfo(key_id, &public_key, &configs, &auxinfo_result.auxinfo_outputs)?;
+
+    tracing::debug!(threshold = t, "📋 Running tshare protocol");
+    let tshare_start = std::time::Instant::now();
+    let tshare_result = crate::tshare::tshare_helper::<TestCurve>(
+        configs.clone(),
+        auxinfo_result.auxinfo_outputs,
+        &keygen_result.keygen_outputs,
+        t,
+        rng,
+    )?;
+
+    tracing::info!(
+        protocol_duration_ms = tshare_start.elapsed().as_millis(),
+        participants = n,
+        threshold = t,
+        "✅ Threshold key shares generated"
+    );
+
+    keygen_result.threshold = Some(t);
+    keygen_result.tshare_outputs = Some(tshare_result.tshare_outputs);
+
+    Ok((configs, keygen_result))
+}
+
+// Picks the participants for a signing session. Requested names may use either
+// the IDs returned by /keygen or their plain form; without a request t random
+// participants are chosen. Legacy n-of-n keys always sign with every participant.
+pub fn select_signers(
+    configs: &[ParticipantConfig],
+    keygen_result: &KeygenHelperOutput<TestCurve>,
+    requested: Option<&[String]>,
+    rng: &mut StdRng,
+) -> anyhow::Result<Vec<ParticipantIdentifier>> {
+    let threshold = keygen_result.threshold();
+    let all_ids: Vec<ParticipantIdentifier> = configs.iter().map(|config| config.id()).collect();
+
+    if keygen_result.tshare_outputs.is_none() {
+        if let Some(requested) = requested
+            && requested.len() != all_ids.len()
+        {
+            anyhow::bail!(
+                "This key was generated as {}-of-{} and must be signed by all participants",
+                all_ids.len(),
+                all_ids.len()
+            );
+        }
+        return Ok(all_ids);
+    }
+
+    let Some(requested) = requested else {
+        return Ok(all_ids.choose_multiple(rng, threshold).copied().collect());
+    };
+
+    let mut selected = Vec::with_capacity(requested.len());
+    for name in requested {
+        let pid = all_ids
+            .iter()
+            .find(|pid| format!("{:?}", pid) == *name || pid.to_string() == *name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown participant: {}", name))?;
+        if selected.contains(pid) {
+            anyhow::bail!("Participant listed more than once: {}", name);
+        }
+        selected.push(*pid);
+    }
+    if selected.len() != threshold {
+        anyhow::bail!(
+            "Exactly {} signers are required for this key, got {}",
+            threshold,
+            selected.len()
+        );
+    }
+    Ok(selected)
+}
+
+// Builds the configs for a signing session among the given signers together with
+// the keygen outputs they should sign with. For t-of-n keys the signers' Shamir
+// shares are converted into additive t-of-t shares.
+pub fn signing_quorum(
+    keygen_result: &KeygenHelperOutput<TestCurve>,
+    signer_ids: &[ParticipantIdentifier],
+) -> anyhow::Result<(Vec<ParticipantConfig>, KeygenOutputs<TestCurve>)> {
+    let signer_configs = signer_ids
+        .iter()
+        .map(|pid| {
+            let others: Vec<ParticipantIdentifier> =
+                signer_ids.iter().filter(|other| *other != pid).copied().collect();
+            ParticipantConfig::new(*pid, &others)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(tshare_outputs) = keygen_result.tshare_outputs.as_ref() else {
+        if signer_ids.len() != keygen_result.keygen_outputs.len() {
+            anyhow::bail!("Legacy n-of-n keys must be signed by all participants");
+        }
+        return Ok((signer_configs, keygen_result.keygen_outputs.clone()));
+    };
+
+    let signer_tshares = tshare_outputs
+        .iter()
+        .filter(|(pid, _)| signer_ids.contains(pid))
+        .map(|(pid, output)| (*pid, output.clone()))
+        .collect::<HashMap<_, _>>();
+    if signer_tshares.len() != signer_ids.len() {
+        anyhow::bail!("Missing threshold shares for some of the selected signers");
+    }
+
+    // Any keygen output carries the shared rid and chain code
+    let reference = keygen_result
+        .keygen_outputs
+        .values()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No keygen outputs available"))?;
+
+    let signer_outputs = TshareParticipant::<TestCurve>::convert_to_t_out_of_t_shares(
+        signer_tshares,
+        signer_ids.to_vec(),
+        *reference.rid(),
+        *reference.chain_code(),
+        keygen_result.threshold(),
+    )?;
+
+    tracing::debug!(
+        signers = ?signer_ids,
+        threshold = keygen_result.threshold(),
+        total_participants = keygen_result.keygen_outputs.len(),
+        "👥 Built signing quorum"
+    );
+
+    Ok((signer_configs, signer_outputs))
+}
+
+// Hex-encoded root public key, used to tie cached protocol outputs to the key they belong to
+pub fn root_public_key_hex(keygen_result: &KeygenHelperOutput<TestCurve>) -> anyhow::Result<String> {
+    let output = keygen_result
+        .keygen_outputs
+        .values()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No keygen outputs available"))?;
+    Ok(hex::encode(output.public_key()?.to_sec1_bytes()))
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quorum(n: usize) -> Vec<ParticipantConfig> {
+        ParticipantConfig::random_quorum(n, &mut StdRng::seed_from_u64(n as u64)).unwrap()
+    }
+
+    // Quorum selection only looks at the key layout, so the shares themselves can stay empty
+    fn key_layout(threshold: Option<usize>) -> KeygenHelperOutput<TestCurve> {
+        KeygenHelperOutput {
+            keygen_outputs: HashMap::new(),
+            threshold,
+            tshare_outputs: threshold.map(|_| HashMap::new()),
+        }
+    }
+
+    fn names(configs: &[ParticipantConfig], indices: &[usize]) -> Vec<String> {
+        indices.iter().map(|i| configs[*i].id().to_string()).collect()
+    }
+
+    #[test]
+    fn requested_signers_must_be_t_distinct_participants() {
+        let configs = quorum(3);
+        let key = key_layout(Some(2));
+        let mut rng = StdRng::seed_from_u64(1);
+        let select = |requested: Vec<String>, rng: &mut StdRng| select_signers(&configs, &key, Some(&requested), rng);
+
+        let selected = select(names(&configs, &[2, 0]), &mut rng).unwrap();
+        assert_eq!(selected, vec![configs[2].id(), configs[0].id()]);
+        // Debug names, as listed in signing responses, are accepted too
+        let debug_names = vec![format!("{:?}", configs[1].id()), format!("{:?}", configs[2].id())];
+        assert_eq!(select(debug_names, &mut rng).unwrap(), vec![configs[1].id(), configs[2].id()]);
+
+        let duplicate = select(names(&configs, &[1, 1]), &mut rng).unwrap_err();
+        assert!(duplicate.to_string().contains("more than once"), "{}", duplicate);
+        let unknown = select(vec![configs[0].id().to_string(), "intruder".to_string()], &mut rng).unwrap_err();
+        assert!(unknown.to_string().contains("Unknown participant: intruder"), "{}", unknown);
+        for wrong_count in [vec![0], vec![0, 1, 2]] {
+            let error = select(names(&configs, &wrong_count), &mut rng).unwrap_err();
+            assert!(error.to_string().contains("Exactly 2 signers"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn unrequested_signers_are_a_random_t_subset() {
+        let configs = quorum(5);
+        let key = key_layout(Some(3));
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..10 {
+            let mut selected = select_signers(&configs, &key, None, &mut rng).unwrap();
+            assert_eq!(selected.len(), 3);
+            selected.sort();
+            selected.dedup();
+            assert_eq!(selected.len(), 3);
+            assert!(selected.iter().all(|pid| configs.iter().any(|config| config.id() == *pid)));
+        }
+    }
+
+    #[test]
+    fn legacy_keys_are_signed_by_every_participant() {
+        let configs = quorum(3);
+        let key = key_layout(None);
+        let all: Vec<ParticipantIdentifier> = configs.iter().map(|config| config.id()).collect();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        assert_eq!(select_signers(&configs, &key, None, &mut rng).unwrap(), all);
+        assert_eq!(select_signers(&configs, &key, Some(&names(&configs, &[0, 1, 2])), &mut rng).unwrap(), all);
+        let error = select_signers(&configs, &key, Some(&names(&configs, &[0, 1])), &mut rng).unwrap_err();
+        assert!(error.to_string().contains("must be signed by all participants"), "{}", error);
+
+        // The shares of a legacy key cannot be re-split for a smaller quorum
+        assert!(signing_quorum(&key, &all[..2]).is_err());
+    }
+
+    // Runs keygen, tshare, presign and sign with the real protocol: `cargo test -- --ignored`
+    #[test]
+    #[ignore = "runs the full TSS protocol, which takes minutes"]
+    fn every_t_subset_signs_for_the_root_key() {
+        use crate::sign::{sign_helper, verify_prehashed_signature, SignHelperInput, SignTarget};
+
+        crate::session_store::init_test_key_store();
+        crate::master_key::init_test_master_key();
+        let key_id = format!("t-subsets-{}", std::process::id());
+        let (configs, keygen_result) = generate_threshold_key(&key_id, 3, 2).unwrap();
+        let root_key = keygen_result.keygen_outputs.values().next().unwrap().public_key().unwrap();
+
+        let message = b"any two of three";
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let signers = [configs[a].id(), configs[b].id()];
+            let entry = crate::presign_pool::generate_entry(&key_id, &keygen_result, &signers).unwrap();
+            let (signer_configs, signer_outputs) = signing_quorum(&keygen_result, &signers).unwrap();
+            let input = SignHelperInput {
+                public_key_shares: signer_outputs.values().next().unwrap().public_key_shares().to_vec(),
+                presign_outputs: entry.records,
+                key_tweak: None,
+                threshold: keygen_result.threshold(),
+            };
+            let signature = sign_helper(signer_configs, input, SignTarget::Message(message), StdRng::seed_from_u64(4)).unwrap();
+
+            let signature = k256::ecdsa::Signature::from_der(&signature).unwrap();
+            let digest = SignTarget::Message(message).digest();
+            assert!(verify_prehashed_signature(&root_key, &digest, &signature).unwrap(), "signers {} and {}", a, b);
+        }
+    }
+}
@@ -1,26 +1,214 @@
-/// Axum hello world example application.
-
 mod dashboard;
+mod encryption;
+mod master_key;
 mod keygen;
 mod auxinfo;
+mod auxinfo_cache;
 mod presign;
+mod presign_pool;
+mod protocol;
+mod tshare;
 mod sign;
+mod eth_tx;
+mod btc_psbt;
+mod eth_message;
 mod delete_key;
 mod hd_keys;
+mod addresses;
+mod session_store;
+mod wallets;
+mod users;
+mod api_tokens;
+mod rbac;
+mod audit;
+mod policy;
+mod approvals;
 mod logging;
+mod node_api;
+mod coordinator;
 
 use axum::{
-    extract::FromRequestParts, http::{request::Parts, StatusCode}, routing::{get, post}, Router
+    extract::{FromRequestParts, MatchedPath, Request},
+    http::{request::Parts, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
 };
 use axum_server::tls_rustls::RustlsConfig;
 use base64::{engine::general_purpose, Engine as _};
-use tower_http::services::ServeDir;
+use serde::Serialize;
+use tower_http::services::ServeFile;
 use std::{future::Future, net::SocketAddr};
 
-#[allow(dead_code)]
+// Routes that answer without credentials, besides the static assets below
+const PUBLIC_ROUTES: &[&str] = &["/health"];
+
+// The web UI files, each on its own route. Nothing else under src/static is served.
+const STATIC_ASSETS: &[(&str, &str)] = &[
+    ("/", "src/static/index.html"),
+    ("/index.html", "src/static/index.html"),
+    ("/index_clean.html", "src/static/index_clean.html"),
+];
+
+// Authenticated caller, from a password (Basic) or an API token (Bearer).
+// `role` decides which routes the caller may use.
+#[derive(Clone)]
 struct BasicAuth {
     username: String,
-    password: String,
+    role: rbac::Role,
+    // Set for API token requests, which are limited to the token's scopes
+    token_id: Option<String>,
+    scopes: Option<Vec<api_tokens::Scope>>,
+}
+
+impl BasicAuth {
+    fn is_admin(&self) -> bool {
+        self.role == rbac::Role::Admin
+    }
+
+    // Checks the caller's role, then the token's scopes for token requests
+    fn check(&self, permission: rbac::Permission) -> Result<(), String> {
+        if !self.role.allows(permission) {
+            return Err(format!(
+                "Role '{}' does not allow the '{}' operation",
+                self.role.name(),
+                permission.name()
+            ));
+        }
+        if let Some(scopes) = &self.scopes {
+            let scope = api_tokens::required_scope(permission);
+            if !scopes.contains(&api_tokens::Scope::Admin) && !scopes.contains(&scope) {
+                return Err(format!("Token lacks the '{}' scope", scope.name()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Checks the Basic credentials or Bearer token in `headers`
+async fn authenticate(headers: &HeaderMap) -> Result<BasicAuth, (StatusCode, String)> {
+    let header = match headers.get("authorization") {
+        Some(h) => h.to_str().unwrap_or(""),
+        None => return Err((StatusCode::UNAUTHORIZED, "Missing Authorization".into())),
+    };
+
+    if let Some(token) = header.strip_prefix("Bearer ") {
+        let token = token.trim().to_string();
+        let resolved = tokio::task::spawn_blocking(move || api_tokens::authenticate(&token))
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Authentication failed".to_string()))?
+            .map_err(|e| {
+                tracing::error!(error = %e, "❌ Failed to read API token store");
+                (StatusCode::INTERNAL_SERVER_ERROR, "Authentication failed".to_string())
+            })?;
+        return match resolved {
+            Some((token, user)) => Ok(BasicAuth {
+                username: user.username,
+                role: user.role,
+                token_id: Some(token.id),
+                scopes: Some(token.scopes),
+            }),
+            None => Err((StatusCode::UNAUTHORIZED, "Invalid token".into())),
+        };
+    }
+
+    if !header.starts_with("Basic ") {
+        return Err((StatusCode::UNAUTHORIZED, "Unsupported auth scheme".into()));
+    }
+
+    let b64 = &header[6..];
+    let decoded = general_purpose::STANDARD
+        .decode(b64)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid Base64".into()))?;
+    let cred = String::from_utf8(decoded)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid UTF-8".into()))?;
+
+    let mut parts = cred.splitn(2, ':');
+    let username = parts.next().unwrap_or("").to_string();
+    let password = parts.next().unwrap_or("").to_string();
+
+    // bcrypt verification is CPU-bound, keep it off the async workers
+    let user = tokio::task::spawn_blocking(move || users::authenticate(&username, &password))
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Authentication failed".to_string()))?
+        .map_err(|e| {
+            tracing::error!(error = %e, "❌ Failed to read user store");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Authentication failed".to_string())
+        })?;
+
+    match user {
+        Some(user) => Ok(BasicAuth {
+            username: user.username,
+            role: user.role,
+            token_id: None,
+            scopes: None,
+        }),
+        None => Err((StatusCode::UNAUTHORIZED, "Invalid credentials".into())),
+    }
+}
+
+fn is_public(route: &str) -> bool {
+    PUBLIC_ROUTES.contains(&route) || STATIC_ASSETS.iter().any(|(path, _)| *path == route)
+}
+
+// Router-wide authentication and authorization layer: every route requires
+// credentials unless it is public, and a role allowing it. Requests matching no
+// route need credentials too before they get their 404.
+async fn require_auth(mut request: Request, next: Next) -> Response {
+    let matched = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+    if matched.as_deref().is_some_and(is_public) {
+        return next.run(request).await;
+    }
+    let route = matched.clone().unwrap_or_else(|| request.uri().path().to_string());
+
+    match authenticate(request.headers()).await {
+        Ok(auth) => {
+            let Some(matched) = matched else {
+                return next.run(request).await;
+            };
+            let permission = rbac::required_permission(request.method(), &matched);
+            if let Err(reason) = auth.check(permission) {
+                tracing::warn!(
+                    username = %auth.username,
+                    role = auth.role.name(),
+                    token_id = ?auth.token_id,
+                    route = %route,
+                    method = %request.method(),
+                    permission = permission.name(),
+                    reason = %reason,
+                    "🚫 Access denied"
+                );
+                audit::record(
+                    audit::AuditEvent::new(&auth, "access_denied", "denied")
+                        .detail(format!("{} {}: {}", request.method(), route, reason)),
+                );
+                return (StatusCode::FORBIDDEN, reason).into_response();
+            }
+            if let Some(token_id) = &auth.token_id {
+                tracing::info!(
+                    token_id = %token_id,
+                    username = %auth.username,
+                    route = %route,
+                    method = %request.method(),
+                    "🎫 API token used"
+                );
+            }
+            // Handlers pick the caller up from here instead of verifying again
+            request.extensions_mut().insert(auth);
+            next.run(request).await
+        }
+        Err((status, message)) => {
+            tracing::warn!(
+                route = %route,
+                method = %request.method(),
+                status = %status,
+                reason = %message,
+                "🚫 Rejected unauthenticated request"
+            );
+            (status, message).into_response()
+        }
+    }
 }
 
 // Implement BasicAuth extractor
@@ -30,39 +218,74 @@ where
 {
     type Rejection = (StatusCode, String);
 
-    fn from_request_parts<'a, 'b>(
-        parts: &'a mut Parts,
-        _state: &'b S,
-    ) -> impl Future<Output = Result<Self, <Self as FromRequestParts<S>>::Rejection>> + Send 
-    {
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl Future<Output = Result<Self, <Self as FromRequestParts<S>>::Rejection>> + Send {
         Box::pin(async move {
-            let header = match parts.headers.get("authorization") {
-                Some(h) => h.to_str().unwrap_or(""),
-                None => return Err((StatusCode::UNAUTHORIZED, "Missing Authorization".into())),
-            };
-
-            if !header.starts_with("Basic ") {
-                return Err((StatusCode::UNAUTHORIZED, "Unsupported auth scheme".into()));
+            match parts.extensions.get::<BasicAuth>() {
+                Some(auth) => Ok(auth.clone()),
+                None => authenticate(&parts.headers).await,
             }
+        })
+    }
+}
 
-            let b64 = &header[6..];
-            let decoded = general_purpose::STANDARD
-                .decode(b64)
-                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid Base64".into()))?;
-            let cred = String::from_utf8(decoded)
-                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid UTF-8".into()))?;
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    version: &'static str,
+}
 
-            let mut parts = cred.splitn(2, ':');
-            let username = parts.next().unwrap_or("").to_string();
-            let password = parts.next().unwrap_or("").to_string();
+// Liveness probe (GET, no authentication)
+async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+    })
+}
 
-            if username == "admin" && password == "admin123" {
-                Ok(BasicAuth { username, password })
-            } else {
-                Err((StatusCode::UNAUTHORIZED, "Invalid credentials".into()))
-            }
-        })
+// Every route of the server, behind the authentication layer
+fn app() -> Router {
+    let mut app = Router::new()
+        .route("/dashboard", get(dashboard::dashboard))
+        .route("/keygen", post(keygen::keygen).get(keygen::check_keygen))
+        .route("/wallets", get(wallets::wallets))
+        .route("/delete_key", post(delete_key::delete_key))
+        .route("/derive_key", post(hd_keys::derive_key))
+        .route("/list_keys", get(hd_keys::list_keys))
+        .route("/delete_child_key", post(hd_keys::delete_child_key))
+        .route("/addresses/{address}", get(addresses::lookup_address))
+        .route("/sign", post(sign::sign))
+        .route("/verify", post(sign::verify))
+        .route("/recover", post(sign::recover))
+        .route("/eth/sign_transaction", post(eth_tx::sign_transaction))
+        .route("/btc/sign_psbt", post(btc_psbt::sign_psbt))
+        .route("/presign_pool", get(presign_pool::presign_pool_status))
+        .route("/auxinfo/refresh", post(auxinfo_cache::refresh_auxinfo))
+        .route("/nodes/status", get(coordinator::nodes_status))
+        .route("/nodes/keygen", post(coordinator::nodes_keygen))
+        .route("/nodes/sign", post(coordinator::nodes_sign))
+        .route("/admin/users", get(users::list_users).post(users::create_user))
+        .route("/admin/users/{username}/disable", post(users::disable_user))
+        .route("/admin/users/{username}/enable", post(users::enable_user))
+        .route("/admin/users/{username}/role", post(users::set_role))
+        .route("/policies", get(policy::list_policies).post(policy::set_policy))
+        .route("/policies/delete", post(policy::delete_policy))
+        .route("/policies/evaluate", post(policy::evaluate_policy))
+        .route("/signing_requests", get(approvals::list_requests))
+        .route("/signing_requests/{id}", get(approvals::get_request))
+        .route("/signing_requests/{id}/approve", post(approvals::approve_request))
+        .route("/signing_requests/{id}/reject", post(approvals::reject_request))
+        .route("/tokens", get(api_tokens::list_tokens).post(api_tokens::create_token))
+        .route("/tokens/{id}/revoke", post(api_tokens::revoke_token))
+        .route("/audit", get(audit::audit_log))
+        .route("/health", get(health));
+    for (route, file) in STATIC_ASSETS {
+        app = app.route_service(route, ServeFile::new(file));
     }
+    // Added last so it wraps every route, including requests that match none
+    app.layer(middleware::from_fn(require_auth))
 }
 
 // Route
@@ -71,6 +294,21 @@ async fn main() -> anyhow::Result<()> {
     // Initialize tracing with Zama.ai UI colors
     logging::init_zama_logging();
 
+    // `waas verify-audit` checks the audit log and exits instead of serving
+    if std::env::args().nth(1).as_deref() == Some("verify-audit") {
+        session_store::init_key_store()?;
+        encryption::init_master_key()?;
+        let intact = audit::run_verify_command()?;
+        std::process::exit(if intact { 0 } else { 1 });
+    }
+    // `waas chain-audit-log` chains a log from before entries were chained, once
+    if std::env::args().nth(1).as_deref() == Some("chain-audit-log") {
+        session_store::init_key_store()?;
+        encryption::init_master_key()?;
+        audit::run_chain_command()?;
+        return Ok(());
+    }
+
     tracing::info!(
         service = "TSS-ECDSA Wallet-as-a-Service",
         version = env!("CARGO_PKG_VERSION"),
@@ -79,25 +317,57 @@ async fn main() -> anyhow::Result<()> {
 
     // Build application routes with logging
     tracing::debug!("📋 Configuring application routes");
-    let app = Router::new()
-        .route("/dashboard", get(dashboard::dashboard))
-        .route("/keygen", post(keygen::keygen).get(keygen::check_keygen))
-        .route("/delete_key", post(delete_key::delete_key))
-        .route("/derive_key", post(hd_keys::derive_key))
-        .route("/list_keys", get(hd_keys::list_keys))
-        .route("/delete_child_key", post(hd_keys::delete_child_key))
-        .route("/sign", post(sign::sign))
-        .route("/verify", post(sign::verify))
-        // Serve everything under ./static, with index.html support
-        .fallback_service(ServeDir::new("src/static").append_index_html_on_directories(true));
+    let app = app();
 
     tracing::info!(
-        routes_count = 8,
-        routes = "/dashboard, /keygen (GET/POST), /delete_key, /sign, /verify",
-        static_content = "src/static",
+        public_routes = ?PUBLIC_ROUTES,
+        static_assets = ?STATIC_ASSETS.iter().map(|(route, _)| *route).collect::<Vec<_>>(),
         "✅ Application routes configured"
     );
 
+    // Open the configured key store before anything reads key material
+    session_store::init_key_store().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to open key store");
+        e
+    })?;
+
+    // Refuse to start unless the master key opens the stored key material
+    encryption::init_master_key().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to load master key");
+        e
+    })?;
+    audit::init_audit_log().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to open audit log");
+        e
+    })?;
+    users::init_user_store().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to initialize user store");
+        e
+    })?;
+    encryption::migrate_key_material().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to migrate stored key material");
+        e
+    })?;
+    hd_keys::migrate_legacy_hd_key_stores(&session_store::storage_dir()).map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to migrate HD key stores");
+        e
+    })?;
+    wallets::register_legacy_wallet()?;
+
+    // Keep presignatures precomputed so /sign only runs the online round
+    presign_pool::spawn_refill_task();
+
+    // Sign requests whose approvals were collected before the last shutdown
+    approvals::resume_approved_requests().map_err(|e| {
+        tracing::error!(error = %e, "❌ Failed to resume approved signing requests");
+        e
+    })?;
+
+    // axum-server and reqwest enable different rustls crypto providers, so pick one explicitly
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .map_err(|_| anyhow::anyhow!("Failed to install the TLS crypto provider"))?;
+
     // Load TLS cert and key (PEM files)
     tracing::debug!(
         cert_file = "cert.pem",
@@ -130,7 +400,7 @@ async fn main() -> anyhow::Result<()> {
     println!("\n🎯 TSS-ECDSA Wallet-as-a-Service Server");
     println!("📍 Listening on https://localhost:8443");
     println!("🔐 TLS encryption enabled");
-    println!("🔑 Authentication: admin/admin123");
+    println!("🔑 Authentication: HTTP Basic or Bearer API token, users managed under /admin/users");
     println!("📊 Dashboard: https://localhost:8443/dashboard");
     println!("\n✨ Ready to process TSS operations!");
 
@@ -153,4 +423,210 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("👋 Server shutdown completed");
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Method;
+    use tower::ServiceExt;
+
+    // Every API route, with path parameters filled in
+    const API_ROUTES: &[(Method, &str)] = &[
+        (Method::GET, "/dashboard"),
+        (Method::GET, "/keygen"),
+        (Method::POST, "/keygen"),
+        (Method::GET, "/wallets"),
+        (Method::POST, "/delete_key"),
+        (Method::POST, "/derive_key"),
+        (Method::GET, "/list_keys"),
+        (Method::POST, "/delete_child_key"),
+        (Method::GET, "/addresses/0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"),
+        (Method::POST, "/sign"),
+        (Method::POST, "/verify"),
+        (Method::POST, "/recover"),
+        (Method::POST, "/eth/sign_transaction"),
+        (Method::POST, "/btc/sign_psbt"),
+        (Method::GET, "/presign_pool"),
+        (Method::POST, "/auxinfo/refresh"),
+        (Method::GET, "/nodes/status"),
+        (Method::POST, "/nodes/keygen"),
+        (Method::POST, "/nodes/sign"),
+        (Method::GET, "/admin/users"),
+        (Method::POST, "/admin/users"),
+        (Method::POST, "/admin/users/alice/disable"),
+        (Method::POST, "/admin/users/alice/enable"),
+        (Method::POST, "/admin/users/alice/role"),
+        (Method::GET, "/policies"),
+        (Method::POST, "/policies"),
+        (Method::POST, "/policies/delete"),
+        (Method::POST, "/policies/evaluate"),
+        (Method::GET, "/signing_requests"),
+        (Method::GET, "/signing_requests/req-1"),
+        (Method::POST, "/signing_requests/req-1/approve"),
+        (Method::POST, "/signing_requests/req-1/reject"),
+        (Method::GET, "/tokens"),
+        (Method::POST, "/tokens"),
+        (Method::POST, "/tokens/tok-1/revoke"),
+        (Method::GET, "/audit"),
+    ];
+
+    async fn status(method: Method, uri: &str, authorization: Option<&str>) -> StatusCode {
+        session_store::init_test_key_store();
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        let request = request
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        app().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn api_routes_reject_requests_without_credentials() {
+        for (method, uri) in API_ROUTES {
+            assert_eq!(status(method.clone(), uri, None).await, StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn api_routes_reject_bad_credentials() {
+        let bad_password = format!("Basic {}", general_purpose::STANDARD.encode("nobody:wrong-password"));
+        let bad_credentials = [
+            bad_password.as_str(),
+            "Bearer waas_unknown_0000000000000000",
+            "Bearer not-a-token",
+            "Digest username=\"admin\"",
+        ];
+        for (method, uri) in API_ROUTES {
+            for authorization in bad_credentials {
+                assert_eq!(
+                    status(method.clone(), uri, Some(authorization)).await,
+                    StatusCode::UNAUTHORIZED,
+                    "{} {} with {}",
+                    method,
+                    uri,
+                    authorization
+                );
+            }
+        }
+    }
+
+    async fn call(method: Method, uri: &str, authorization: &str, body: serde_json::Value) -> serde_json::Value {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", authorization)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    // Service state for the tests that run the real protocol; returns the admin's credentials
+    fn init_protocol_test_service() -> String {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            // SAFETY: set before the service state is initialized and read
+            unsafe {
+                std::env::set_var("WAAS_STORAGE_DIR", std::env::temp_dir().join(format!("waas-test-store-{}", std::process::id())));
+                std::env::set_var("WAAS_ADMIN_PASSWORD", "waas-test-admin");
+            }
+            session_store::init_test_key_store();
+            master_key::init_test_master_key();
+            users::init_user_store().unwrap();
+        });
+        format!("Basic {}", general_purpose::STANDARD.encode("admin:waas-test-admin"))
+    }
+
+    // Runs keygen, auxinfo, presign and sign with the real protocol: `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "runs the full TSS protocol, which takes minutes"]
+    async fn signatures_in_every_hash_mode_verify() {
+        use sha2::{Digest, Sha256};
+
+        let admin = init_protocol_test_service();
+        let keygen = call(Method::POST, "/keygen", &admin, serde_json::json!({})).await;
+        let key_id = keygen["key_id"].as_str().unwrap().to_string();
+        let derived = call(Method::POST, "/derive_key", &admin, serde_json::json!({ "key_id": key_id, "path": "m/0/5" })).await;
+        assert_eq!(derived["success"], true, "{}", derived);
+
+        let digest = hex::encode(Sha256::digest(b"waas prehashed digest"));
+        let requests = [
+            serde_json::json!({ "message": "hello waas", "hash": "keccak256" }),
+            serde_json::json!({ "message": "hello waas", "hash": "sha256" }),
+            serde_json::json!({ "message": "hello waas", "hash": "sha256d" }),
+            serde_json::json!({ "digest": digest }),
+            serde_json::json!({ "message": "hello waas", "hash": "sha256", "path": "m/0/5" }),
+            serde_json::json!({ "digest": digest, "path": "m/0/5", "encoding": "rsv" }),
+        ];
+        for mut request in requests {
+            request["key_id"] = key_id.clone().into();
+            let signed = call(Method::POST, "/sign", &admin, request.clone()).await;
+            assert_eq!(signed["success"], true, "{} -> {}", request, signed);
+
+            request["signature"] = signed["signature"].clone();
+            let verified = call(Method::POST, "/verify", &admin, request.clone()).await;
+            assert_eq!(verified["valid"], true, "{} -> {}", request, verified);
+            assert_eq!(verified["digest"], signed["digest"], "{}", request);
+
+            // The same signature does not cover another message
+            if request.get("digest").is_some() {
+                request["digest"] = hex::encode(Sha256::digest(b"another digest")).into();
+            } else {
+                request["message"] = "hello bob".into();
+            }
+            let verified = call(Method::POST, "/verify", &admin, request.clone()).await;
+            assert_eq!(verified["valid"], false, "{} -> {}", request, verified);
+        }
+    }
+
+    // A child key signs with tweaked shares; the result must verify under the public
+    // key /derive_key returned, and not under the root key
+    #[tokio::test]
+    #[ignore = "runs the full TSS protocol, which takes minutes"]
+    async fn child_key_signatures_verify_under_the_derived_public_key() {
+        use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+        use sha3::{Digest, Keccak256};
+
+        let admin = init_protocol_test_service();
+        let keygen = call(Method::POST, "/keygen", &admin, serde_json::json!({})).await;
+        let key_id = keygen["key_id"].as_str().unwrap().to_string();
+        let derived = call(Method::POST, "/derive_key", &admin, serde_json::json!({ "key_id": key_id, "path": "m/3/7" })).await;
+        assert_eq!(derived["success"], true, "{}", derived);
+
+        let signed = call(
+            Method::POST,
+            "/sign",
+            &admin,
+            serde_json::json!({ "key_id": key_id, "path": "m/3/7", "message": "child key message" }),
+        )
+        .await;
+        assert_eq!(signed["success"], true, "{}", signed);
+
+        let signature = Signature::from_der(&hex::decode(signed["signature"].as_str().unwrap()).unwrap()).unwrap();
+        let digest = Keccak256::digest(b"child key message");
+        let key = |hex_key: &serde_json::Value| VerifyingKey::from_sec1_bytes(&hex::decode(hex_key.as_str().unwrap()).unwrap()).unwrap();
+        assert!(key(&derived["public_key"]).verify_prehash(&digest, &signature).is_ok());
+
+        let keys = call(Method::GET, &format!("/list_keys?key_id={}", key_id), &admin, serde_json::json!({})).await;
+        let root = keys["keys"].as_array().unwrap().iter().find(|k| k["path"] == keys["root_path"]).unwrap();
+        assert!(key(&root["public_key_hex"]).verify_prehash(&digest, &signature).is_err());
+    }
+
+    #[tokio::test]
+    async fn only_listed_static_assets_are_public() {
+        for (route, _) in STATIC_ASSETS {
+            assert_eq!(status(Method::GET, route, None).await, StatusCode::OK, "{}", route);
+        }
+        assert_eq!(status(Method::GET, "/health", None).await, StatusCode::OK);
+
+        for uri in ["/static/index.html", "/main.rs", "/../Cargo.toml", "/index_clean.html/", "/unknown"] {
+            assert_eq!(status(Method::GET, uri, None).await, StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// Master key handling shared by the service and the participant nodes: loading and
+// verifying the master key, and sealing values in envelopes wrapped by it. Nothing in
+// here knows about wallets, so the node binary includes this file on its own.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::Result;
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// Master key sources: a passphrase stretched with Argon2id, or a 32-byte keyfile
+const MASTER_PASSPHRASE_ENV: &str = "WAAS_MASTER_PASSPHRASE";
+const MASTER_KEYFILE_ENV: &str = "WAAS_MASTER_KEYFILE";
+const DEFAULT_MASTER_KEYFILE: &str = "master_key.hex";
+// Salt and a known-plaintext verifier, so a wrong master key is caught at startup.
+// Kept next to the key material it protects: the storage directory or a node's data directory.
+const MASTER_KEY_METADATA_FILE: &str = "master_key.json";
+const VERIFIER_PLAINTEXT: &[u8] = b"waas master key verifier";
+// Version 0 stores may still hold plaintext or envelopes from earlier formats; the
+// owner's startup migration rewrites them and then calls complete_migration
+const KEY_MATERIAL_VERSION: u32 = 1;
+
+pub const ENCRYPTED_FORMAT: &str = "waas-encrypted-v1";
+
+static MASTER_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+// Metadata of a store that still has to be migrated, and where to write it back
+static PENDING_MIGRATION: Mutex<Option<(PathBuf, MasterKeyMetadata)>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SealedBlob {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+// A value encrypted under its own random data key, which is in turn wrapped by the master key
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EncryptedEnvelope {
+    pub format: String,
+    pub wrapped_key: SealedBlob,
+    pub payload: SealedBlob,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MasterKeyMetadata {
+    source: String, // "passphrase" or "keyfile"
+    salt: Option<String>,
+    verifier: SealedBlob,
+    created_at: String,
+    #[serde(default)]
+    version: u32, // Bound into the verifier, so it can't be rolled back without the master key
+}
+
+impl MasterKeyMetadata {
+    fn verifier_context(version: u32) -> String {
+        match version {
+            0 => MASTER_KEY_METADATA_FILE.to_string(),
+            version => format!("{}:v{}", MASTER_KEY_METADATA_FILE, version),
+        }
+    }
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<SealedBlob> {
+    let cipher = Aes256Gcm::new(key.into());
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+    Ok(SealedBlob {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn open(key: &[u8; 32], blob: &SealedBlob, aad: &[u8]) -> Result<Vec<u8>> {
+    let nonce = hex::decode(&blob.nonce)?;
+    if nonce.len() != 12 {
+        anyhow::bail!("Invalid nonce length");
+    }
+    let cipher = Aes256Gcm::new(key.into());
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &hex::decode(&blob.ciphertext)?, aad })
+        .map_err(|_| anyhow::anyhow!("Decryption failed: wrong master key or tampered data"))
+}
+
+fn master_key() -> Result<&'static [u8; 32]> {
+    MASTER_KEY
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("Master key not initialized"))
+}
+
+fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Argon2 key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn read_keyfile(path: &str) -> Result<[u8; 32]> {
+    let contents = std::fs::read(path)?;
+    // Accept raw 32 bytes or 64 hex characters
+    let bytes = match contents.len() {
+        32 => contents,
+        _ => hex::decode(String::from_utf8_lossy(&contents).trim())
+            .map_err(|_| anyhow::anyhow!("Keyfile {} must hold 32 raw bytes or 64 hex characters", path))?,
+    };
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Keyfile {} must hold a 32-byte key", path))
+}
+
+fn create_keyfile(path: &str) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    std::fs::write(path, hex::encode(key))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(key)
+}
+
+// The service used to write master_key.json to the working directory
+pub fn move_legacy_metadata(storage_dir: &Path) -> Result<()> {
+    let legacy_path = Path::new(MASTER_KEY_METADATA_FILE);
+    let metadata_path = storage_dir.join(MASTER_KEY_METADATA_FILE);
+    if metadata_path.exists() || !legacy_path.is_file() || metadata_path == legacy_path {
+        return Ok(());
+    }
+    std::fs::create_dir_all(storage_dir)?;
+    std::fs::rename(legacy_path, &metadata_path).map_err(|e| {
+        anyhow::anyhow!("Failed to move {} to {}: {}", MASTER_KEY_METADATA_FILE, metadata_path.display(), e)
+    })?;
+    tracing::info!(path = %metadata_path.display(), "🔄 Moved master key metadata into the storage directory");
+    Ok(())
+}
+
+// Loads the master key and checks it against the verifier in `dir`. Returns an error
+// (and the server refuses to start) if the key does not match the stored key material.
+pub fn init_master_key(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create directory {}: {}", dir.display(), e))?;
+    let metadata_path = dir.join(MASTER_KEY_METADATA_FILE);
+    let metadata: Option<MasterKeyMetadata> = match std::fs::read_to_string(&metadata_path) {
+        Ok(json) => Some(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => anyhow::bail!("Failed to read {}: {}", metadata_path.display(), e),
+    };
+
+    let passphrase = std::env::var(MASTER_PASSPHRASE_ENV).ok().filter(|p| !p.is_empty());
+    let (key, source, salt) = match passphrase {
+        Some(passphrase) => {
+            let salt = match metadata.as_ref().and_then(|m| m.salt.as_ref()) {
+                Some(salt) => hex::decode(salt)?,
+                None => {
+                    let mut salt = vec![0u8; 16];
+                    OsRng.fill_bytes(&mut salt);
+                    salt
+                }
+            };
+            (derive_passphrase_key(&passphrase, &salt)?, "passphrase", Some(salt))
+        }
+        None => {
+            let path = std::env::var(MASTER_KEYFILE_ENV).unwrap_or_else(|_| DEFAULT_MASTER_KEYFILE.to_string());
+            let key = if std::path::Path::new(&path).exists() {
+                read_keyfile(&path)?
+            } else if metadata.is_some() {
+                anyhow::bail!(
+                    "Master keyfile {} not found but encrypted key material exists; set {} or {}",
+                    path,
+                    MASTER_KEYFILE_ENV,
+                    MASTER_PASSPHRASE_ENV
+                );
+            } else {
+                tracing::warn!(
+                    keyfile = %path,
+                    "⚠️ No master key configured, generated a new keyfile. Keep it away from the key material in production"
+                );
+                create_keyfile(&path)?
+            };
+            (key, "keyfile", None)
+        }
+    };
+
+    let metadata = match metadata {
+        Some(metadata) => {
+            if metadata.source != source {
+                anyhow::bail!(
+                    "Key material was encrypted with a {} master key, but a {} was configured",
+                    metadata.source,
+                    source
+                );
+            }
+            let context = MasterKeyMetadata::verifier_context(metadata.version);
+            open(&key, &metadata.verifier, context.as_bytes())
+                .map_err(|_| anyhow::anyhow!("Master key does not match the key used to encrypt stored key material"))?;
+            tracing::info!(source = source, "🔐 Master key verified");
+            metadata
+        }
+        None => {
+            // Stores from before encryption at rest have no metadata yet, so start at version 0
+            let metadata = MasterKeyMetadata {
+                source: source.to_string(),
+                salt: salt.map(hex::encode),
+                verifier: seal(&key, VERIFIER_PLAINTEXT, MasterKeyMetadata::verifier_context(0).as_bytes())?,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                version: 0,
+            };
+            std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+            tracing::info!(source = source, path = %metadata_path.display(), "🔐 Master key initialized");
+            metadata
+        }
+    };
+    if metadata.version < KEY_MATERIAL_VERSION {
+        *PENDING_MIGRATION.lock().unwrap() = Some((metadata_path, metadata));
+    }
+
+    MASTER_KEY
+        .set(key)
+        .map_err(|_| anyhow::anyhow!("Master key already initialized"))?;
+    Ok(())
+}
+
+// Encrypts a value under a fresh data key; `context` is bound as associated data so
+// an envelope can't be swapped into another slot
+pub fn seal_json<T: Serialize>(value: &T, context: &str) -> Result<EncryptedEnvelope> {
+    seal_envelope(&serde_json::to_vec(value)?, context)
+}
+
+pub fn open_json<T: DeserializeOwned>(envelope: &EncryptedEnvelope, context: &str) -> Result<T> {
+    Ok(serde_json::from_slice(&open_envelope(envelope, context)?)?)
+}
+
+pub fn seal_envelope(plaintext: &[u8], context: &str) -> Result<EncryptedEnvelope> {
+    let mut data_key = [0u8; 32];
+    OsRng.fill_bytes(&mut data_key);
+    let payload = seal(&data_key, plaintext, context.as_bytes())?;
+    let wrapped_key = seal(master_key()?, &data_key, context.as_bytes())?;
+    Ok(EncryptedEnvelope {
+        format: ENCRYPTED_FORMAT.to_string(),
+        wrapped_key,
+        payload,
+    })
+}
+
+pub fn open_envelope(envelope: &EncryptedEnvelope, context: &str) -> Result<Vec<u8>> {
+    let data_key: [u8; 32] = open(master_key()?, &envelope.wrapped_key, context.as_bytes())?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid wrapped data key"))?;
+    open(&data_key, &envelope.payload, context.as_bytes())
+}
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+fn keyed_mac(context: &str, data: &[u8]) -> Result<HmacSha256> {
+    use hmac::Mac;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(master_key()?)
+        .map_err(|e| anyhow::anyhow!("Invalid MAC key: {}", e))?;
+    mac.update(context.as_bytes());
+    mac.update(&[0]);
+    mac.update(data);
+    Ok(mac)
+}
+
+// Authenticates data that is stored in the clear but must not be rewritten by
+// someone without the master key; `context` separates the different uses
+pub fn mac(context: &str, data: &[u8]) -> Result<String> {
+    use hmac::Mac;
+    Ok(hex::encode(keyed_mac(context, data)?.finalize().into_bytes()))
+}
+
+pub fn verify_mac(context: &str, data: &[u8], tag: &str) -> Result<bool> {
+    use hmac::Mac;
+    let Ok(tag) = hex::decode(tag) else {
+        return Ok(false);
+    };
+    Ok(keyed_mac(context, data)?.verify_slice(&tag).is_ok())
+}
+
+
+// Whether the store still has to go through its one-time migration
+pub fn migration_pending() -> bool {
+    PENDING_MIGRATION.lock().unwrap().is_some()
+}
+
+// Marks the store as migrated once every record has been rewritten in the current format
+pub fn complete_migration() -> Result<()> {
+    let Some((metadata_path, mut metadata)) = PENDING_MIGRATION.lock().unwrap().take() else {
+        return Ok(());
+    };
+    metadata.version = KEY_MATERIAL_VERSION;
+    metadata.verifier = seal(
+        master_key()?,
+        VERIFIER_PLAINTEXT,
+        MasterKeyMetadata::verifier_context(metadata.version).as_bytes(),
+    )?;
+    std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+    tracing::info!(version = metadata.version, "🔐 Key material migrated to the current encryption format");
+    Ok(())
+}
+
+pub fn is_encrypted(json: &str, format: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| value.get("format").and_then(|f| f.as_str()).map(|f| f == format))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+pub fn init_test_master_key() {
+    let _ = MASTER_KEY.set([7u8; 32]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifier_version_cannot_be_rolled_back() {
+        let key = [9u8; 32];
+        let verifier = seal(&key, VERIFIER_PLAINTEXT, MasterKeyMetadata::verifier_context(KEY_MATERIAL_VERSION).as_bytes()).unwrap();
+        assert!(open(&key, &verifier, MasterKeyMetadata::verifier_context(KEY_MATERIAL_VERSION).as_bytes()).is_ok());
+        assert!(open(&key, &verifier, MasterKeyMetadata::verifier_context(0).as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+// Wire types shared by the coordinator (main server) and the participant node binary.
+// Both sides include this file, so it must only depend on external crates.
+
+use serde::{Deserialize, Serialize};
+use tss_ecdsa::{messages::Message, Identifier, ParticipantConfig, ParticipantIdentifier};
+
+// Shared secret nodes and coordinator use to authenticate each other (sent as a Bearer token)
+pub const NODE_TOKEN_ENV: &str = "WAAS_NODE_TOKEN";
+
+pub const START_SESSION_PATH: &str = "/node/session/start";
+pub const DELIVER_MESSAGE_PATH: &str = "/node/session/message";
+pub const NODE_STATUS_PATH: &str = "/node/status";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Keygen,
+    AuxInfo,
+    Presign,
+    Sign,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartSessionRequest {
+    pub session_id: Identifier,
+    pub phase: Phase,
+    pub config: Option<ParticipantConfig>, // Keygen only: the identity the coordinator assigns to this node
+    pub message_hex: Option<String>,       // Sign only: message to sign
+    pub presign_session_id: Option<Identifier>, // Sign only: presign session whose record to consume
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliverMessageRequest {
+    pub session_id: Identifier,
+    pub message: Message,
+}
+
+// Public results only; private shares never leave the node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum SessionOutput {
+    Keygen { public_key: String, chain_code: String },
+    AuxInfo,
+    Presign,
+    Sign { signature: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub success: bool,
+    pub message: String,
+    pub outgoing: Vec<Message>,
+    pub output: Option<SessionOutput>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeStatusResponse {
+    pub success: bool,
+    pub participant_id: Option<ParticipantIdentifier>,
+    pub has_key_share: bool,
+    pub has_auxinfo: bool,
+    pub presign_records: usize,
+    pub active_sessions: usize,
+}
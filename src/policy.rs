@@ -0,0 +1,726 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use axum::{
+    extract::{Json, Query},
+    http::StatusCode,
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hd_keys::DerivationPath;
+use crate::session_store::{read_service_json, write_service_json, ServiceRecord};
+
+// Serializes read-modify-write cycles of the policy file
+static POLICY_LOCK: Mutex<()> = Mutex::new(());
+
+// Signing times inside the current rate limit window, per policy. Kept in memory only,
+// so every window starts empty after a restart.
+static RATE_WINDOWS: Mutex<BTreeMap<String, VecDeque<Instant>>> = Mutex::new(BTreeMap::new());
+
+// What a signing request asks to sign, as far as policies can tell
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageFormat {
+    Text,   // Anything not matching a more specific format
+    Hex,    // 0x-prefixed hex data
+    Json,   // A JSON object or array
+    Digest, // A 32-byte digest from the caller, whose message policies cannot see
+}
+
+impl MessageFormat {
+    pub fn detect(message: &str) -> Self {
+        let trimmed = message.trim();
+        if let Some(digits) = trimmed.strip_prefix("0x")
+            && !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return MessageFormat::Hex;
+        }
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            return MessageFormat::Json;
+        }
+        MessageFormat::Text
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageFormat::Text => "text",
+            MessageFormat::Hex => "hex",
+            MessageFormat::Json => "json",
+            MessageFormat::Digest => "digest",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimit {
+    pub max_signatures: u32,
+    pub window_secs: u64,
+}
+
+// Rules of one policy; every rule left out is not checked
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PolicyRules {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_formats: Option<Vec<MessageFormat>>,
+    // Largest declared value, as a decimal string in the chain's base unit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_allowlist: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub destination_denylist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub required_approvals: u32,
+    // Users who may approve; when empty, any approver or admin other than the requester
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvers: Vec<String>,
+    // How long a signing request waits for its approvals (default 24 hours)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_timeout_secs: Option<u64>,
+}
+
+// A policy covers a whole wallet (no path) or one derived key
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Policy {
+    pub key_id: String,
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub rules: PolicyRules,
+    pub updated_at: String,
+    pub updated_by: String,
+}
+
+impl Policy {
+    fn scope(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{}:{}", self.key_id, path),
+            None => format!("{}:*", self.key_id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct PolicyStore {
+    policies: Vec<Policy>,
+}
+
+// A signing request as seen by the policy engine
+pub struct SigningContext<'a> {
+    pub key_id: &'a str,
+    pub path: &'a str, // Canonical derivation path of the signing key
+    pub format: MessageFormat,
+    pub destination: Option<&'a str>,
+    pub value: Option<&'a str>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Evaluation {
+    pub allowed: bool,
+    pub required_approvals: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub approvers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_timeout_secs: Option<u64>,
+    pub violations: Vec<String>,
+    pub policy: Option<String>, // Scope of the policy that applied, e.g. "<key_id>:m/0/5"
+}
+
+#[derive(Deserialize)]
+pub struct SetPolicyRequest {
+    pub key_id: String,
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub rules: PolicyRules,
+}
+
+#[derive(Deserialize)]
+pub struct DeletePolicyRequest {
+    pub key_id: String,
+    pub path: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EvaluatePolicyRequest {
+    pub key_id: String,
+    #[serde(default)]
+    pub message: String,
+    pub digest: Option<String>, // As for /sign; only its presence matters
+    pub path: Option<String>,
+    pub child_index: Option<u32>,
+    pub destination: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PoliciesResponse {
+    pub success: bool,
+    pub message: String,
+    pub policies: Vec<Policy>,
+}
+
+#[derive(Serialize)]
+pub struct EvaluatePolicyResponse {
+    pub success: bool,
+    pub message: String,
+    pub evaluation: Option<Evaluation>,
+}
+
+fn load_policies() -> Result<PolicyStore> {
+    Ok(read_service_json(ServiceRecord::Policies)?.unwrap_or_default())
+}
+
+fn save_policies(store: &PolicyStore) -> Result<()> {
+    write_service_json(ServiceRecord::Policies, store)
+}
+
+fn parse_value(value: &str) -> Result<u128> {
+    value
+        .trim()
+        .parse::<u128>()
+        .map_err(|_| anyhow::anyhow!("Invalid value '{}': expected a non-negative integer", value))
+}
+
+fn normalize_address(address: &str) -> String {
+    address.trim().to_ascii_lowercase()
+}
+
+fn canonical_path(path: Option<&str>) -> Result<Option<String>> {
+    path.map(|path| Ok(DerivationPath::parse(path)?.to_string())).transpose()
+}
+
+fn validate_rules(rules: &PolicyRules) -> Result<()> {
+    if let Some(max_value) = &rules.max_value {
+        parse_value(max_value)?;
+    }
+    if let Some(formats) = &rules.allowed_formats
+        && formats.is_empty()
+    {
+        anyhow::bail!("allowed_formats must not be empty; remove it to allow every format");
+    }
+    if let Some(rate_limit) = &rules.rate_limit
+        && (rate_limit.max_signatures == 0 || rate_limit.window_secs == 0)
+    {
+        anyhow::bail!("rate_limit needs a positive max_signatures and window_secs");
+    }
+    if !rules.approvers.is_empty() && (rules.approvers.len() as u32) < rules.required_approvals {
+        anyhow::bail!(
+            "{} approvals required but only {} approvers designated",
+            rules.required_approvals,
+            rules.approvers.len()
+        );
+    }
+    if rules.approval_timeout_secs == Some(0) {
+        anyhow::bail!("approval_timeout_secs must be positive");
+    }
+    Ok(())
+}
+
+// The policy for a signing key: the one for its exact path, else the wallet-wide one
+fn find_policy<'a>(store: &'a PolicyStore, key_id: &str, path: &str) -> Option<&'a Policy> {
+    store
+        .policies
+        .iter()
+        .find(|policy| policy.key_id == key_id && policy.path.as_deref() == Some(path))
+        .or_else(|| store.policies.iter().find(|policy| policy.key_id == key_id && policy.path.is_none()))
+}
+
+fn check_rules(rules: &PolicyRules, context: &SigningContext) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    // Nothing ties a caller's digest to the declared value and destination
+    let content_rules = rules.max_value.is_some()
+        || rules.destination_allowlist.is_some()
+        || !rules.destination_denylist.is_empty();
+    if context.format == MessageFormat::Digest && content_rules {
+        violations.push("Policy checks value or destination, which a caller-supplied digest cannot prove".to_string());
+    }
+
+    if let Some(formats) = &rules.allowed_formats
+        && !formats.contains(&context.format)
+    {
+        violations.push(format!("Message format '{}' is not allowed", context.format.name()));
+    }
+
+    if let Some(max_value) = &rules.max_value {
+        match context.value.map(parse_value) {
+            None => violations.push("Policy limits the value, but the request declares none".to_string()),
+            Some(Err(e)) => violations.push(e.to_string()),
+            Some(Ok(value)) => {
+                // max_value was validated when the policy was stored
+                if value > parse_value(max_value).unwrap_or(0) {
+                    violations.push(format!("Value {} exceeds the maximum of {}", value, max_value));
+                }
+            }
+        }
+    }
+
+    let destination = context.destination.map(normalize_address);
+    if let Some(allowlist) = &rules.destination_allowlist {
+        match &destination {
+            None => violations.push("Policy restricts destinations, but the request declares none".to_string()),
+            Some(destination) if !allowlist.iter().any(|allowed| normalize_address(allowed) == *destination) => {
+                violations.push(format!("Destination {} is not on the allowlist", destination));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(destination) = &destination
+        && rules.destination_denylist.iter().any(|denied| normalize_address(denied) == *destination)
+    {
+        violations.push(format!("Destination {} is on the denylist", destination));
+    }
+
+    violations
+}
+
+// Checks the rate limit and, when `reserve` is set and the request passes,
+// counts it against the window
+fn check_rate_limit(scope: &str, rate_limit: &RateLimit, reserve: bool) -> Option<String> {
+    let window = Duration::from_secs(rate_limit.window_secs);
+    let now = Instant::now();
+    let mut windows = RATE_WINDOWS.lock().unwrap();
+    let signed = windows.entry(scope.to_string()).or_default();
+    while signed.front().is_some_and(|at| now.duration_since(*at) >= window) {
+        signed.pop_front();
+    }
+    if signed.len() >= rate_limit.max_signatures as usize {
+        return Some(format!(
+            "Rate limit of {} signatures per {}s reached",
+            rate_limit.max_signatures, rate_limit.window_secs
+        ));
+    }
+    if reserve {
+        signed.push_back(now);
+    }
+    None
+}
+
+// When an allowed request counts against the rate limit
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Reserve {
+    Never,         // Dry runs
+    UnlessPending, // New requests, unless they wait for approvals and are counted once signed
+    Always,        // Approved requests about to be signed
+}
+
+fn evaluate_impl(context: &SigningContext, reserve: Reserve) -> Result<Evaluation> {
+    let store = load_policies()?;
+    let Some(policy) = find_policy(&store, context.key_id, context.path) else {
+        return Ok(Evaluation {
+            allowed: true,
+            required_approvals: 0,
+            approvers: vec![],
+            approval_timeout_secs: None,
+            violations: vec![],
+            policy: None,
+        });
+    };
+
+    let mut violations = check_rules(&policy.rules, context);
+    // Only count requests that pass every other rule and are signed right away
+    let reserve = match reserve {
+        Reserve::Never => false,
+        Reserve::UnlessPending => policy.rules.required_approvals == 0,
+        Reserve::Always => true,
+    };
+    if let Some(rate_limit) = &policy.rules.rate_limit
+        && let Some(violation) = check_rate_limit(&policy.scope(), rate_limit, reserve && violations.is_empty())
+    {
+        violations.push(violation);
+    }
+
+    Ok(Evaluation {
+        allowed: violations.is_empty(),
+        required_approvals: policy.rules.required_approvals,
+        approvers: policy.rules.approvers.clone(),
+        approval_timeout_secs: policy.rules.approval_timeout_secs,
+        violations,
+        policy: Some(policy.scope()),
+    })
+}
+
+// Evaluates the policy for a new signing request. An allowed request that is
+// signed right away counts against the policy's rate limit; one that has to wait
+// for approvals is counted by `enforce_approved` when it is signed.
+pub fn enforce(context: &SigningContext) -> Result<Evaluation> {
+    evaluate_impl(context, Reserve::UnlessPending)
+}
+
+// Evaluates the policy again for an approved request about to be signed, which
+// counts against the rate limit if it is still allowed
+pub fn enforce_approved(context: &SigningContext) -> Result<Evaluation> {
+    evaluate_impl(context, Reserve::Always)
+}
+
+// Evaluates without side effects, for the dry-run endpoint
+pub fn dry_run(context: &SigningContext) -> Result<Evaluation> {
+    evaluate_impl(context, Reserve::Never)
+}
+
+// Drops the policies of a deleted wallet
+pub fn remove_wallet_policies(key_id: &str) -> Result<usize> {
+    let _guard = POLICY_LOCK.lock().unwrap();
+    let mut store = load_policies()?;
+    let before = store.policies.len();
+    store.policies.retain(|policy| policy.key_id != key_id);
+    let removed = before - store.policies.len();
+    if removed > 0 {
+        save_policies(&store)?;
+    }
+    Ok(removed)
+}
+
+fn set_policy_impl(auth: &crate::BasicAuth, request: SetPolicyRequest) -> Result<Policy> {
+    crate::wallets::authorize(auth, &request.key_id)?;
+    validate_rules(&request.rules)?;
+    let policy = Policy {
+        path: canonical_path(request.path.as_deref())?,
+        key_id: request.key_id,
+        rules: request.rules,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        updated_by: auth.username.clone(),
+    };
+
+    let _guard = POLICY_LOCK.lock().unwrap();
+    let mut store = load_policies()?;
+    store
+        .policies
+        .retain(|existing| !(existing.key_id == policy.key_id && existing.path == policy.path));
+    store.policies.push(policy.clone());
+    save_policies(&store)?;
+    Ok(policy)
+}
+
+fn delete_policy_impl(auth: &crate::BasicAuth, request: &DeletePolicyRequest) -> Result<Policy> {
+    crate::wallets::authorize(auth, &request.key_id)?;
+    let path = canonical_path(request.path.as_deref())?;
+
+    let _guard = POLICY_LOCK.lock().unwrap();
+    let mut store = load_policies()?;
+    let index = store
+        .policies
+        .iter()
+        .position(|policy| policy.key_id == request.key_id && policy.path == path)
+        .ok_or_else(|| anyhow::anyhow!("No policy for {} at {}", request.key_id, path.as_deref().unwrap_or("wallet level")))?;
+    let removed = store.policies.remove(index);
+    save_policies(&store)?;
+    Ok(removed)
+}
+
+fn error_status(e: &anyhow::Error) -> StatusCode {
+    if crate::wallets::is_wallet_not_found(e) {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+// Policies of one wallet (GET)
+pub async fn list_policies(
+    auth: crate::BasicAuth,
+    Query(query): Query<crate::wallets::WalletQuery>,
+) -> (StatusCode, ResponseJson<PoliciesResponse>) {
+    let result = crate::wallets::authorize(&auth, &query.key_id).and_then(|_| load_policies());
+    match result {
+        Ok(store) => {
+            let policies: Vec<Policy> = store
+                .policies
+                .into_iter()
+                .filter(|policy| policy.key_id == query.key_id)
+                .collect();
+            (StatusCode::OK, ResponseJson(PoliciesResponse {
+                success: true,
+                message: format!("{} policies", policies.len()),
+                policies,
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(key_id = %query.key_id, error = %e, "⚠️ Failed to list policies");
+            (error_status(&e), ResponseJson(PoliciesResponse {
+                success: false,
+                message: format!("Failed to list policies: {}", e),
+                policies: vec![],
+            }))
+        }
+    }
+}
+
+// Create or replace the policy of a wallet or derived key (POST)
+pub async fn set_policy(
+    auth: crate::BasicAuth,
+    Json(request): Json<SetPolicyRequest>,
+) -> (StatusCode, ResponseJson<PoliciesResponse>) {
+    let key_id = request.key_id.clone();
+    match set_policy_impl(&auth, request) {
+        Ok(policy) => {
+            tracing::info!(
+                key_id = %policy.key_id,
+                path = ?policy.path,
+                updated_by = %policy.updated_by,
+                required_approvals = policy.rules.required_approvals,
+                "📜 Signing policy stored"
+            );
+            (StatusCode::OK, ResponseJson(PoliciesResponse {
+                success: true,
+                message: format!("Policy for {} stored", policy.scope()),
+                policies: vec![policy],
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(key_id = %key_id, error = %e, "⚠️ Failed to store policy");
+            (error_status(&e), ResponseJson(PoliciesResponse {
+                success: false,
+                message: format!("Failed to store policy: {}", e),
+                policies: vec![],
+            }))
+        }
+    }
+}
+
+// Remove the policy of a wallet or derived key (POST)
+pub async fn delete_policy(
+    auth: crate::BasicAuth,
+    Json(request): Json<DeletePolicyRequest>,
+) -> (StatusCode, ResponseJson<PoliciesResponse>) {
+    match delete_policy_impl(&auth, &request) {
+        Ok(policy) => {
+            tracing::info!(key_id = %policy.key_id, path = ?policy.path, deleted_by = %auth.username, "📜 Signing policy deleted");
+            (StatusCode::OK, ResponseJson(PoliciesResponse {
+                success: true,
+                message: format!("Policy for {} deleted", policy.scope()),
+                policies: vec![policy],
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, error = %e, "⚠️ Failed to delete policy");
+            (error_status(&e), ResponseJson(PoliciesResponse {
+                success: false,
+                message: format!("Failed to delete policy: {}", e),
+                policies: vec![],
+            }))
+        }
+    }
+}
+
+// Evaluate a signing request against the policies without signing (POST)
+pub async fn evaluate_policy(
+    auth: crate::BasicAuth,
+    Json(request): Json<EvaluatePolicyRequest>,
+) -> (StatusCode, ResponseJson<EvaluatePolicyResponse>) {
+    let result = crate::sign::resolve_key_path(&auth, &request.key_id, request.path.as_deref(), request.child_index)
+        .and_then(|key_path| {
+            dry_run(&SigningContext {
+                key_id: &request.key_id,
+                path: &key_path,
+                format: crate::sign::message_format(crate::sign::SigningMode::Raw, &request.message, request.digest.as_deref()),
+                destination: request.destination.as_deref(),
+                value: request.value.as_deref(),
+            })
+        });
+
+    match result {
+        Ok(evaluation) => {
+            let message = if !evaluation.allowed {
+                format!("Signing would be denied: {}", evaluation.violations.join("; "))
+            } else if evaluation.required_approvals > 0 {
+                format!("Signing would need {} approvals", evaluation.required_approvals)
+            } else {
+                "Signing would be allowed".to_string()
+            };
+            tracing::debug!(
+                key_id = %request.key_id,
+                allowed = evaluation.allowed,
+                violations = evaluation.violations.len(),
+                "📜 Policy dry run evaluated"
+            );
+            (StatusCode::OK, ResponseJson(EvaluatePolicyResponse {
+                success: true,
+                message,
+                evaluation: Some(evaluation),
+            }))
+        }
+        Err(e) => (error_status(&e), ResponseJson(EvaluatePolicyResponse {
+            success: false,
+            message: format!("Policy evaluation failed: {}", e),
+            evaluation: None,
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context<'a>(key_id: &'a str, path: &'a str) -> SigningContext<'a> {
+        SigningContext { key_id, path, format: MessageFormat::Text, destination: None, value: None }
+    }
+
+    fn policy(key_id: &str, path: Option<&str>, rules: PolicyRules) -> Policy {
+        Policy {
+            key_id: key_id.to_string(),
+            path: path.map(str::to_string),
+            rules,
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_by: "admin".to_string(),
+        }
+    }
+
+    // Replaces the stored policies of `key_id`; test wallets use unique key IDs
+    fn store_policies(key_id: &str, policies: Vec<Policy>) {
+        crate::session_store::init_test_key_store();
+        let _guard = POLICY_LOCK.lock().unwrap();
+        let mut store = load_policies().unwrap();
+        store.policies.retain(|policy| policy.key_id != key_id);
+        store.policies.extend(policies);
+        save_policies(&store).unwrap();
+    }
+
+    fn unique_key_id(name: &str) -> String {
+        format!("policy-{}-{}", name, std::process::id())
+    }
+
+    #[test]
+    fn message_formats_are_detected() {
+        assert_eq!(MessageFormat::detect("0xdeadBEEF"), MessageFormat::Hex);
+        assert_eq!(MessageFormat::detect(" {\"to\": 1} "), MessageFormat::Json);
+        assert_eq!(MessageFormat::detect("[1, 2]"), MessageFormat::Json);
+        for text in ["hello", "0x", "0xnothex", "{not json", ""] {
+            assert_eq!(MessageFormat::detect(text), MessageFormat::Text, "{}", text);
+        }
+        // A caller's digest stands for a message policies never see
+        let digest = Some("00".repeat(32));
+        for mode in [crate::sign::SigningMode::Raw, crate::sign::SigningMode::Eip712] {
+            assert_eq!(crate::sign::message_format(mode, "", digest.as_deref()), MessageFormat::Digest);
+        }
+        assert_eq!(crate::sign::message_format(crate::sign::SigningMode::Eip712, "", None), MessageFormat::Json);
+    }
+
+    #[test]
+    fn value_destination_and_format_rules() {
+        let rules = PolicyRules {
+            allowed_formats: Some(vec![MessageFormat::Hex]),
+            max_value: Some("1000".to_string()),
+            destination_allowlist: Some(vec!["0xAbC".to_string(), "0xdef".to_string()]),
+            destination_denylist: vec!["0xDEF".to_string()],
+            ..Default::default()
+        };
+        let check = |format, destination, value| {
+            check_rules(&rules, &SigningContext { key_id: "k", path: "m", format, destination, value })
+        };
+
+        assert!(check(MessageFormat::Hex, Some("0xabc"), Some("1000")).is_empty());
+        assert_eq!(check(MessageFormat::Text, Some("0xabc"), Some("1")), vec!["Message format 'text' is not allowed"]);
+        assert_eq!(check(MessageFormat::Hex, Some("0xabc"), Some("1001")), vec!["Value 1001 exceeds the maximum of 1000"]);
+        assert_eq!(check(MessageFormat::Hex, Some("0xabc"), Some("-5")).len(), 1);
+        assert_eq!(
+            check(MessageFormat::Hex, Some("0xabc"), None),
+            vec!["Policy limits the value, but the request declares none"]
+        );
+        assert_eq!(check(MessageFormat::Hex, Some("0x123"), Some("1")), vec!["Destination 0x123 is not on the allowlist"]);
+        assert_eq!(
+            check(MessageFormat::Hex, None, Some("1")),
+            vec!["Policy restricts destinations, but the request declares none"]
+        );
+        // The denylist wins over the allowlist, whatever the case of the address
+        assert_eq!(check(MessageFormat::Hex, Some("0xDeF"), Some("1")), vec!["Destination 0xdef is on the denylist"]);
+        assert_eq!(check(MessageFormat::Json, Some("0x1"), Some("2000")).len(), 3);
+
+        // Declared fields don't vouch for a digest, even where they would pass
+        let digest = check(MessageFormat::Digest, Some("0xabc"), Some("1"));
+        assert_eq!(digest.len(), 2, "{:?}", digest);
+        assert!(digest[0].contains("caller-supplied digest"), "{:?}", digest);
+        let rules = PolicyRules { allowed_formats: Some(vec![MessageFormat::Digest]), ..Default::default() };
+        let context = SigningContext { key_id: "k", path: "m", format: MessageFormat::Digest, destination: None, value: None };
+        assert!(check_rules(&rules, &context).is_empty());
+    }
+
+    #[test]
+    fn exact_path_policy_wins_over_the_wallet_policy() {
+        let store = PolicyStore {
+            policies: vec![
+                policy("wallet", None, PolicyRules { required_approvals: 1, ..Default::default() }),
+                policy("wallet", Some("m/0/5"), PolicyRules { required_approvals: 2, ..Default::default() }),
+                policy("other", Some("m/0/6"), PolicyRules { required_approvals: 3, ..Default::default() }),
+            ],
+        };
+        let approvals = |key_id, path| find_policy(&store, key_id, path).map(|policy| policy.rules.required_approvals);
+        assert_eq!(approvals("wallet", "m/0/5"), Some(2));
+        assert_eq!(approvals("wallet", "m/0/6"), Some(1));
+        assert_eq!(approvals("wallet", "m"), Some(1));
+        assert_eq!(approvals("other", "m/0/5"), None);
+        assert_eq!(approvals("other", "m/0/6"), Some(3));
+    }
+
+    #[test]
+    fn rate_limit_counts_allowed_signatures_in_the_window() {
+        let scope = unique_key_id("window");
+        let limit = RateLimit { max_signatures: 2, window_secs: 3600 };
+        // Checks without reserving never fill the window
+        for _ in 0..5 {
+            assert!(check_rate_limit(&scope, &limit, false).is_none());
+        }
+        assert!(check_rate_limit(&scope, &limit, true).is_none());
+        assert!(check_rate_limit(&scope, &limit, true).is_none());
+        let violation = check_rate_limit(&scope, &limit, true).unwrap();
+        assert_eq!(violation, "Rate limit of 2 signatures per 3600s reached");
+
+        // Windows are kept per policy
+        assert!(check_rate_limit(&format!("{}-other", scope), &limit, true).is_none());
+    }
+
+    #[test]
+    fn requests_waiting_for_approval_are_counted_when_signed() {
+        let key_id = unique_key_id("approvals");
+        store_policies(&key_id, vec![policy(&key_id, None, PolicyRules {
+            rate_limit: Some(RateLimit { max_signatures: 1, window_secs: 3600 }),
+            required_approvals: 1,
+            ..Default::default()
+        })]);
+        let request = context(&key_id, "m");
+
+        // Any number of requests may wait for approval
+        for _ in 0..3 {
+            let evaluation = enforce(&request).unwrap();
+            assert!(evaluation.allowed, "{:?}", evaluation.violations);
+            assert_eq!(evaluation.required_approvals, 1);
+        }
+        assert!(enforce_approved(&request).unwrap().allowed);
+        assert!(!enforce_approved(&request).unwrap().allowed);
+        assert!(!enforce(&request).unwrap().allowed);
+        assert!(!dry_run(&request).unwrap().allowed);
+    }
+
+    #[test]
+    fn signatures_without_approval_are_counted_at_once_and_dry_runs_never() {
+        let key_id = unique_key_id("direct");
+        store_policies(&key_id, vec![
+            policy(&key_id, None, PolicyRules {
+                rate_limit: Some(RateLimit { max_signatures: 1, window_secs: 3600 }),
+                ..Default::default()
+            }),
+            policy(&key_id, Some("m/1"), PolicyRules { max_value: Some("10".to_string()), ..Default::default() }),
+        ]);
+
+        for _ in 0..3 {
+            assert!(dry_run(&context(&key_id, "m")).unwrap().allowed);
+        }
+        assert!(enforce(&context(&key_id, "m")).unwrap().allowed);
+        let refused = enforce(&context(&key_id, "m")).unwrap();
+        assert!(!refused.allowed);
+        assert_eq!(refused.policy, Some(format!("{}:*", key_id)));
+
+        // The derived key's own policy replaces the wallet policy, rate limit included
+        let derived = SigningContext { value: Some("5"), ..context(&key_id, "m/1") };
+        assert!(enforce(&derived).unwrap().allowed);
+        assert!(enforce(&derived).unwrap().allowed);
+        assert_eq!(enforce(&derived).unwrap().policy, Some(format!("{}:m/1", key_id)));
+    }
+}
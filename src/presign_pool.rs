@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::{extract::Query, http::StatusCode, response::Json as ResponseJson};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tss_ecdsa::{
+    curve::TestCurve,
+    presign::PresignRecord,
+    ParticipantConfig, ParticipantIdentifier,
+};
+
+use crate::keygen::KeygenHelperOutput;
+use crate::session_store::Record;
+
+const PRESIGN_POOL_TARGET_ENV: &str = "WAAS_PRESIGN_POOL_TARGET";
+const DEFAULT_PRESIGN_POOL_TARGET: usize = 5;
+const REFILL_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Serializes every read-modify-write of the pool file so a record is never handed out twice
+static POOL_LOCK: Mutex<()> = Mutex::new(());
+
+// Refill status per wallet, so one wallet's errors are never shown to another wallet's users
+static POOL_STATUS: Mutex<BTreeMap<String, RefillStatus>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Default)]
+struct RefillStatus {
+    refilling: bool,
+    generated_total: u64,
+    last_refill_at: Option<String>,
+    last_error: Option<String>,
+}
+
+// One presignature: a record for each signer of the quorum it was computed for
+#[derive(Serialize, Deserialize)]
+pub struct PresignPoolEntry {
+    pub id: u64,
+    pub signers: Vec<ParticipantIdentifier>,
+    pub created_at: String,
+    pub records: HashMap<ParticipantIdentifier, PresignRecord<TestCurve>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct PresignPool {
+    public_key: String, // Root key the records belong to; entries for any other key are stale
+    next_id: u64,
+    entries: Vec<PresignPoolEntry>,
+}
+
+#[derive(Serialize)]
+pub struct PresignPoolStatusResponse {
+    pub success: bool,
+    pub message: String,
+    pub available: usize,
+    pub target: usize,
+    pub refilling: bool,
+    pub generated_total: u64,
+    pub last_refill_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+pub fn pool_target() -> usize {
+    std::env::var(PRESIGN_POOL_TARGET_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_PRESIGN_POOL_TARGET)
+}
+
+fn load_pool(key_id: &str) -> Result<PresignPool> {
+    Ok(crate::encryption::read_encrypted(key_id, Record::PresignPool)?.unwrap_or_default())
+}
+
+// Presign records hold nonce shares, so the pool is encrypted like the key shares
+fn save_pool(key_id: &str, pool: &PresignPool) -> Result<()> {
+    crate::encryption::write_encrypted(key_id, Record::PresignPool, pool)
+}
+
+// Number of unused presignatures for the wallet's current key
+pub fn available(key_id: &str, keygen_result: &KeygenHelperOutput<TestCurve>) -> Result<usize> {
+    available_for_key(key_id, &crate::keygen::root_public_key_hex(keygen_result)?)
+}
+
+fn available_for_key(key_id: &str, public_key: &str) -> Result<usize> {
+    let _guard = POOL_LOCK.lock().unwrap();
+    let pool = load_pool(key_id)?;
+    if pool.public_key != public_key {
+        return Ok(0);
+    }
+    Ok(pool.entries.len())
+}
+
+// Removes a presignature from the pool and persists the removal before returning it,
+// so the nonce is gone from disk before the online sign round ever uses it.
+// With `signers` set, only an entry computed for exactly that quorum is taken.
+pub fn take_entry(
+    key_id: &str,
+    keygen_result: &KeygenHelperOutput<TestCurve>,
+    signers: Option<&[ParticipantIdentifier]>,
+) -> Result<Option<PresignPoolEntry>> {
+    take_entry_for_key(key_id, crate::keygen::root_public_key_hex(keygen_result)?, signers)
+}
+
+fn take_entry_for_key(
+    key_id: &str,
+    public_key: String,
+    signers: Option<&[ParticipantIdentifier]>,
+) -> Result<Option<PresignPoolEntry>> {
+    let _guard = POOL_LOCK.lock().unwrap();
+    let mut pool = load_pool(key_id)?;
+
+    if pool.public_key != public_key {
+        if !pool.entries.is_empty() {
+            tracing::warn!(
+                stale_entries = pool.entries.len(),
+                "🗑️ Discarding presignatures generated for a previous key"
+            );
+        }
+        pool = PresignPool {
+            public_key,
+            next_id: pool.next_id,
+            entries: Vec::new(),
+        };
+        save_pool(key_id, &pool)?;
+        return Ok(None);
+    }
+
+    let position = pool.entries.iter().position(|entry| match signers {
+        Some(signers) => {
+            entry.signers.len() == signers.len()
+                && signers.iter().all(|pid| entry.signers.contains(pid))
+        }
+        None => true,
+    });
+    let Some(position) = position else {
+        return Ok(None);
+    };
+
+    let entry = pool.entries.remove(position);
+    save_pool(key_id, &pool)?;
+
+    tracing::info!(
+        key_id = %key_id,
+        entry_id = entry.id,
+        remaining = pool.entries.len(),
+        "🎟️ Presignature taken from pool"
+    );
+
+    Ok(Some(entry))
+}
+
+// Runs presign among the given signers, reusing their cached auxinfo outputs
+pub fn generate_entry(
+    key_id: &str,
+    keygen_result: &KeygenHelperOutput<TestCurve>,
+    signers: &[ParticipantIdentifier],
+) -> Result<PresignPoolEntry> {
+    use crate::presign::presign_helper;
+
+    let (configs, signer_outputs): (Vec<ParticipantConfig>, _) =
+        crate::keygen::signing_quorum(keygen_result, signers)?;
+
+    let public_key = crate::keygen::root_public_key_hex(keygen_result)?;
+    let auxinfo_outputs = crate::auxinfo_cache::load_or_generate_auxinfo(key_id, &public_key, &configs)?;
+
+    // SECURITY: Always use fresh entropy for presign generation - NEVER use deterministic seeds!
+    let presign_start = std::time::Instant::now();
+    let presign_result = presign_helper(
+        configs,
+        auxinfo_outputs,
+        signer_outputs,
+        StdRng::from_entropy(),
+    )?;
+    tracing::debug!(
+        duration_ms = presign_start.elapsed().as_millis(),
+        "✅ Presign records generated"
+    );
+
+    Ok(PresignPoolEntry {
+        id: 0,
+        signers: signers.to_vec(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        records: presign_result.presign_outputs,
+    })
+}
+
+// Adds one freshly computed presignature to the first wallet whose pool is below
+// target. Returns false when there is nothing to do (no wallets, or all pools full).
+// A wallet that fails to refill is logged and skipped so it cannot starve the others.
+fn refill_once(target: usize) -> Result<bool> {
+    for wallet in crate::wallets::list_wallets()? {
+        let result = refill_wallet(&wallet.key_id, target);
+        update_status(&wallet.key_id, |status| {
+            status.refilling = false;
+            match &result {
+                Ok(true) => {
+                    status.generated_total += 1;
+                    status.last_refill_at = Some(chrono::Utc::now().to_rfc3339());
+                    status.last_error = None;
+                }
+                Ok(false) => {}
+                Err(e) => status.last_error = Some(e.to_string()),
+            }
+        });
+        match result {
+            Ok(true) => return Ok(true),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(
+                    key_id = %wallet.key_id,
+                    error = %e,
+                    "⚠️ Presignature pool refill failed for wallet, continuing with the next one"
+                );
+            }
+        }
+    }
+    Ok(false)
+}
+
+fn refill_wallet(key_id: &str, target: usize) -> Result<bool> {
+    if !crate::sign::is_keygen_completed(key_id) {
+        return Ok(false);
+    }
+
+    let (configs, keygen_result) = crate::sign::load_keygen_outputs(key_id)?;
+    if available(key_id, &keygen_result)? >= target {
+        return Ok(false);
+    }
+
+    update_status(key_id, |status| status.refilling = true);
+    let mut rng = StdRng::from_entropy();
+    let signers = crate::keygen::select_signers(&configs, &keygen_result, None, &mut rng)?;
+    let mut entry = generate_entry(key_id, &keygen_result, &signers)?;
+
+    let _guard = POOL_LOCK.lock().unwrap();
+    let mut pool = load_pool(key_id)?;
+    let public_key = crate::keygen::root_public_key_hex(&keygen_result)?;
+    if pool.public_key != public_key {
+        pool = PresignPool {
+            public_key,
+            next_id: pool.next_id,
+            entries: Vec::new(),
+        };
+    }
+    entry.id = pool.next_id;
+    pool.next_id += 1;
+    pool.entries.push(entry);
+    save_pool(key_id, &pool)?;
+
+    tracing::info!(
+        key_id = %key_id,
+        available = pool.entries.len(),
+        target = target,
+        "🧮 Presignature added to pool"
+    );
+
+    Ok(true)
+}
+
+fn update_status(key_id: &str, update: impl FnOnce(&mut RefillStatus)) {
+    update(POOL_STATUS.lock().unwrap().entry(key_id.to_string()).or_default());
+}
+
+// Background task keeping the pool topped up to the configured target size
+pub fn spawn_refill_task() {
+    let target = pool_target();
+    if target == 0 {
+        tracing::info!("⏸️ Presignature pool disabled ({}=0)", PRESIGN_POOL_TARGET_ENV);
+        return;
+    }
+
+    tracing::info!(target = target, "🧮 Starting presignature pool refill task");
+
+    tokio::spawn(async move {
+        loop {
+            // Presign is CPU bound, so keep it off the async workers
+            let result = tokio::task::spawn_blocking(move || refill_once(target)).await;
+
+            let generated = match result {
+                Ok(Ok(generated)) => generated,
+                Ok(Err(e)) => {
+                    tracing::error!(error = %e, "❌ Presignature pool refill failed");
+                    false
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "❌ Presignature pool refill task panicked");
+                    // The wallet being refilled is unknown here, so none of them is refilling any more
+                    for status in POOL_STATUS.lock().unwrap().values_mut() {
+                        status.refilling = false;
+                    }
+                    false
+                }
+            };
+
+            if !generated {
+                tokio::time::sleep(REFILL_POLL_INTERVAL).await;
+            }
+        }
+    });
+}
+
+// Pool size and refill status endpoint (GET)
+pub async fn presign_pool_status(
+    auth: crate::BasicAuth,
+    Query(query): Query<crate::wallets::WalletQuery>,
+) -> (StatusCode, ResponseJson<PresignPoolStatusResponse>) {
+    let target = pool_target();
+
+    let available = crate::wallets::authorize(&auth, &query.key_id).and_then(|_| {
+        if crate::sign::is_keygen_completed(&query.key_id) {
+            crate::sign::load_keygen_outputs(&query.key_id)
+                .and_then(|(_, keygen_result)| available(&query.key_id, &keygen_result))
+        } else {
+            Ok(0)
+        }
+    });
+
+    match available {
+        Ok(available) => {
+            let status = POOL_STATUS.lock().unwrap().get(&query.key_id).cloned().unwrap_or_default();
+            tracing::debug!(
+                key_id = %query.key_id,
+                available = available,
+                target = target,
+                refilling = status.refilling,
+                "📊 Presignature pool status requested"
+            );
+            (StatusCode::OK, ResponseJson(PresignPoolStatusResponse {
+                success: true,
+                message: format!("{} of {} presignatures available", available, target),
+                available,
+                target,
+                refilling: status.refilling,
+                generated_total: status.generated_total,
+                last_refill_at: status.last_refill_at,
+                last_error: status.last_error,
+            }))
+        }
+        Err(e) => {
+            tracing::error!(key_id = %query.key_id, error = %e, "❌ Failed to read presignature pool");
+            let status_code = if crate::wallets::is_wallet_not_found(&e) {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::OK
+            };
+            (status_code, ResponseJson(PresignPoolStatusResponse {
+                success: false,
+                message: format!("Failed to read presignature pool: {}", e),
+                available: 0,
+                target,
+                refilling: false,
+                generated_total: 0,
+                last_refill_at: None,
+                last_error: None,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    fn init() -> String {
+        crate::session_store::init_test_key_store();
+        crate::master_key::init_test_master_key();
+        format!("pool-{}-{}", std::process::id(), rand::thread_rng().next_u64())
+    }
+
+    fn signer(byte: u8) -> ParticipantIdentifier {
+        ParticipantIdentifier::random(&mut StdRng::from_seed([byte; 32]))
+    }
+
+    fn add_entry(key_id: &str, public_key: &str, signers: Vec<ParticipantIdentifier>) {
+        let mut pool = load_pool(key_id).unwrap();
+        pool.public_key = public_key.to_string();
+        pool.entries.push(PresignPoolEntry {
+            id: pool.next_id,
+            signers,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            records: HashMap::new(),
+        });
+        pool.next_id += 1;
+        save_pool(key_id, &pool).unwrap();
+    }
+
+    #[test]
+    fn entries_are_taken_once_and_only_by_their_quorum() {
+        let key_id = init();
+        let (a, b, c) = (signer(1), signer(2), signer(3));
+        add_entry(&key_id, "02aa", vec![a, b]);
+        add_entry(&key_id, "02aa", vec![b, c]);
+        assert_eq!(available_for_key(&key_id, "02aa").unwrap(), 2);
+
+        // A requested quorum only matches an entry computed for exactly those signers
+        assert!(take_entry_for_key(&key_id, "02aa".into(), Some(&[a, c])).unwrap().is_none());
+        assert!(take_entry_for_key(&key_id, "02aa".into(), Some(&[a])).unwrap().is_none());
+        let taken = take_entry_for_key(&key_id, "02aa".into(), Some(&[c, b])).unwrap().unwrap();
+        assert_eq!(taken.id, 1);
+        assert!(take_entry_for_key(&key_id, "02aa".into(), Some(&[b, c])).unwrap().is_none());
+
+        let taken = take_entry_for_key(&key_id, "02aa".into(), None).unwrap().unwrap();
+        assert_eq!(taken.id, 0);
+        assert!(take_entry_for_key(&key_id, "02aa".into(), None).unwrap().is_none());
+        assert_eq!(available_for_key(&key_id, "02aa").unwrap(), 0);
+    }
+
+    #[test]
+    fn entries_of_a_previous_key_are_discarded() {
+        let key_id = init();
+        add_entry(&key_id, "02aa", vec![signer(1), signer(2)]);
+        add_entry(&key_id, "02aa", vec![signer(1), signer(2)]);
+
+        assert_eq!(available_for_key(&key_id, "03bb").unwrap(), 0);
+        assert!(take_entry_for_key(&key_id, "03bb".into(), None).unwrap().is_none());
+
+        // The reset is persisted, so the old entries are gone for the old key as well
+        let pool = load_pool(&key_id).unwrap();
+        assert_eq!(pool.public_key, "03bb");
+        assert!(pool.entries.is_empty());
+        assert_eq!(pool.next_id, 2);
+        assert_eq!(available_for_key(&key_id, "02aa").unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn status_is_only_reported_for_the_callers_wallet() {
+        let key_id = init();
+        crate::wallets::save_wallet(&crate::wallets::WalletInfo {
+            key_id: key_id.clone(),
+            name: None,
+            public_key: "02aa".to_string(),
+            threshold: 2,
+            participants: 3,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            owner: Some("pool-owner".to_string()),
+        })
+        .unwrap();
+        update_status(&key_id, |status| status.last_error = Some("presign failed".to_string()));
+
+        let status = |username: &str| {
+            let auth = crate::BasicAuth {
+                username: username.to_string(),
+                role: crate::rbac::Role::Viewer,
+                token_id: None,
+                scopes: None,
+            };
+            presign_pool_status(auth, Query(crate::wallets::WalletQuery { key_id: key_id.clone() }))
+        };
+
+        let (status_code, ResponseJson(response)) = status("pool-owner").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(response.last_error.as_deref(), Some("presign failed"));
+
+        let (status_code, ResponseJson(response)) = status("pool-stranger").await;
+        assert_eq!(status_code, StatusCode::NOT_FOUND);
+        assert_eq!(response.last_error, None);
+
+        // Another wallet of the owner has no refill history of its own
+        update_status(&format!("{}-other", key_id), |status| status.generated_total = 3);
+        let (_, ResponseJson(response)) = status("pool-owner").await;
+        assert_eq!(response.generated_total, 0);
+    }
+}
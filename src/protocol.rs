@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, CryptoRng, RngCore};
+use tss_ecdsa::{messages::Message, Participant, ParticipantIdentifier, ProtocolParticipant};
+
+// Upper bound on processed messages per protocol run; far above what any of our
+// protocols needs for 16 participants, low enough to stop a runaway exchange
+pub const MAX_PROTOCOL_STEPS: usize = 100_000;
+
+// What the message loop needs from a protocol participant; implemented by the
+// tss-ecdsa participants, and by scripted participants in the tests
+trait QuorumMember {
+    type Message;
+    type Output;
+
+    fn id(&self) -> ParticipantIdentifier;
+    fn initialize_message(&self) -> anyhow::Result<Self::Message>;
+    fn process_message<R: RngCore + CryptoRng>(
+        &mut self,
+        message: &Self::Message,
+        rng: &mut R,
+    ) -> anyhow::Result<Step<Self>>;
+    fn sender(message: &Self::Message) -> ParticipantIdentifier;
+    fn recipient(message: &Self::Message) -> ParticipantIdentifier;
+}
+
+// Output of processing one message, if the participant finished, and the messages it sent
+type Step<M> = (Option<<M as QuorumMember>::Output>, Vec<<M as QuorumMember>::Message>);
+
+impl<P: ProtocolParticipant> QuorumMember for Participant<P> {
+    type Message = Message;
+    type Output = P::Output;
+
+    fn id(&self) -> ParticipantIdentifier {
+        Participant::id(self)
+    }
+
+    fn initialize_message(&self) -> anyhow::Result<Message> {
+        Ok(Participant::initialize_message(self)?)
+    }
+
+    fn process_message<R: RngCore + CryptoRng>(
+        &mut self,
+        message: &Message,
+        rng: &mut R,
+    ) -> anyhow::Result<Step<Self>> {
+        Ok(self.process_single_message(message, rng)?)
+    }
+
+    fn sender(message: &Message) -> ParticipantIdentifier {
+        message.from()
+    }
+
+    fn recipient(message: &Message) -> ParticipantIdentifier {
+        message.to()
+    }
+}
+
+// Runs an in-process protocol among `quorum` until every participant has produced
+// an output. Messages are delivered to a randomly chosen participant, oldest first
+// within each inbox, which exercises the protocols' tolerance to reordering the way
+// a real network would.
+pub fn run_protocol<P, R>(
+    name: &str,
+    quorum: &mut [Participant<P>],
+    rng: &mut R,
+) -> anyhow::Result<HashMap<ParticipantIdentifier, P::Output>>
+where
+    P: ProtocolParticipant,
+    R: RngCore + CryptoRng,
+{
+    exchange_messages(name, quorum, rng)
+}
+
+fn exchange_messages<M, R>(
+    name: &str,
+    quorum: &mut [M],
+    rng: &mut R,
+) -> anyhow::Result<HashMap<ParticipantIdentifier, M::Output>>
+where
+    M: QuorumMember,
+    R: RngCore + CryptoRng,
+{
+    let quorum_size = quorum.len();
+    let mut inboxes: HashMap<ParticipantIdentifier, Vec<M::Message>> =
+        quorum.iter().map(|p| (p.id(), Vec::new())).collect();
+    let mut outputs: HashMap<ParticipantIdentifier, M::Output> = HashMap::new();
+
+    tracing::debug!(protocol = name, quorum_size = quorum_size, "📨 Initializing protocol messages");
+    for participant in quorum.iter() {
+        let message = participant.initialize_message().map_err(|e| {
+            anyhow::anyhow!("{}: participant {} failed to initialize: {}", name, participant.id(), e)
+        })?;
+        inboxes.get_mut(&participant.id()).unwrap().push(message);
+    }
+
+    let exchange_start = std::time::Instant::now();
+    let mut steps = 0;
+
+    while outputs.len() < quorum_size {
+        let non_empty_inboxes: Vec<ParticipantIdentifier> = inboxes
+            .iter()
+            .filter(|(_, messages)| !messages.is_empty())
+            .map(|(pid, _)| *pid)
+            .collect();
+
+        let Some(&selected_pid) = non_empty_inboxes.choose(rng) else {
+            anyhow::bail!(
+                "{}: protocol stalled with {} of {} outputs and no messages in flight",
+                name,
+                outputs.len(),
+                quorum_size
+            );
+        };
+
+        steps += 1;
+        if steps > MAX_PROTOCOL_STEPS {
+            anyhow::bail!("{}: exceeded {} message steps without completing", name, MAX_PROTOCOL_STEPS);
+        }
+
+        let message = inboxes.get_mut(&selected_pid).unwrap().remove(0);
+        let participant = quorum.iter_mut().find(|p| p.id() == selected_pid).unwrap();
+
+        let (output, new_messages) = participant.process_message(&message, rng).map_err(|e| {
+            anyhow::anyhow!(
+                "{}: participant {} failed processing message from {} at step {}: {}",
+                name,
+                selected_pid,
+                M::sender(&message),
+                steps,
+                e
+            )
+        })?;
+
+        // Deliver new messages to their recipients
+        for msg in new_messages {
+            let recipient = M::recipient(&msg);
+            let inbox = inboxes.get_mut(&recipient).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{}: participant {} sent a message to {} outside the quorum",
+                    name,
+                    selected_pid,
+                    recipient
+                )
+            })?;
+            inbox.push(msg);
+        }
+
+        if let Some(output) = output {
+            tracing::trace!(
+                protocol = name,
+                participant_id = %selected_pid,
+                step = steps,
+                outputs_collected = outputs.len() + 1,
+                total_required = quorum_size,
+                "📨 Collected protocol output from participant"
+            );
+            if outputs.insert(selected_pid, output).is_some() {
+                anyhow::bail!("{}: participant {} produced more than one output", name, selected_pid);
+            }
+        }
+    }
+
+    // Every message must have been consumed once all outputs are in
+    let undelivered: usize = inboxes.values().map(Vec::len).sum();
+    if undelivered > 0 {
+        anyhow::bail!("{}: {} messages left undelivered after completion", name, undelivered);
+    }
+
+    tracing::debug!(
+        protocol = name,
+        exchange_duration_ms = exchange_start.elapsed().as_millis(),
+        total_steps = steps,
+        outputs_collected = outputs.len(),
+        "✅ Protocol message exchange completed"
+    );
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    struct ScriptedMessage {
+        from: ParticipantIdentifier,
+        to: ParticipantIdentifier,
+    }
+
+    // Reacts to every delivered message with `script`, given the participant and how
+    // many messages it has received so far
+    struct Scripted {
+        id: ParticipantIdentifier,
+        others: Vec<ParticipantIdentifier>,
+        received: usize,
+        script: fn(&Scripted) -> (Option<usize>, Vec<ParticipantIdentifier>),
+    }
+
+    impl QuorumMember for Scripted {
+        type Message = ScriptedMessage;
+        type Output = usize;
+
+        fn id(&self) -> ParticipantIdentifier {
+            self.id
+        }
+
+        fn initialize_message(&self) -> anyhow::Result<ScriptedMessage> {
+            Ok(ScriptedMessage { from: self.id, to: self.id })
+        }
+
+        fn process_message<R: RngCore + CryptoRng>(
+            &mut self,
+            _message: &ScriptedMessage,
+            _rng: &mut R,
+        ) -> anyhow::Result<Step<Self>> {
+            self.received += 1;
+            let (output, recipients) = (self.script)(self);
+            let messages = recipients.into_iter().map(|to| ScriptedMessage { from: self.id, to }).collect();
+            Ok((output, messages))
+        }
+
+        fn sender(message: &ScriptedMessage) -> ParticipantIdentifier {
+            message.from
+        }
+
+        fn recipient(message: &ScriptedMessage) -> ParticipantIdentifier {
+            message.to
+        }
+    }
+
+    fn run(script: fn(&Scripted) -> (Option<usize>, Vec<ParticipantIdentifier>)) -> anyhow::Result<HashMap<ParticipantIdentifier, usize>> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let ids: Vec<ParticipantIdentifier> = (0..3).map(|_| ParticipantIdentifier::random(&mut rng)).collect();
+        let mut quorum: Vec<Scripted> = ids
+            .iter()
+            .map(|id| Scripted {
+                id: *id,
+                others: ids.iter().filter(|other| *other != id).copied().collect(),
+                received: 0,
+                script,
+            })
+            .collect();
+        exchange_messages("scripted", &mut quorum, &mut rng)
+    }
+
+    #[test]
+    fn broadcast_round_collects_every_output() {
+        // Broadcast on initialize, finish once every other participant's message is in
+        let outputs = run(|p| match p.received {
+            1 => (None, p.others.clone()),
+            n if n == p.others.len() + 1 => (Some(n), vec![]),
+            _ => (None, vec![]),
+        })
+        .unwrap();
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs.values().all(|received| *received == 3));
+    }
+
+    #[test]
+    fn runner_errors_are_reported() {
+        let stalled = run(|_| (None, vec![])).unwrap_err();
+        assert!(stalled.to_string().contains("stalled with 0 of 3 outputs"), "{}", stalled);
+
+        let endless = run(|p| (None, vec![p.id])).unwrap_err();
+        assert!(endless.to_string().contains(&format!("exceeded {} message steps", MAX_PROTOCOL_STEPS)), "{}", endless);
+
+        let stranger = run(|_| (None, vec![ParticipantIdentifier::random(&mut StdRng::seed_from_u64(99))])).unwrap_err();
+        assert!(stranger.to_string().contains("outside the quorum"), "{}", stranger);
+
+        // One participant outputs on each of two messages while the others are still running
+        let duplicate = run(|p| match p.received {
+            _ if p.others.iter().any(|other| *other > p.id) => (None, vec![]),
+            1 => (Some(1), vec![p.id]),
+            n => (Some(n), vec![]),
+        })
+        .unwrap_err();
+        assert!(duplicate.to_string().contains("produced more than one output"), "{}", duplicate);
+
+        // Outputs are all in while a message is still queued
+        let undelivered = run(|p| match p.received {
+            1 => (Some(1), vec![p.others[0]]),
+            _ => (None, vec![]),
+        })
+        .unwrap_err();
+        assert!(undelivered.to_string().contains("left undelivered"), "{}", undelivered);
+    }
+
+    // Runs keygen and auxinfo with the real protocol: `cargo test -- --ignored`
+    #[test]
+    #[ignore = "runs the full TSS protocol, which takes minutes"]
+    fn keygen_and_auxinfo_complete_through_the_runner() {
+        use tss_ecdsa::{
+            auxinfo::AuxInfoParticipant, curve::TestCurve, keygen::KeygenParticipant, Identifier, ParticipantConfig,
+        };
+
+        let mut rng = StdRng::seed_from_u64(8);
+        let configs = ParticipantConfig::random_quorum(3, &mut rng).unwrap();
+
+        let keygen_sid = Identifier::random(&mut rng);
+        let mut keygen_quorum = configs
+            .iter()
+            .map(|config| Participant::<KeygenParticipant<TestCurve>>::from_config(config.clone(), keygen_sid, ()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let keygen_outputs = run_protocol("keygen", &mut keygen_quorum, &mut rng).unwrap();
+        assert_eq!(keygen_outputs.len(), 3);
+        let public_keys: Vec<_> = keygen_outputs.values().map(|output| output.public_key().unwrap()).collect();
+        assert!(public_keys.windows(2).all(|pair| pair[0] == pair[1]));
+
+        let auxinfo_sid = Identifier::random(&mut rng);
+        let mut auxinfo_quorum = configs
+            .iter()
+            .map(|config| Participant::<AuxInfoParticipant<TestCurve>>::from_config(config.clone(), auxinfo_sid, ()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let auxinfo_outputs = run_protocol("auxinfo", &mut auxinfo_quorum, &mut rng).unwrap();
+        assert!(configs.iter().all(|config| auxinfo_outputs.contains_key(&config.id())));
+    }
+}
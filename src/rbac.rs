@@ -0,0 +1,172 @@
+use axum::http::Method;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,   // Read-only access to the user's wallets
+    #[default]
+    Operator, // Viewer plus signing and child key derivation
+    Approver, // Viewer plus approving or rejecting signing requests of other users
+    Admin,    // Everything, including wallet lifecycle and user management
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Approver => "approver",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn allows(self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Operator => matches!(
+                permission,
+                Permission::Read | Permission::Sign | Permission::Derive | Permission::ManageTokens
+            ),
+            Role::Approver => matches!(
+                permission,
+                Permission::Read | Permission::Approve | Permission::ManageTokens
+            ),
+            Role::Viewer => matches!(permission, Permission::Read | Permission::ManageTokens),
+        }
+    }
+}
+
+// What a route does, as far as authorization is concerned
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Permission {
+    Read,          // GET endpoints, signature verification and recovery, policy dry runs
+    Sign,          // /sign and transaction signing
+    Derive,        // Child key derivation and deletion
+    Approve,       // Deciding on signing requests that wait for approval
+    ManageWallets, // Keygen, wallet deletion, auxinfo rotation, signing policies
+    ManageTokens,  // The caller's own API tokens
+    Admin,         // User management, the audit log and the participant node endpoints
+}
+
+impl Permission {
+    pub fn name(self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Sign => "sign",
+            Permission::Derive => "derive",
+            Permission::Approve => "approve",
+            Permission::ManageWallets => "manage_wallets",
+            Permission::ManageTokens => "manage_tokens",
+            Permission::Admin => "admin",
+        }
+    }
+}
+
+// Permission needed to call `route`. Anything not listed explicitly needs admin.
+pub fn required_permission(method: &Method, route: &str) -> Permission {
+    match (method.as_str(), route) {
+        ("POST", "/sign") | ("POST", "/eth/sign_transaction") | ("POST", "/btc/sign_psbt") => Permission::Sign,
+        ("POST", "/derive_key") | ("POST", "/delete_child_key") => Permission::Derive,
+        ("POST", "/signing_requests/{id}/approve") | ("POST", "/signing_requests/{id}/reject") => Permission::Approve,
+        ("POST", "/verify") | ("POST", "/recover") | ("POST", "/policies/evaluate") => Permission::Read,
+        ("POST", "/keygen") | ("POST", "/delete_key") | ("POST", "/auxinfo/refresh") => Permission::ManageWallets,
+        ("POST", "/policies") | ("POST", "/policies/delete") => Permission::ManageWallets,
+        (_, route) if route == "/tokens" || route.starts_with("/tokens/") => Permission::ManageTokens,
+        (_, route) if route.starts_with("/admin/") || route.starts_with("/nodes/") => Permission::Admin,
+        ("GET", "/audit") => Permission::Admin,
+        ("GET", _) => Permission::Read,
+        _ => Permission::Admin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERMISSIONS: [Permission; 7] = [
+        Permission::Read,
+        Permission::Sign,
+        Permission::Derive,
+        Permission::Approve,
+        Permission::ManageWallets,
+        Permission::ManageTokens,
+        Permission::Admin,
+    ];
+
+    #[test]
+    fn roles_allow_exactly_their_permissions() {
+        let table = [
+            (Role::Viewer, vec![Permission::Read, Permission::ManageTokens]),
+            (Role::Operator, vec![Permission::Read, Permission::Sign, Permission::Derive, Permission::ManageTokens]),
+            (Role::Approver, vec![Permission::Read, Permission::Approve, Permission::ManageTokens]),
+            (Role::Admin, PERMISSIONS.to_vec()),
+        ];
+        for (role, allowed) in table {
+            for permission in PERMISSIONS {
+                assert_eq!(
+                    role.allows(permission),
+                    allowed.contains(&permission),
+                    "{} / {}",
+                    role.name(),
+                    permission.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn routes_map_to_their_permission() {
+        let table = [
+            (Method::POST, "/sign", Permission::Sign),
+            (Method::POST, "/eth/sign_transaction", Permission::Sign),
+            (Method::POST, "/btc/sign_psbt", Permission::Sign),
+            (Method::POST, "/derive_key", Permission::Derive),
+            (Method::POST, "/delete_child_key", Permission::Derive),
+            (Method::POST, "/signing_requests/{id}/approve", Permission::Approve),
+            (Method::POST, "/signing_requests/{id}/reject", Permission::Approve),
+            (Method::POST, "/verify", Permission::Read),
+            (Method::POST, "/recover", Permission::Read),
+            (Method::POST, "/policies/evaluate", Permission::Read),
+            (Method::POST, "/keygen", Permission::ManageWallets),
+            (Method::POST, "/delete_key", Permission::ManageWallets),
+            (Method::POST, "/auxinfo/refresh", Permission::ManageWallets),
+            (Method::POST, "/policies", Permission::ManageWallets),
+            (Method::POST, "/policies/delete", Permission::ManageWallets),
+            (Method::GET, "/tokens", Permission::ManageTokens),
+            (Method::POST, "/tokens", Permission::ManageTokens),
+            (Method::POST, "/tokens/{id}/revoke", Permission::ManageTokens),
+            (Method::GET, "/admin/users", Permission::Admin),
+            (Method::POST, "/admin/users/{username}/role", Permission::Admin),
+            (Method::GET, "/nodes/status", Permission::Admin),
+            (Method::POST, "/nodes/sign", Permission::Admin),
+            (Method::GET, "/audit", Permission::Admin),
+            (Method::GET, "/keygen", Permission::Read),
+            (Method::GET, "/wallets", Permission::Read),
+            (Method::GET, "/list_keys", Permission::Read),
+            (Method::GET, "/addresses/{address}", Permission::Read),
+            (Method::GET, "/presign_pool", Permission::Read),
+            (Method::GET, "/policies", Permission::Read),
+            (Method::GET, "/signing_requests", Permission::Read),
+            (Method::GET, "/signing_requests/{id}", Permission::Read),
+            // Anything not listed needs admin, including new POST routes and other methods
+            (Method::POST, "/signing_requests/{id}/cancel", Permission::Admin),
+            (Method::POST, "/unknown", Permission::Admin),
+            (Method::DELETE, "/wallets", Permission::Admin),
+            (Method::PUT, "/sign", Permission::Admin),
+        ];
+        for (method, route, permission) in table {
+            assert_eq!(required_permission(&method, route), permission, "{} {}", method, route);
+        }
+    }
+
+    #[test]
+    fn only_approvers_and_admins_decide_on_signing_requests() {
+        let approve = required_permission(&Method::POST, "/signing_requests/{id}/approve");
+        let allowed: Vec<Role> = [Role::Viewer, Role::Operator, Role::Approver, Role::Admin]
+            .into_iter()
+            .filter(|role| role.allows(approve))
+            .collect();
+        assert_eq!(allowed, vec![Role::Approver, Role::Admin]);
+    }
+}
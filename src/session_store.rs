@@ -0,0 +1,540 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+// Backend selection: "filesystem" (default) or "sqlite"
+const STORAGE_BACKEND_ENV: &str = "WAAS_STORAGE_BACKEND";
+// Root directory for the filesystem backend
+const STORAGE_DIR_ENV: &str = "WAAS_STORAGE_DIR";
+// Database file for the SQLite backend
+const STORAGE_DB_ENV: &str = "WAAS_STORAGE_DB";
+const DEFAULT_STORAGE_DB: &str = "waas.db";
+// Filesystem layout: <storage dir>/wallets/<key id>/<record>
+const WALLETS_DIR: &str = "wallets";
+// Key ID given to the single key stored before multiple wallets were supported
+pub const LEGACY_KEY_ID: &str = "default";
+const MAX_KEY_ID_LEN: usize = 64;
+
+static KEY_STORE: OnceLock<Box<dyn KeyStore>> = OnceLock::new();
+
+// Everything the service persists about a wallet. Values are opaque bytes; the owning
+// module decides the encoding (and whether it is encrypted).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Record {
+    Wallet,
+    KeygenResult,
+    KeygenConfigs,
+    KeygenMarker,
+    PublicKey,
+    HdKeys,
+    AuxInfoCache,
+    PresignPool,
+}
+
+impl Record {
+    pub const ALL: [Record; 8] = [
+        Record::Wallet,
+        Record::KeygenResult,
+        Record::KeygenConfigs,
+        Record::KeygenMarker,
+        Record::PublicKey,
+        Record::HdKeys,
+        Record::AuxInfoCache,
+        Record::PresignPool,
+    ];
+
+    // File name for the filesystem backend and row key for SQLite. These are the file
+    // names used before the store existed, so existing deployments keep working, and
+    // they double as the associated data of encrypted records.
+    pub fn name(self) -> &'static str {
+        match self {
+            Record::Wallet => "wallet.json",
+            Record::KeygenResult => "keygen_result.json",
+            Record::KeygenConfigs => "keygen_configs.bin",
+            Record::KeygenMarker => "keygen_completed.marker",
+            Record::PublicKey => "public_key.bin",
+            Record::HdKeys => "hd_keys.json",
+            Record::AuxInfoCache => "auxinfo_outputs.json",
+            Record::PresignPool => "presign_outputs.json",
+        }
+    }
+}
+
+// State of the service itself rather than of a wallet: accounts, tokens, policies,
+// signing requests and the audit checkpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceRecord {
+    Users,
+    ApiTokens,
+    Policies,
+    SigningRequests,
+    AuditCheckpoint,
+    DistributedKey,
+}
+
+impl ServiceRecord {
+    pub const ALL: [ServiceRecord; 6] = [
+        ServiceRecord::Users,
+        ServiceRecord::ApiTokens,
+        ServiceRecord::Policies,
+        ServiceRecord::SigningRequests,
+        ServiceRecord::AuditCheckpoint,
+        ServiceRecord::DistributedKey,
+    ];
+
+    // File name in the storage directory for the filesystem backend, row key for SQLite
+    pub fn name(self) -> &'static str {
+        match self {
+            ServiceRecord::Users => "users.json",
+            ServiceRecord::ApiTokens => "api_tokens.json",
+            ServiceRecord::Policies => "policies.json",
+            ServiceRecord::SigningRequests => "signing_requests.json",
+            ServiceRecord::AuditCheckpoint => "audit.log.checkpoint",
+            ServiceRecord::DistributedKey => "distributed_key.json",
+        }
+    }
+
+    // Where earlier versions kept the record: a file named by an environment variable,
+    // or the record's file name in the working directory
+    fn legacy_path(self) -> PathBuf {
+        let configured = match self {
+            ServiceRecord::Users => std::env::var("WAAS_USERS_FILE").ok(),
+            ServiceRecord::ApiTokens => std::env::var("WAAS_TOKENS_FILE").ok(),
+            ServiceRecord::Policies => std::env::var("WAAS_POLICY_FILE").ok(),
+            ServiceRecord::SigningRequests => std::env::var("WAAS_SIGNING_REQUESTS_FILE").ok(),
+            ServiceRecord::AuditCheckpoint => std::env::var("WAAS_AUDIT_LOG").ok().map(|log| format!("{}.checkpoint", log)),
+            // Already kept in the storage directory
+            ServiceRecord::DistributedKey => return storage_dir().join(self.name()),
+        };
+        PathBuf::from(configured.unwrap_or_else(|| self.name().to_string()))
+    }
+}
+
+// Key IDs end up in file paths, so only a conservative character set is accepted
+pub fn validate_key_id(key_id: &str) -> Result<()> {
+    let valid = !key_id.is_empty()
+        && key_id.len() <= MAX_KEY_ID_LEN
+        && key_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        anyhow::bail!(
+            "Invalid key ID '{}': use 1 to {} letters, digits, '-' or '_'",
+            key_id,
+            MAX_KEY_ID_LEN
+        );
+    }
+    Ok(())
+}
+
+// Records are scoped by wallet key ID
+pub trait KeyStore: Send + Sync {
+    fn backend(&self) -> &'static str;
+    fn get(&self, key_id: &str, record: Record) -> Result<Option<Vec<u8>>>;
+    fn put(&self, key_id: &str, record: Record, data: &[u8]) -> Result<()>;
+    // Returns whether the record existed
+    fn delete(&self, key_id: &str, record: Record) -> Result<bool>;
+    // Every key ID with at least one record
+    fn key_ids(&self) -> Result<Vec<String>>;
+    fn get_service(&self, record: ServiceRecord) -> Result<Option<Vec<u8>>>;
+    fn put_service(&self, record: ServiceRecord, data: &[u8]) -> Result<()>;
+
+    fn contains(&self, key_id: &str, record: Record) -> Result<bool> {
+        Ok(self.get(key_id, record)?.is_some())
+    }
+}
+
+// One directory per wallet and one file per record under a root directory
+pub struct FileKeyStore {
+    root: PathBuf,
+}
+
+impl FileKeyStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(root.join(WALLETS_DIR))
+            .map_err(|e| anyhow::anyhow!("Failed to create storage directory {}: {}", root.display(), e))?;
+        let store = Self { root };
+        store.migrate_legacy_layout()?;
+        Ok(store)
+    }
+
+    fn wallet_dir(&self, key_id: &str) -> Result<PathBuf> {
+        validate_key_id(key_id)?;
+        Ok(self.root.join(WALLETS_DIR).join(key_id))
+    }
+
+    // Records written directly into the root directory belong to the single key
+    // supported before wallets; move them into the legacy wallet
+    fn migrate_legacy_layout(&self) -> Result<()> {
+        let legacy: Vec<Record> = Record::ALL
+            .into_iter()
+            .filter(|record| self.root.join(record.name()).is_file())
+            .collect();
+        if legacy.is_empty() {
+            return Ok(());
+        }
+
+        let wallet_dir = self.wallet_dir(LEGACY_KEY_ID)?;
+        if wallet_dir.exists() {
+            tracing::warn!(
+                key_id = LEGACY_KEY_ID,
+                "⚠️ Legacy key records found but the legacy wallet already exists, leaving them in place"
+            );
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&wallet_dir)?;
+        for record in &legacy {
+            std::fs::rename(self.root.join(record.name()), wallet_dir.join(record.name()))?;
+        }
+        tracing::info!(
+            key_id = LEGACY_KEY_ID,
+            records = legacy.len(),
+            "🔄 Moved single-key records into a wallet directory"
+        );
+        Ok(())
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn backend(&self) -> &'static str {
+        "filesystem"
+    }
+
+    fn get(&self, key_id: &str, record: Record) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(self.wallet_dir(key_id)?.join(record.name())) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Failed to read {}: {}", record.name(), e)),
+        }
+    }
+
+    fn put(&self, key_id: &str, record: Record, data: &[u8]) -> Result<()> {
+        let wallet_dir = self.wallet_dir(key_id)?;
+        std::fs::create_dir_all(&wallet_dir)?;
+        write_file_atomic(&wallet_dir.join(record.name()), data)
+    }
+
+    fn delete(&self, key_id: &str, record: Record) -> Result<bool> {
+        let wallet_dir = self.wallet_dir(key_id)?;
+        let deleted = match std::fs::remove_file(wallet_dir.join(record.name())) {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => return Err(anyhow::anyhow!("Failed to delete {}: {}", record.name(), e)),
+        };
+        // Drop the wallet directory once its last record is gone
+        let _ = std::fs::remove_dir(&wallet_dir);
+        Ok(deleted)
+    }
+
+    fn key_ids(&self) -> Result<Vec<String>> {
+        let mut key_ids = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(WALLETS_DIR))? {
+            let entry = entry?;
+            let key_id = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_dir() && validate_key_id(&key_id).is_ok() {
+                key_ids.push(key_id);
+            }
+        }
+        key_ids.sort();
+        Ok(key_ids)
+    }
+
+    fn get_service(&self, record: ServiceRecord) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(self.root.join(record.name())) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Failed to read {}: {}", record.name(), e)),
+        }
+    }
+
+    fn put_service(&self, record: ServiceRecord, data: &[u8]) -> Result<()> {
+        write_file_atomic(&self.root.join(record.name()), data)
+    }
+}
+
+// All records in a single table of an embedded SQLite database
+pub struct SqliteKeyStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteKeyStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open SQLite key store {}: {}", path, e))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS wallet_records (
+                 key_id TEXT NOT NULL,
+                 name TEXT NOT NULL,
+                 data BLOB NOT NULL,
+                 updated_at TEXT NOT NULL,
+                 PRIMARY KEY (key_id, name)
+             );
+             CREATE TABLE IF NOT EXISTS service_records (
+                 name TEXT PRIMARY KEY,
+                 data BLOB NOT NULL,
+                 updated_at TEXT NOT NULL
+             );",
+        )?;
+        Self::migrate_legacy_table(&conn)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    // The single-key schema kept one row per record; move those rows into the legacy wallet
+    fn migrate_legacy_table(conn: &rusqlite::Connection) -> Result<()> {
+        let has_legacy_table: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'key_records'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_legacy_table {
+            return Ok(());
+        }
+
+        let moved = conn.execute(
+            "INSERT OR IGNORE INTO wallet_records (key_id, name, data, updated_at)
+             SELECT ?1, name, data, updated_at FROM key_records",
+            [LEGACY_KEY_ID],
+        )?;
+        conn.execute_batch("DROP TABLE key_records")?;
+        tracing::info!(
+            key_id = LEGACY_KEY_ID,
+            records = moved,
+            "🔄 Moved single-key records into a wallet"
+        );
+        Ok(())
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM wallet_records", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+}
+
+impl KeyStore for SqliteKeyStore {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn get(&self, key_id: &str, record: Record) -> Result<Option<Vec<u8>>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT data FROM wallet_records WHERE key_id = ?1 AND name = ?2",
+                [key_id, record.name()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put(&self, key_id: &str, record: Record, data: &[u8]) -> Result<()> {
+        validate_key_id(key_id)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO wallet_records (key_id, name, data, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(key_id, name) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+            rusqlite::params![key_id, record.name(), data, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, key_id: &str, record: Record) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM wallet_records WHERE key_id = ?1 AND name = ?2",
+            [key_id, record.name()],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    fn key_ids(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare("SELECT DISTINCT key_id FROM wallet_records ORDER BY key_id")?;
+        let key_ids = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(key_ids)
+    }
+
+    fn get_service(&self, record: ServiceRecord) -> Result<Option<Vec<u8>>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT data FROM service_records WHERE name = ?1",
+                [record.name()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put_service(&self, record: ServiceRecord, data: &[u8]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO service_records (name, data, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+            rusqlite::params![record.name(), data, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+}
+
+// Written to a temporary file and renamed, so a crash never leaves a half-written file
+pub fn write_file_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp_path = path.with_file_name(format!("{}.tmp", file_name));
+    std::fs::write(&tmp_path, data)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", file_name, e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", file_name, e))?;
+    Ok(())
+}
+
+// Copies every record present in `from` into `to`
+fn import_records(from: &dyn KeyStore, to: &dyn KeyStore) -> Result<usize> {
+    let mut imported = 0;
+    for key_id in from.key_ids()? {
+        for record in Record::ALL {
+            if let Some(data) = from.get(&key_id, record)? {
+                to.put(&key_id, record, &data)?;
+                imported += 1;
+            }
+        }
+    }
+    for record in ServiceRecord::ALL {
+        if let Some(data) = from.get_service(record)? {
+            to.put_service(record, &data)?;
+            imported += 1;
+        }
+    }
+    Ok(imported)
+}
+
+// Moves service state that earlier versions kept in separate files into the store
+fn import_legacy_service_files(store: &dyn KeyStore) -> Result<()> {
+    for record in ServiceRecord::ALL {
+        let path = record.legacy_path();
+        if store.get_service(record)?.is_some() || !path.is_file() {
+            continue;
+        }
+        let data = std::fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        store.put_service(record, &data)?;
+        std::fs::remove_file(&path)?;
+        tracing::info!(
+            record = record.name(),
+            path = %path.display(),
+            backend = store.backend(),
+            "🔄 Moved service state into the key store"
+        );
+    }
+    Ok(())
+}
+
+// JSON state of a service record, or None if it has not been written yet
+pub fn read_service_json<T: DeserializeOwned>(record: ServiceRecord) -> Result<Option<T>> {
+    match key_store()?.get_service(record)? {
+        Some(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", record.name(), e)),
+        None => Ok(None),
+    }
+}
+
+pub fn write_service_json<T: Serialize>(record: ServiceRecord, value: &T) -> Result<()> {
+    key_store()?.put_service(record, &serde_json::to_vec_pretty(value)?)
+}
+
+// Directory holding the filesystem backend and the service's own state files
+pub fn storage_dir() -> PathBuf {
+    PathBuf::from(std::env::var(STORAGE_DIR_ENV).unwrap_or_else(|_| ".".to_string()))
+}
+
+// Opens the configured backend. Must run before anything touches key material.
+pub fn init_key_store() -> Result<()> {
+    let backend = std::env::var(STORAGE_BACKEND_ENV).unwrap_or_else(|_| "filesystem".to_string());
+    let storage_dir = storage_dir();
+
+    let store: Box<dyn KeyStore> = match backend.as_str() {
+        "filesystem" | "fs" => {
+            tracing::info!(backend = "filesystem", directory = %storage_dir.display(), "💾 Using filesystem key store");
+            Box::new(FileKeyStore::open(&storage_dir)?)
+        }
+        "sqlite" => {
+            let db_path = std::env::var(STORAGE_DB_ENV).unwrap_or_else(|_| DEFAULT_STORAGE_DB.to_string());
+            let store = SqliteKeyStore::open(&db_path)?;
+
+            // First start on SQLite: pick up key material left by the filesystem backend
+            if store.is_empty()? {
+                let imported = import_records(&FileKeyStore::open(&storage_dir)?, &store)?;
+                if imported > 0 {
+                    tracing::info!(
+                        records = imported,
+                        directory = %storage_dir.display(),
+                        "🔄 Imported existing key material into SQLite key store"
+                    );
+                }
+            }
+
+            tracing::info!(backend = "sqlite", database = %db_path, "💾 Using SQLite key store");
+            Box::new(store)
+        }
+        other => anyhow::bail!(
+            "Unknown storage backend '{}' in {}; expected 'filesystem' or 'sqlite'",
+            other,
+            STORAGE_BACKEND_ENV
+        ),
+    };
+
+    import_legacy_service_files(store.as_ref())?;
+
+    KEY_STORE
+        .set(store)
+        .map_err(|_| anyhow::anyhow!("Key store already initialized"))?;
+    Ok(())
+}
+
+pub fn key_store() -> Result<&'static dyn KeyStore> {
+    KEY_STORE
+        .get()
+        .map(|store| store.as_ref())
+        .ok_or_else(|| anyhow::anyhow!("Key store not initialized"))
+}
+
+#[cfg(test)]
+pub fn init_test_key_store() {
+    KEY_STORE.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("waas-test-store-{}", std::process::id()));
+        Box::new(FileKeyStore::open(dir).unwrap())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_records_survive_the_move_to_sqlite() {
+        let dir = std::env::temp_dir().join(format!("waas-store-test-{}", std::process::id()));
+        let files = FileKeyStore::open(&dir).unwrap();
+        files.put("alpha", Record::Wallet, b"wallet").unwrap();
+        files.put_service(ServiceRecord::Users, b"users").unwrap();
+        assert_eq!(std::fs::read(dir.join("users.json")).unwrap(), b"users");
+        assert_eq!(files.get_service(ServiceRecord::Policies).unwrap(), None);
+
+        let db = SqliteKeyStore::open(":memory:").unwrap();
+        assert_eq!(import_records(&files, &db).unwrap(), 2);
+        assert_eq!(db.get_service(ServiceRecord::Users).unwrap().as_deref(), Some(&b"users"[..]));
+        db.put_service(ServiceRecord::Users, b"updated").unwrap();
+        assert_eq!(db.get_service(ServiceRecord::Users).unwrap().as_deref(), Some(&b"updated"[..]));
+        assert_eq!(db.key_ids().unwrap(), vec!["alpha".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,25 +1,174 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use axum::{extract::Json, response::Json as ResponseJson};
-//use k256::Secp256k1;
+use axum::{extract::Json, http::StatusCode, response::Json as ResponseJson};
 use rand::{rngs::StdRng, SeedableRng};
 use serde::{Deserialize, Serialize};
-// SHA3 imports removed as they're not needed in the current implementation
 use tss_ecdsa::{
     curve::{CurveTrait, VerifyingKeyTrait},
     keygen::KeySharePublic,
-    messages::Message,
     presign::PresignRecord,
     protocol::participant_config::ParticipantConfig,
     sign::{Input as SignInput, SignParticipant},
-    Identifier, Participant, ParticipantIdentifier, ProtocolParticipant
+    Identifier, Participant, ParticipantIdentifier
 };
 
+use crate::session_store::{key_store, Record};
+
+// What is hashed and how the signature is encoded
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMode {
+    #[default]
+    Raw,    // Keccak256(message), DER signature
+    Eip191, // Ethereum personal_sign, 65-byte r||s||v signature
+    Eip712, // Ethereum typed data from `typed_data`, 65-byte r||s||v signature
+}
+
+impl SigningMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            SigningMode::Raw => "raw",
+            SigningMode::Eip191 => "eip191",
+            SigningMode::Eip712 => "eip712",
+        }
+    }
+}
+
+// How a signature is written out. Every encoding carries the low-S form (BIP62/EIP-2).
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    Der, // Hex ASN.1 DER
+    #[serde(alias = "compact64")]
+    Compact, // Hex r||s, 64 bytes
+    #[serde(alias = "ethereum", alias = "rsv65")]
+    Rsv, // 0x-prefixed hex r||s||v with v = 27/28, 65 bytes
+    Base64, // Standard base64 DER
+    #[serde(alias = "base64url")]
+    Jws, // Unpadded base64url r||s, as in JWS ES256K
+}
+
+impl SignatureEncoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureEncoding::Der => "der",
+            SignatureEncoding::Compact => "compact",
+            SignatureEncoding::Rsv => "rsv",
+            SignatureEncoding::Base64 => "base64",
+            SignatureEncoding::Jws => "jws",
+        }
+    }
+
+    /// DER for raw messages, r||s||v for Ethereum messages.
+    pub fn default_for(mode: SigningMode) -> Self {
+        match mode {
+            SigningMode::Raw => SignatureEncoding::Der,
+            SigningMode::Eip191 | SigningMode::Eip712 => SignatureEncoding::Rsv,
+        }
+    }
+
+    pub fn encode(self, signature: &TssSignature) -> String {
+        use base64::{engine::general_purpose, Engine as _};
+        match self {
+            SignatureEncoding::Der => hex::encode(signature.signature.to_der().as_bytes()),
+            SignatureEncoding::Compact => hex::encode(signature.signature.to_bytes()),
+            SignatureEncoding::Rsv => format!("0x{}", hex::encode(signature.rsv(27))),
+            SignatureEncoding::Base64 => general_purpose::STANDARD.encode(signature.signature.to_der().as_bytes()),
+            SignatureEncoding::Jws => general_purpose::URL_SAFE_NO_PAD.encode(signature.signature.to_bytes()),
+        }
+    }
+
+    /// Parses an encoded signature; only r||s||v carries a recovery id. High-S values are kept.
+    pub fn decode(self, encoded: &str) -> Result<(k256::ecdsa::Signature, Option<k256::ecdsa::RecoveryId>)> {
+        use base64::{engine::general_purpose, Engine as _};
+        use k256::ecdsa::Signature as K256Signature;
+
+        let encoded = encoded.trim();
+        let hex_bytes = || {
+            hex::decode(encoded.strip_prefix("0x").unwrap_or(encoded))
+                .map_err(|_| anyhow::anyhow!("Invalid signature format. Expected hex string."))
+        };
+        let from_der = |bytes: Vec<u8>| {
+            K256Signature::from_der(&bytes).map_err(|_| anyhow::anyhow!("Failed to parse DER signature"))
+        };
+        let from_compact = |bytes: Vec<u8>| {
+            if bytes.len() != 64 {
+                anyhow::bail!("Expected a 64-byte r||s signature, got {} bytes", bytes.len());
+            }
+            K256Signature::from_slice(&bytes).map_err(|_| anyhow::anyhow!("Failed to parse r||s signature"))
+        };
+
+        match self {
+            SignatureEncoding::Der => Ok((from_der(hex_bytes()?)?, None)),
+            SignatureEncoding::Compact => Ok((from_compact(hex_bytes()?)?, None)),
+            SignatureEncoding::Rsv => {
+                let (signature, recovery_id) = parse_rsv_signature(&hex_bytes()?)?;
+                Ok((signature, Some(recovery_id)))
+            }
+            SignatureEncoding::Base64 => {
+                let bytes = general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|_| anyhow::anyhow!("Invalid signature format. Expected base64."))?;
+                Ok((from_der(bytes)?, None))
+            }
+            SignatureEncoding::Jws => {
+                let bytes = general_purpose::URL_SAFE_NO_PAD
+                    .decode(encoded.trim_end_matches('='))
+                    .map_err(|_| anyhow::anyhow!("Invalid signature format. Expected base64url."))?;
+                Ok((from_compact(bytes)?, None))
+            }
+        }
+    }
+}
+
+// Hash that turns a message into the 32-byte digest that is signed
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Keccak256,
+    Sha256,
+    Sha256d, // SHA-256 applied twice, as Bitcoin hashes
+}
+
+impl HashAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Keccak256 => "keccak256",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha256d => "sha256d",
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> [u8; 32] {
+        use sha2::Sha256;
+        use sha3::{Digest, Keccak256};
+        match self {
+            HashAlgorithm::Keccak256 => Keccak256::digest(data).into(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).into(),
+            HashAlgorithm::Sha256d => Sha256::digest(Sha256::digest(data)).into(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct SignRequest {
-    pub message: String,
+    pub key_id: String,
+    #[serde(default)]
+    pub message: String, // Unused in eip712 mode
+    #[serde(default)]
+    pub mode: SigningMode,
+    pub typed_data: Option<serde_json::Value>, // EIP-712 payload: types, primaryType, domain and message
+    pub encoding: Option<SignatureEncoding>, // Defaults to DER, or r||s||v for Ethereum modes
+    #[serde(default)]
+    pub hash: HashAlgorithm, // Hash applied to the message (raw mode only)
+    pub digest: Option<String>, // Hex 32-byte digest computed by the caller, instead of a message
+    pub path: Option<String>, // Optional derivation path, e.g. m/0/5
     pub child_index: Option<u32>, // Optional: if None, use root key (0)
+    pub participants: Option<Vec<String>>, // Optional signer subset; if None, t random signers are chosen
+    pub destination: Option<String>, // Declared recipient, checked against the signing policy
+    pub value: Option<String>, // Declared amount in base units, checked against the signing policy
 }
 
 #[derive(Serialize)]
@@ -27,12 +176,37 @@ pub struct SignResponse {
     pub signature: String,
     pub success: bool,
     pub message: String,
+    pub signers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<SignatureEncoding>, // Encoding of `signature`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<HashAlgorithm>, // Hash of the message that was signed; unset for a caller's digest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>, // Hex 32-byte digest the signature covers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_id: Option<u8>, // Recovers the signing key from compact_signature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compact_signature: Option<String>, // Low-S r||s||v with v = recovery id (0 or 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // Set when the signature waits for approvals
 }
 
 #[derive(Deserialize)]
 pub struct VerifyRequest {
+    pub key_id: String,
+    #[serde(default)]
     pub message: String,
+    #[serde(default)]
+    pub mode: SigningMode,
+    pub typed_data: Option<serde_json::Value>,
     pub signature: String,
+    pub encoding: Option<SignatureEncoding>, // Same default as /sign
+    #[serde(default)]
+    pub hash: HashAlgorithm,
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub strict: bool, // Reject high-S signatures instead of normalizing them
+    pub path: Option<String>, // Optional derivation path, e.g. m/0/5
     pub child_index: Option<u32>, // Optional: if None, use root key (0)
 }
 
@@ -41,32 +215,133 @@ pub struct VerifyResponse {
     pub valid: bool,
     pub success: bool,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<HashAlgorithm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>, // Hex 32-byte digest the signature was checked against
 }
 
-#[allow(dead_code)]
 pub struct SignHelperInput {
     pub public_key_shares: Vec<KeySharePublic<tss_ecdsa::curve::TestCurve>>,
-    pub saved_public_key: <tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
     pub presign_outputs: HashMap<ParticipantIdentifier, PresignRecord<tss_ecdsa::curve::TestCurve>>,
-    pub chain_code: [u8; 32],
-    pub inboxes: HashMap<ParticipantIdentifier, Vec<Message>>,
-    pub child_index: u32,
+    pub key_tweak: Option<[u8; 32]>, // HD tweak of the signing key from hd_keys::signing_tweak (None for the root key)
     pub threshold: usize,
 }
 
+/// What the TSS protocol signs: a message, whose Keccak-256 hash the protocol signs itself,
+/// or a 32-byte digest hashed some other way.
+#[derive(Clone, Copy)]
+pub enum SignTarget<'a> {
+    Message(&'a [u8]),
+    Digest(&'a [u8; 32]),
+}
+
+impl<'a> SignTarget<'a> {
+    /// Keccak-256 hashes of the payload go through the protocol's own hashing;
+    /// other hashes and caller-supplied digests are signed prehashed.
+    pub fn select(hash: HashAlgorithm, caller_digest: bool, payload: &'a [u8], digest: &'a [u8; 32]) -> Self {
+        if hash == HashAlgorithm::Keccak256 && !caller_digest {
+            SignTarget::Message(payload)
+        } else {
+            SignTarget::Digest(digest)
+        }
+    }
+
+    /// The 32-byte digest the signature covers.
+    pub fn digest(&self) -> [u8; 32] {
+        match self {
+            SignTarget::Message(message) => HashAlgorithm::Keccak256.digest(message),
+            SignTarget::Digest(digest) => **digest,
+        }
+    }
+}
+
+/// Nonce point R of a presignature. The record only exposes R through its serialized form,
+/// where curve points are compressed SEC1 hex, so it has to hold exactly one point.
+fn presign_nonce_point(record: &serde_json::Value) -> Result<k256::AffinePoint> {
+    fn collect_points(value: &serde_json::Value, points: &mut Vec<k256::AffinePoint>) {
+        match value {
+            serde_json::Value::String(text) if text.len() == 66 => {
+                if let Ok(bytes) = hex::decode(text)
+                    && matches!(bytes[0], 0x02 | 0x03)
+                    && let Ok(point) = k256::PublicKey::from_sec1_bytes(&bytes)
+                    && !points.contains(point.as_affine())
+                {
+                    points.push(*point.as_affine());
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|value| collect_points(value, points)),
+            serde_json::Value::Object(fields) => fields.values().for_each(|value| collect_points(value, points)),
+            _ => {}
+        }
+    }
+
+    let mut points = vec![];
+    collect_points(record, &mut points);
+    match points.as_slice() {
+        [point] => Ok(*point),
+        [] => anyhow::bail!("Presignature record has no nonce point"),
+        _ => anyhow::bail!("Presignature record has {} curve points, expected only the nonce point", points.len()),
+    }
+}
+
+/// r = x(R) mod n of the presignature shared by the signing quorum.
+fn presign_nonce_r(records: &HashMap<ParticipantIdentifier, PresignRecord<tss_ecdsa::curve::TestCurve>>) -> Result<k256::Scalar> {
+    use k256::elliptic_curve::{ops::Reduce, point::AffineCoordinates};
+
+    let mut nonce_point = None;
+    for (participant, record) in records {
+        let record = serde_json::to_value(record)
+            .map_err(|e| anyhow::anyhow!("Failed to read presignature of participant {}: {}", participant, e))?;
+        let point = presign_nonce_point(&record)?;
+        if nonce_point.is_some_and(|nonce_point| nonce_point != point) {
+            anyhow::bail!("Presignature records of the signing quorum disagree on the nonce point");
+        }
+        nonce_point = Some(point);
+    }
+    let nonce_point = nonce_point.ok_or_else(|| anyhow::anyhow!("No presignature records to sign with"))?;
+    Ok(<k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&nonce_point.x()))
+}
+
+/// Shift under which the protocol's signature over Keccak256(`message`) is a signature over
+/// `digest` by the key shifted by `key_tweak`. With shift t the protocol computes
+/// s = k(m + r(x + t)) for m = Keccak256(message); t = tweak + (d - m)/r turns that into
+/// s = k(d + r(x + tweak)), a signature over d with the same R.
+fn prehash_shift(message: &[u8], digest: &[u8; 32], r: &k256::Scalar, key_tweak: Option<&[u8; 32]>) -> Result<k256::Scalar> {
+    use k256::elliptic_curve::{ops::Reduce, PrimeField};
+
+    let tweak = match key_tweak {
+        Some(tweak) => Option::from(k256::Scalar::from_repr((*tweak).into()))
+            .ok_or_else(|| anyhow::anyhow!("HD tweak is not a valid scalar"))?,
+        None => k256::Scalar::ZERO,
+    };
+    let r_inverse: k256::Scalar = Option::from(r.invert())
+        .ok_or_else(|| anyhow::anyhow!("Presignature nonce has r = 0"))?;
+    let m = <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&HashAlgorithm::Keccak256.digest(message).into());
+    let d = <k256::Scalar as Reduce<k256::U256>>::reduce_bytes(&(*digest).into());
+    Ok(tweak + (d - m) * r_inverse)
+}
+
 pub fn sign_helper(
     configs: Vec<ParticipantConfig>,
     sign_helper_input: SignHelperInput,
-    message: &[u8],
+    target: SignTarget,
     mut rng: StdRng,
 ) -> Result<Vec<u8>> {
     let quorum_real = configs.len();
     let sign_sid = Identifier::random(&mut rng);
 
+    // A digest is passed to the protocol as the message; the shift below corrects for its hashing
+    let message: &[u8] = match target {
+        SignTarget::Message(message) => message,
+        SignTarget::Digest(digest) => digest,
+    };
+
     tracing::debug!(
         quorum_size = quorum_real,
         threshold = sign_helper_input.threshold,
         message_length = message.len(),
+        prehashed = matches!(target, SignTarget::Digest(_)),
         session_id = %sign_sid,
         "🔐 Initializing signing session"
     );
@@ -74,7 +349,22 @@ pub fn sign_helper(
     let mut presign_outputs = sign_helper_input.presign_outputs;
     let public_key_shares = sign_helper_input.public_key_shares;
     let threshold = sign_helper_input.threshold;
-    let mut inboxes = sign_helper_input.inboxes;
+
+    // Child keys are signed by shifting the root key shares with the additive HD tweak.
+    // Prehashed digests fold the difference to the protocol's own hash into the same shift.
+    let (shift, expected_r) = match target {
+        SignTarget::Message(_) => (sign_helper_input.key_tweak.map(|tweak| tweak.to_vec()), None),
+        SignTarget::Digest(digest) => {
+            let r = presign_nonce_r(&presign_outputs)?;
+            let shift = prehash_shift(message, digest, &r, sign_helper_input.key_tweak.as_ref())?;
+            (Some(shift.to_bytes().to_vec()), Some(r))
+        }
+    };
+    let child_shift = shift.map(|shift| {
+        use tss_ecdsa::curve::{ScalarTrait, TestCurve};
+        tracing::debug!("🌱 Applying key shift to signing shares");
+        <TestCurve as CurveTrait>::Scalar::from_repr(shift)
+    });
 
     // Make signing participants
     tracing::debug!("👥 Creating signing participants");
@@ -82,55 +372,25 @@ pub fn sign_helper(
         .clone()
         .into_iter()
         .map(|config| {
-            let record = presign_outputs.remove(&config.id()).unwrap();
-            let input = SignInput::new(message, record, public_key_shares.clone(), threshold, None);
-            Participant::<SignParticipant<tss_ecdsa::curve::TestCurve>>::from_config(config, sign_sid, input)
+            let record = presign_outputs.remove(&config.id())
+                .ok_or_else(|| anyhow::anyhow!("Missing presign record for participant {}", config.id()))?;
+            let input = SignInput::new(message, record, public_key_shares.clone(), threshold, child_shift.clone());
+            Ok(Participant::<SignParticipant<tss_ecdsa::curve::TestCurve>>::from_config(config, sign_sid, input)?)
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Result<Vec<_>>>()?;
         
     tracing::debug!(
         participants_created = sign_quorum.len(),
         "✅ Signing participants initialized"
     );
 
-    // Ensure all participants have inboxes
-    for participant in &sign_quorum {
-        if !inboxes.contains_key(&participant.id()) {
-            inboxes.insert(participant.id(), Vec::new());
-        }
-    }
-
-    // Prepare output storage and initial "ready" messages
-    let mut sign_outputs = Vec::with_capacity(quorum_real);
-    for participant in &mut sign_quorum {
-        let inbox = inboxes.get_mut(&participant.id()).unwrap();
-        inbox.push(participant.initialize_message()?);
-    }
-
     // Run signing protocol
     tracing::debug!("🔄 Starting signing protocol message exchange");
     let protocol_start = std::time::Instant::now();
-    let mut round_count = 0;
-    
-    while sign_outputs.len() < quorum_real {
-        let output = process_random_message(&mut sign_quorum, &mut inboxes, &mut rng)?;
-
-        if let Some((pid, output)) = output {
-            round_count += 1;
-            tracing::trace!(
-                participant_id = %pid,
-                round = round_count,
-                outputs_collected = sign_outputs.len() + 1,
-                total_required = quorum_real,
-                "📨 Collected signature output from participant"
-            );
-            sign_outputs.push(output);
-        }
-    }
+    let sign_outputs = crate::protocol::run_protocol("sign", &mut sign_quorum, &mut rng)?;
     
     tracing::info!(
         protocol_duration_ms = protocol_start.elapsed().as_millis(),
-        total_rounds = round_count,
         outputs_collected = sign_outputs.len(),
         "✅ Signing protocol completed successfully"
     );
@@ -138,7 +398,16 @@ pub fn sign_helper(
     // Return the first signature (they should all be the same)
     // Since we're using TestCurve which defaults to K256, we know the signature type
     use std::ops::Deref;
-    let signature_bytes = sign_outputs[0].deref().to_der().as_bytes().to_vec();
+    let signature = sign_outputs
+        .values()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Signing produced no output"))?;
+    if let Some(expected_r) = expected_r
+        && *signature.deref().r().as_ref() != expected_r
+    {
+        anyhow::bail!("Signature was not made with the presignature nonce the prehashed shift was computed for");
+    }
+    let signature_bytes = signature.deref().to_der().as_bytes().to_vec();
     
     tracing::debug!(
         signature_length = signature_bytes.len(),
@@ -149,52 +418,10 @@ pub fn sign_helper(
     Ok(signature_bytes)
 }
 
-fn process_random_message<R: rand::RngCore + rand::CryptoRng>(
-    quorum: &mut Vec<Participant<SignParticipant<tss_ecdsa::curve::TestCurve>>>,
-    inboxes: &mut HashMap<ParticipantIdentifier, Vec<Message>>,
-    rng: &mut R,
-) -> Result<Option<(ParticipantIdentifier, <SignParticipant<tss_ecdsa::curve::TestCurve> as ProtocolParticipant>::Output)>> {
-    // Find all participants with messages
-    let participants_with_messages: Vec<usize> = quorum
-        .iter()
-        .enumerate()
-        .filter(|(_, p)| !inboxes.get(&p.id()).unwrap().is_empty())
-        .map(|(i, _)| i)
-        .collect();
-
-    if participants_with_messages.is_empty() {
-        return Ok(None);
-    }
-
-    use rand::seq::SliceRandom;
-    // Pick a random participant with messages
-    let participant_idx = *participants_with_messages.choose(rng).unwrap();
-    let participant = &mut quorum[participant_idx];
-    let pid = participant.id();
-
-    // Get the inbox and pop a message
-    let inbox = inboxes.get_mut(&pid).unwrap();
-    if let Some(message) = inbox.pop() {
-        let (output, new_messages) = participant.process_single_message(&message, rng)?;
-
-        // Deliver new messages to their recipients
-        for msg in new_messages {
-            let recipient = msg.to();
-            if let Some(inbox) = inboxes.get_mut(&recipient) {
-                inbox.push(msg);
-            }
-        }
-
-        match output {
-            Some(output) => Ok(Some((pid, output))),
-            None => Ok(None),
-        }
-    } else {
-        Ok(None)
-    }
-}
-
-pub async fn sign(Json(request): Json<SignRequest>) -> ResponseJson<SignResponse> {
+pub async fn sign(
+    auth: crate::BasicAuth,
+    Json(request): Json<SignRequest>,
+) -> (StatusCode, ResponseJson<SignResponse>) {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
     
@@ -203,6 +430,7 @@ pub async fn sign(Json(request): Json<SignRequest>) -> ResponseJson<SignResponse
     let message_hash = format!("{:x}", hasher.finish());
     
     tracing::info!(
+        key_id = %request.key_id,
         message = %request.message,
         message_length = request.message.len(),
         message_hash = %message_hash,
@@ -211,30 +439,169 @@ pub async fn sign(Json(request): Json<SignRequest>) -> ResponseJson<SignResponse
 
     let start_time = std::time::Instant::now();
     
-    let child_index = request.child_index.unwrap_or(0);
-    match run_tss_sign(&request.message, child_index).await {
-        Ok(signature) => {
+    let key_path = match resolve_key_path(&auth, &request.key_id, request.path.as_deref(), request.child_index) {
+        Ok(key_path) => key_path,
+        Err(e) => {
+            tracing::error!(
+                key_id = %request.key_id,
+                path = ?request.path,
+                child_index = ?request.child_index,
+                error = %e,
+                "❌ Invalid signing key selection"
+            );
+            let response = ResponseJson(SignResponse {
+                signature: String::new(),
+                success: false,
+                message: format!("Signing failed: {}", e),
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: None,
+            });
+            if crate::wallets::is_wallet_not_found(&e) {
+                return (StatusCode::NOT_FOUND, response);
+            }
+            return (StatusCode::OK, response);
+        }
+    };
+
+    let payload = match signing_payload(request.mode, &request.message, request.typed_data.as_ref()) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, mode = request.mode.name(), error = %e, "⚠️ Invalid message to sign");
+            return (StatusCode::BAD_REQUEST, ResponseJson(SignResponse {
+                signature: String::new(),
+                success: false,
+                message: format!("Invalid {} message: {}", request.mode.name(), e),
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: None,
+            }));
+        }
+    };
+
+    let digest = match message_digest(request.mode, request.hash, request.digest.as_deref(), &payload) {
+        Ok(digest) => digest,
+        Err(e) => {
+            tracing::warn!(key_id = %request.key_id, hash = request.hash.name(), error = %e, "⚠️ Invalid digest to sign");
+            return (StatusCode::BAD_REQUEST, ResponseJson(SignResponse {
+                signature: String::new(),
+                success: false,
+                message: format!("Invalid digest: {}", e),
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: None,
+            }));
+        }
+    };
+
+    let context = crate::policy::SigningContext {
+        key_id: &request.key_id,
+        path: &key_path,
+        format: message_format(request.mode, &request.message, request.digest.as_deref()),
+        destination: request.destination.as_deref(),
+        value: request.value.as_deref(),
+    };
+    let evaluation = match check_policy(&auth, &context, &payload) {
+        Ok(evaluation) => evaluation,
+        Err(refusal) => {
+            return (StatusCode::FORBIDDEN, ResponseJson(SignResponse {
+                signature: String::new(),
+                success: false,
+                message: format!("Signing refused: {}", refusal),
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: None,
+            }));
+        }
+    };
+    if evaluation.required_approvals > 0 {
+        return match crate::approvals::create_request(&auth, &request, &key_path, &evaluation) {
+            Ok(pending) => (StatusCode::ACCEPTED, ResponseJson(SignResponse {
+                signature: String::new(),
+                success: true,
+                message: format!(
+                    "Signing request {} needs {} approvals before {}",
+                    pending.id, pending.required_approvals, pending.expires_at
+                ),
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: Some(pending.id),
+            })),
+            Err(e) => {
+                tracing::error!(key_id = %request.key_id, error = %e, "❌ Failed to store signing request");
+                (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(SignResponse {
+                    signature: String::new(),
+                    success: false,
+                    message: format!("Failed to store signing request: {}", e),
+                    signers: vec![],
+                    encoding: None,
+                    hash: None,
+                    digest: None,
+                    recovery_id: None,
+                    compact_signature: None,
+                    request_id: None,
+                }))
+            }
+        };
+    }
+    let encoding = request.encoding.unwrap_or(SignatureEncoding::default_for(request.mode));
+    let target = SignTarget::select(request.hash, request.digest.is_some(), &payload, &digest);
+    match sign_message(&request.key_id, &key_path, encoding, target, request.participants.as_deref()).await {
+        Ok(signed) => {
             let duration = start_time.elapsed();
-            let sig_hex = hex::encode(&signature);
+            let response = SignResponse {
+                signature: signed.signature,
+                success: true,
+                message: format!("Successfully signed message: '{}'", request.message),
+                signers: signed.signers,
+                encoding: Some(encoding),
+                hash: request.digest.is_none().then_some(request.hash),
+                digest: Some(hex::encode(digest)),
+                recovery_id: Some(signed.recovery_id),
+                compact_signature: Some(signed.compact_signature),
+                request_id: None,
+            };
             
             tracing::info!(
                 message = %request.message,
-                signature = %sig_hex,
-                signature_length = signature.len(),
+                mode = request.mode.name(),
+                encoding = encoding.name(),
+                signature = %response.signature,
+                recovery_id = signed.recovery_id,
                 duration_ms = duration.as_millis(),
-                client_response_size = serde_json::to_string(&SignResponse {
-                    signature: sig_hex.clone(),
-                    success: true,
-                    message: format!("Successfully signed message: '{}'", request.message),
-                }).map(|s| s.len()).unwrap_or(0),
+                signers = ?response.signers,
+                client_response_size = serde_json::to_string(&response).map(|s| s.len()).unwrap_or(0),
                 "✅ TSS signing completed successfully - sending response to client"
             );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "sign", "success")
+                    .key_id(&request.key_id)
+                    .path(&key_path)
+                    .signed_digest(&digest)
+                    .detail(format!("signers {}", response.signers.join(", "))),
+            );
 
-            ResponseJson(SignResponse {
-                signature: sig_hex,
-                success: true,
-                message: format!("Successfully signed message: '{}'", request.message),
-            })
+            (StatusCode::OK, ResponseJson(response))
         },
         Err(e) => {
             let duration = start_time.elapsed();
@@ -245,139 +612,263 @@ pub async fn sign(Json(request): Json<SignRequest>) -> ResponseJson<SignResponse
                 duration_ms = duration.as_millis(),
                 "❌ TSS signing failed"
             );
+            crate::audit::record(
+                crate::audit::AuditEvent::new(&auth, "sign", "failed")
+                    .key_id(&request.key_id)
+                    .path(&key_path)
+                    .signed_digest(&digest)
+                    .detail(e.to_string()),
+            );
             
-            ResponseJson(SignResponse {
+            (StatusCode::OK, ResponseJson(SignResponse {
                 signature: String::new(),
                 success: false,
                 message: format!("Signing failed: {}", e),
-            })
+                signers: vec![],
+                encoding: None,
+                hash: None,
+                digest: None,
+                recovery_id: None,
+                compact_signature: None,
+                request_id: None,
+            }))
         }
     }
 }
 
-async fn run_tss_sign(message: &str, child_index: u32) -> anyhow::Result<Vec<u8>> {
+/// Applies the signing policy of the key; returns the reason when signing must not go ahead.
+/// An allowed evaluation may still require approvals before the signature is produced.
+pub fn check_policy(
+    auth: &crate::BasicAuth,
+    context: &crate::policy::SigningContext,
+    message: &[u8],
+) -> std::result::Result<crate::policy::Evaluation, String> {
+    let refusal = match crate::policy::enforce(context) {
+        Ok(evaluation) if !evaluation.allowed => evaluation.violations.join("; "),
+        Ok(evaluation) => return Ok(evaluation),
+        Err(e) => format!("policy evaluation failed: {}", e),
+    };
+
+    tracing::warn!(
+        key_id = %context.key_id,
+        path = %context.path,
+        username = %auth.username,
+        reason = %refusal,
+        "📜 Signing refused by policy"
+    );
+    crate::audit::record(
+        crate::audit::AuditEvent::new(auth, "sign", "policy_denied")
+            .key_id(context.key_id)
+            .path(context.path)
+            .message(message)
+            .detail(refusal.clone()),
+    );
+    Err(refusal)
+}
+
+/// Format of a message as the signing policies see it; typed data always counts as JSON,
+/// and a caller's digest hides the message whatever it is.
+pub fn message_format(mode: SigningMode, message: &str, digest: Option<&str>) -> crate::policy::MessageFormat {
+    if digest.is_some() {
+        return crate::policy::MessageFormat::Digest;
+    }
+    match mode {
+        SigningMode::Eip712 => crate::policy::MessageFormat::Json,
+        _ => crate::policy::MessageFormat::detect(message),
+    }
+}
+
+/// Canonical derivation path for a request naming a key by path or by child index.
+/// Fails with WalletNotFound unless the caller owns the wallet.
+pub fn resolve_key_path(auth: &crate::BasicAuth, key_id: &str, path: Option<&str>, child_index: Option<u32>) -> Result<String> {
+    crate::wallets::authorize(auth, key_id)?;
+    let store = crate::hd_keys::load_hd_key_store(key_id)?;
+    store.resolve_path(path, child_index)
+}
+
+/// The bytes whose Keccak-256 hash is signed in `mode`.
+pub fn signing_payload(mode: SigningMode, message: &str, typed_data: Option<&serde_json::Value>) -> Result<Vec<u8>> {
+    match mode {
+        SigningMode::Raw => Ok(message.as_bytes().to_vec()),
+        SigningMode::Eip191 => Ok(crate::eth_message::personal_message_preimage(message.as_bytes())),
+        SigningMode::Eip712 => {
+            let typed_data = typed_data.ok_or_else(|| anyhow::anyhow!("eip712 mode needs `typed_data`"))?;
+            crate::eth_message::typed_data_preimage(typed_data)
+        }
+    }
+}
+
+/// The 32-byte digest a signature covers: the caller's `digest`, or `hash` of the payload.
+/// Ethereum modes define their own hashing, so they only take Keccak-256 of the payload.
+pub fn message_digest(mode: SigningMode, hash: HashAlgorithm, digest: Option<&str>, payload: &[u8]) -> Result<[u8; 32]> {
+    if mode != SigningMode::Raw && (digest.is_some() || hash != HashAlgorithm::Keccak256) {
+        anyhow::bail!("{} mode always signs the Keccak-256 hash of its payload", mode.name());
+    }
+    let Some(digest) = digest else {
+        return Ok(hash.digest(payload));
+    };
+    let bytes = hex::decode(digest.strip_prefix("0x").unwrap_or(digest))
+        .map_err(|_| anyhow::anyhow!("Invalid digest format. Expected hex string."))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| anyhow::anyhow!("Expected a 32-byte digest, got {} bytes", bytes.len()))
+}
+
+/// A signature over a `SignTarget` as returned to clients.
+pub struct SignedMessage {
+    pub signature: String,         // In the requested encoding
+    pub recovery_id: u8,           // 0 or 1, for the low-S signature
+    pub compact_signature: String, // Hex r||s||v with v = recovery_id
+    pub signers: Vec<String>,
+}
+
+/// Output of the TSS signing protocol, checked against the public key of the signing key.
+pub struct TssSignature {
+    pub signature: k256::ecdsa::Signature,      // Low-S form
+    pub recovery_id: k256::ecdsa::RecoveryId,   // Recovers the signing key from `signature`
+    pub signers: Vec<String>,
+}
+
+impl TssSignature {
+    /// 65-byte r||s||v with v = recovery id + `v_offset` (0, or 27 for Ethereum).
+    pub fn rsv(&self, v_offset: u8) -> Vec<u8> {
+        let mut rsv = self.signature.to_bytes().to_vec();
+        rsv.push(v_offset + self.recovery_id.to_byte());
+        rsv
+    }
+}
+
+/// Signs `target` and writes the low-S signature in `encoding`.
+pub async fn sign_message(
+    key_id: &str,
+    key_path: &str,
+    encoding: SignatureEncoding,
+    target: SignTarget<'_>,
+    participants: Option<&[String]>,
+) -> Result<SignedMessage> {
+    let tss_signature = run_tss_sign(key_id, target, key_path, participants).await?;
+    Ok(SignedMessage {
+        signature: encoding.encode(&tss_signature),
+        recovery_id: tss_signature.recovery_id.to_byte(),
+        compact_signature: hex::encode(tss_signature.rsv(0)),
+        signers: tss_signature.signers,
+    })
+}
+
+pub async fn run_tss_sign(
+    key_id: &str,
+    target: SignTarget<'_>,
+    key_path: &str,
+    participants: Option<&[String]>,
+) -> anyhow::Result<TssSignature> {
     use tss_ecdsa::curve::TestCurve;
     use crate::keygen::KeygenHelperOutput;
     
     tracing::info!(
-        threshold = 2,
+        key_id = %key_id,
+        key_path = %key_path,
+        requested_signers = ?participants,
         "🚀 Initializing TSS protocol participants"
     );
+
+    // Child keys must be derived before they can sign
+    let hd_store = crate::hd_keys::load_hd_key_store(key_id)?;
+    if !hd_store.is_root(key_path) && hd_store.get_key(key_path).is_none() {
+        anyhow::bail!("Child key {} not found in HD key store. Please derive it first.", key_path);
+    }
     
     // Run the full protocol chain to generate presign records
-    // 1. Generate or restore keygen outputs
-    let (configs, keygen_result): (Vec<ParticipantConfig>, KeygenHelperOutput<TestCurve>) = if is_keygen_completed() {
-        tracing::info!("🔄 Loading keygen outputs from storage");
-        let keygen_start = std::time::Instant::now();
-        
-        let (loaded_configs, loaded_keygen_result) = load_keygen_outputs()?;
-        
-        tracing::info!(
-            duration_ms = keygen_start.elapsed().as_millis(),
-            key_shares = loaded_keygen_result.keygen_outputs.len(),
-            participants = loaded_configs.len(),
-            "✅ Keygen data loaded from storage with configs and private shares"
-        );
-        
-        (loaded_configs, loaded_keygen_result)
-    } else {
-        tracing::debug!("📋 Phase 1: Starting key generation protocol (first time)");
-        let keygen_start = std::time::Instant::now();
-        
-        // SECURITY: Always use fresh entropy for keygen generation
-        let mut keygen_rng = StdRng::from_entropy();
-        let configs = ParticipantConfig::random_quorum(3, &mut keygen_rng)?;
-        
-        let keygen_rng = StdRng::from_entropy();
-        use crate::keygen::{keygen_helper, KeygenHelperOutput};
-        let keygen_result: KeygenHelperOutput<TestCurve> = {
-            let keygen_inboxes: HashMap<ParticipantIdentifier, Vec<Message>> = configs
-                .iter()
-                .map(|config| (config.id(), Vec::new()))
-                .collect();
-            keygen_helper(configs.clone(), keygen_inboxes, keygen_rng)?
-        };
+    // 1. Restore the wallet's keygen outputs; wallets are only created by /keygen
+    if !is_keygen_completed(key_id) {
+        anyhow::bail!("Wallet {} has no completed key generation", key_id);
+    }
+    tracing::info!(key_id = %key_id, "🔄 Loading keygen outputs from storage");
+    let keygen_start = std::time::Instant::now();
+    
+    let (configs, keygen_result): (Vec<ParticipantConfig>, KeygenHelperOutput<TestCurve>) = load_keygen_outputs(key_id)?;
+    
+    tracing::info!(
+        duration_ms = keygen_start.elapsed().as_millis(),
+        key_shares = keygen_result.keygen_outputs.len(),
+        participants = configs.len(),
+        "✅ Keygen data loaded from storage with configs and private shares"
+    );
 
-        tracing::info!(
-            duration_ms = keygen_start.elapsed().as_millis(),
-            key_shares = keygen_result.keygen_outputs.len(),
-            participants = configs.len(),
-            "✅ Key generation completed (first time) with fresh entropy"
-        );
+    // Resolve the key's HD tweak before a presignature is spent on it
+    let key_tweak = {
+        let first_output = keygen_result.keygen_outputs.values().next()
+            .ok_or_else(|| anyhow::anyhow!("No keygen outputs found in loaded data"))?;
+        let root_public_key = first_output.public_key()?.to_sec1_bytes();
+        crate::hd_keys::signing_tweak(&hd_store, key_path, &root_public_key, first_output.chain_code())?
+    };
 
-        // Store complete keygen outputs to local storage
-        store_keygen_outputs(&configs, &keygen_result)?;
-        tracing::debug!("💾 Complete keygen outputs stored to local storage");
-        
-        (configs, keygen_result)
+    // Pick the t signers; an explicit request has to be honoured by the pool as well
+    let mut quorum_rng = StdRng::from_entropy();
+    let threshold = keygen_result.threshold();
+    let requested_signers = match participants {
+        Some(_) => Some(crate::keygen::select_signers(&configs, &keygen_result, participants, &mut quorum_rng)?),
+        None => None,
     };
 
-    // 2. Generate auxinfo outputs (always fresh for security)
-    tracing::debug!("🔧 Phase 2: Starting auxiliary info generation");
-    let auxinfo_start = std::time::Instant::now();
-    
-    // SECURITY: Always use fresh entropy for auxinfo generation - NEVER cache or use deterministic seeds!
-    let auxinfo_rng = StdRng::from_entropy();
-    
-    use crate::auxinfo::{auxinfo_helper, AuxInfoHelperOutput};
-    let auxinfo_result: AuxInfoHelperOutput<TestCurve> = auxinfo_helper(configs.clone(), auxinfo_rng)?;
-    
+    // 2. Take a precomputed presignature from the pool, or run auxinfo + presign now
+    let pool_entry = crate::presign_pool::take_entry(key_id, &keygen_result, requested_signers.as_deref())?;
+    let presign_entry = match pool_entry {
+        Some(entry) => {
+            tracing::info!(
+                entry_id = entry.id,
+                "⚡ Using pooled presignature - only the online sign round is needed"
+            );
+            entry
+        }
+        None => {
+            tracing::debug!("📝 Phase 2-3: No pooled presignature available, generating one now");
+            let presign_start = std::time::Instant::now();
+
+            let signer_ids = match requested_signers {
+                Some(signer_ids) => signer_ids,
+                None => crate::keygen::select_signers(&configs, &keygen_result, None, &mut quorum_rng)?,
+            };
+            let entry = crate::presign_pool::generate_entry(key_id, &keygen_result, &signer_ids)?;
+
+            tracing::info!(
+                duration_ms = presign_start.elapsed().as_millis(),
+                presign_records = entry.records.len(),
+                "✅ Presignature generation completed with fresh entropy"
+            );
+            entry
+        }
+    };
+
+    // Convert the signers' shares for a t-of-t signing session
+    let (configs, signer_outputs) = crate::keygen::signing_quorum(&keygen_result, &presign_entry.signers)?;
+    let signers: Vec<String> = configs
+        .iter()
+        .map(|config| format!("{:?}", config.id()))
+        .collect();
+
     tracing::info!(
-        duration_ms = auxinfo_start.elapsed().as_millis(),
-        auxinfo_outputs = auxinfo_result.auxinfo_outputs.len(),
-        "✅ Auxiliary info generation completed with fresh entropy"
+        threshold = threshold,
+        total_participants = keygen_result.keygen_outputs.len(),
+        signers = ?signers,
+        "👥 Signing quorum selected"
     );
 
     // Extract needed data from keygen before moving it
-    let first_keygen_output = keygen_result.keygen_outputs.values().next().unwrap();
+    let first_keygen_output = signer_outputs.values().next()
+        .ok_or_else(|| anyhow::anyhow!("Signing quorum has no key shares"))?;
     let public_key_shares = first_keygen_output.public_key_shares().to_vec();
     let saved_public_key = first_keygen_output.public_key()?;
-    let chain_code = *first_keygen_output.chain_code();
-
-    // 3. Generate presign outputs (always fresh for security)
-    tracing::debug!("📝 Phase 3: Starting presignature generation");
-    let presign_start = std::time::Instant::now();
-    
-    // SECURITY: Always use fresh entropy for secure presign generation - NEVER use deterministic seeds!
-    let presign_rng = StdRng::from_entropy();
-    
-    use crate::presign::{presign_helper, PresignHelperOutput};
-    let presign_result: PresignHelperOutput<TestCurve> = {
-        let mut inboxes = auxinfo_result.inboxes;
-        presign_helper(
-            configs.clone(), 
-            auxinfo_result.auxinfo_outputs, 
-            keygen_result.keygen_outputs, 
-            &mut inboxes, 
-            presign_rng
-        )?
-    };
     
-    tracing::info!(
-        duration_ms = presign_start.elapsed().as_millis(),
-        presign_records = presign_result.presign_outputs.len(),
-        "✅ Presignature generation completed with fresh entropy"
-    );
-    
-    // Initialize fresh inboxes for all participants
-    let sign_inboxes: HashMap<ParticipantIdentifier, Vec<Message>> = configs
-        .iter()
-        .map(|config| (config.id(), Vec::new()))
-        .collect();
-
     let sign_helper_input = SignHelperInput {
         public_key_shares,
-        saved_public_key,
-        presign_outputs: presign_result.presign_outputs,
-        chain_code,
-        inboxes: sign_inboxes,
-        child_index,
-        threshold: 2, // t-of-n threshold
+        presign_outputs: presign_entry.records,
+        key_tweak,
+        threshold,
     };
 
     // Store the public key for verification use
     tracing::debug!("💾 Storing public key for future verification");
-    store_public_key_for_verification(&saved_public_key)?;
+    store_public_key_for_verification(key_id, &saved_public_key)?;
     tracing::debug!("✅ Public key stored successfully");
 
     // Run the signing protocol
@@ -387,31 +878,56 @@ async fn run_tss_sign(message: &str, child_index: u32) -> anyhow::Result<Vec<u8>
     // Use fresh entropy for each signature (this should vary between messages)
     let signing_rng = StdRng::from_entropy();
     
-    let signature_bytes = sign_helper(configs, sign_helper_input, message.as_bytes(), signing_rng)?;
+    let signature_bytes = sign_helper(configs, sign_helper_input, target, signing_rng)?;
     
     tracing::info!(
         duration_ms = sign_start.elapsed().as_millis(),
         signature_size = signature_bytes.len(),
         "✅ Signature generation completed"
     );
+
+    // Make sure the signature verifies against the key the caller asked for
+    let expected_public_key = load_public_key_for_verification_with_path(key_id, key_path)?
+        .ok_or_else(|| anyhow::anyhow!("No public key found for key {}", key_path))?;
+    let digest = target.digest();
+    let der_signature = k256::ecdsa::Signature::from_der(&signature_bytes)
+        .map_err(|_| anyhow::anyhow!("Failed to parse DER signature"))?;
+    if !verify_prehashed_signature(&expected_public_key, &digest, &der_signature)? {
+        anyhow::bail!("Generated signature does not verify against the public key for key {}", key_path);
+    }
+    tracing::debug!(
+        key_path = %key_path,
+        "✅ Signature verified against the expected public key"
+    );
+
+    // The protocol output has no recovery id, so find the parity that recovers the key
+    let (signature, recovery_id) = recoverable_signature(&expected_public_key, &digest, &signature_bytes)?;
     
-    Ok(signature_bytes)
+    Ok(TssSignature {
+        signature,
+        recovery_id,
+        signers,
+    })
 }
 
-// Simple storage mechanism for the public key (in a real app, this would be in a database)
-fn store_public_key_for_verification(public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey) -> Result<()> {
-    use std::fs;
+// Root public key kept alongside the key material for later verification
+fn store_public_key_for_verification(
+    key_id: &str,
+    public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
+) -> Result<()> {
+    let store = key_store()?;
     
     // Convert public key to bytes for storage
     let public_key_bytes = public_key.to_sec1_bytes();
     
     tracing::debug!(
         key_size_bytes = public_key_bytes.len(),
-        storage_path = "public_key.bin",
-        "💾 Storing public key to filesystem"
+        record = Record::PublicKey.name(),
+        backend = store.backend(),
+        "💾 Storing public key to key store"
     );
     
-    fs::write("public_key.bin", &public_key_bytes)?;
+    store.put(key_id, Record::PublicKey, &public_key_bytes)?;
     
     tracing::info!(
         key_size_bytes = public_key_bytes.len(),
@@ -421,48 +937,58 @@ fn store_public_key_for_verification(public_key: &<tss_ecdsa::curve::TestCurve a
     Ok(())
 }
 
-fn load_public_key_for_verification_with_child(child_index: u32) -> Result<Option<<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey>> {
-    // NOTE: Currently, all signatures are generated using the root TSS private key shares
-    // regardless of child_index, because TSS child key derivation for private shares
-    // is not yet implemented in the TSS library.
-    // 
-    // Therefore, for consistency, we always verify against the root public key
-    // until full HD-TSS support is available.
+pub fn load_public_key_for_verification_with_path(
+    key_id: &str,
+    key_path: &str,
+) -> Result<Option<<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey>> {
+    use crate::hd_keys::{load_hd_key_store, load_key_public_key};
+    let store = load_hd_key_store(key_id)?;
     
-    if child_index == 0 {
-        tracing::debug!("🔑 Loading root key for verification (child index 0)");
-        load_public_key_for_verification()
-    } else {
-        // For child keys, check if they exist in the HD key store first
-        use crate::hd_keys::{load_hd_key_store};
-        let store = load_hd_key_store()?;
-        
-        if let Some(_key_info) = store.get_key(child_index) {
-            tracing::info!(
-                child_index = child_index,
-                "🔑 Child key exists in store, but using root key for verification (TSS limitation)"
-            );
-            
-            // Use root key for verification since signing also uses root TSS key
-            load_public_key_for_verification()
-        } else {
-            anyhow::bail!("Child key {} not found in HD key store", child_index);
-        }
+    if store.is_root(key_path) {
+        tracing::debug!(key_id = %key_id, "🔑 Loading root key for verification");
+        return load_public_key_for_verification(key_id);
     }
+
+    // Child signatures are produced with tweaked shares, so verify against the child public key
+    if store.get_key(key_path).is_none() {
+        anyhow::bail!("Child key {} not found in HD key store", key_path);
+    }
+
+    let Some(bytes) = load_key_public_key(key_id, key_path)? else {
+        return Ok(None);
+    };
+
+    tracing::debug!(
+        key_path = %key_path,
+        key_size_bytes = bytes.len(),
+        "🔑 Loading child key for verification"
+    );
+
+    let verifying_key = <tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey::from_sec1_bytes(&bytes)
+        .map_err(|e| {
+            tracing::error!(
+                key_path = %key_path,
+                error = %e,
+                "❌ Failed to reconstruct child public key from stored bytes"
+            );
+            anyhow::anyhow!("Failed to reconstruct child public key {} from bytes", key_path)
+        })?;
+    Ok(Some(verifying_key))
 }
 
-fn load_public_key_for_verification() -> Result<Option<<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey>> {
-    use std::fs;
+fn load_public_key_for_verification(key_id: &str) -> Result<Option<<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey>> {
+    let store = key_store()?;
     
     tracing::debug!(
-        storage_path = "public_key.bin",
-        "📂 Attempting to load public key from filesystem"
+        record = Record::PublicKey.name(),
+        backend = store.backend(),
+        "📂 Attempting to load public key from key store"
     );
     
-    if let Ok(bytes) = fs::read("public_key.bin") {
+    if let Some(bytes) = store.get(key_id, Record::PublicKey)? {
         tracing::debug!(
             key_size_bytes = bytes.len(),
-            "✅ Public key file found, reconstructing verifying key"
+            "✅ Public key record found, reconstructing verifying key"
         );
         
         // Reconstruct the verifying key from bytes
@@ -478,70 +1004,76 @@ fn load_public_key_for_verification() -> Result<Option<<tss_ecdsa::curve::TestCu
         tracing::debug!("✅ Public key reconstructed successfully");
         Ok(Some(verifying_key))
     } else {
-        tracing::warn!("⚠️ No public key file found - verification requires a previous signing operation");
+        tracing::warn!("⚠️ No public key record found - verification requires a previous signing operation");
         Ok(None)
     }
 }
 
 // Direct keygen output storage and loading - serialize the entire keygen result
 pub fn store_keygen_outputs(
+    key_id: &str,
     configs: &Vec<ParticipantConfig>,
     keygen_result: &crate::keygen::KeygenHelperOutput<tss_ecdsa::curve::TestCurve>
 ) -> Result<()> {
-    use std::fs;
+    let store = key_store()?;
     
     tracing::debug!(
+        key_id = %key_id,
         configs_count = configs.len(),
         keygen_outputs_count = keygen_result.keygen_outputs.len(),
-        "💾 Storing complete keygen result with all private shares to filesystem"
+        backend = store.backend(),
+        "💾 Storing complete keygen result with all private shares to key store"
     );
     
-    // Serialize the entire KeygenHelperOutput directly (including all private shares)
-    let keygen_json = serde_json::to_string_pretty(keygen_result)
-        .map_err(|e| anyhow::anyhow!("Failed to serialize keygen result: {}", e))?;
+    // Each participant's share is encrypted under its own data key wrapped by the master key
+    let keygen_json = crate::encryption::encrypt_keygen_result(key_id, keygen_result)
+        .map_err(|e| anyhow::anyhow!("Failed to encrypt keygen result: {}", e))?;
     
     // Serialize configs separately using bincode for compatibility
     let configs_bincode = bincode::serialize(configs)
         .map_err(|e| anyhow::anyhow!("Failed to serialize configs: {}", e))?;
     
-    // Write both files
-    fs::write("keygen_result.json", keygen_json)?;
-    fs::write("keygen_configs.bin", configs_bincode)?;
-    fs::write("keygen_completed.marker", "1")?;
+    // Write both records, then the marker that makes them visible
+    store.put(key_id, Record::KeygenResult, keygen_json.as_bytes())?;
+    store.put(key_id, Record::KeygenConfigs, &configs_bincode)?;
+    store.put(key_id, Record::KeygenMarker, b"1")?;
     
     tracing::info!(
         configs_count = configs.len(),
         outputs_count = keygen_result.keygen_outputs.len(),
-        "✅ Complete keygen result and configs stored successfully with all private shares"
+        "✅ Complete keygen result and configs stored successfully with encrypted private shares"
     );
     
     Ok(())
 }
 
-pub fn load_keygen_outputs() -> Result<(Vec<ParticipantConfig>, crate::keygen::KeygenHelperOutput<tss_ecdsa::curve::TestCurve>)> {
-    use std::fs;
+pub fn load_keygen_outputs(key_id: &str) -> Result<(Vec<ParticipantConfig>, crate::keygen::KeygenHelperOutput<tss_ecdsa::curve::TestCurve>)> {
+    let store = key_store()?;
     
     tracing::debug!(
-        keygen_path = "keygen_result.json",
-        configs_path = "keygen_configs.bin",
+        key_id = %key_id,
+        keygen_record = Record::KeygenResult.name(),
+        configs_record = Record::KeygenConfigs.name(),
+        backend = store.backend(),
         "📂 Loading complete keygen result and configs from storage"
     );
     
     // Load keygen result
-    let keygen_json = fs::read_to_string("keygen_result.json")
-        .map_err(|_| anyhow::anyhow!("No keygen result found - will generate new keys"))?;
+    let keygen_bytes = store.get(key_id, Record::KeygenResult)?
+        .ok_or_else(|| anyhow::anyhow!("No keygen result found for wallet {}", key_id))?;
+    let keygen_json = String::from_utf8(keygen_bytes)
+        .map_err(|e| anyhow::anyhow!("Stored keygen result is not valid UTF-8: {}", e))?;
         
-    let keygen_result: crate::keygen::KeygenHelperOutput<tss_ecdsa::curve::TestCurve> = 
-        serde_json::from_str(&keygen_json)
-            .map_err(|e| anyhow::anyhow!("Failed to deserialize keygen result: {}", e))?;
+    let keygen_result = crate::encryption::decrypt_keygen_result(key_id, &keygen_json)
+        .map_err(|e| anyhow::anyhow!("Failed to decrypt keygen result: {}", e))?;
     
     // Load configs
-    let configs_bincode = fs::read("keygen_configs.bin")
-        .map_err(|_| anyhow::anyhow!("No keygen configs found"))?;
+    let configs_bincode = store.get(key_id, Record::KeygenConfigs)?
+        .ok_or_else(|| anyhow::anyhow!("No keygen configs found"))?;
         
     let configs: Vec<ParticipantConfig> = bincode::deserialize(&configs_bincode)
         .map_err(|e| anyhow::anyhow!("Failed to deserialize configs: {}", e))?;
-    
+
     tracing::info!(
         configs_count = configs.len(),
         outputs_count = keygen_result.keygen_outputs.len(),
@@ -551,19 +1083,22 @@ pub fn load_keygen_outputs() -> Result<(Vec<ParticipantConfig>, crate::keygen::K
     Ok((configs, keygen_result))
 }
 
-pub fn is_keygen_completed() -> bool {
-    use std::fs;
+pub fn is_keygen_completed(key_id: &str) -> bool {
+    tracing::debug!(key_id = %key_id, "📂 Checking for keygen completion");
     
-    tracing::debug!(
-        marker_path = "keygen_completed.marker",
-        keygen_path = "keygen_result.json",
-        configs_path = "keygen_configs.bin",
-        "📂 Checking for keygen completion"
-    );
-    
-    let marker_exists = fs::metadata("keygen_completed.marker").is_ok();
-    let keygen_exists = fs::metadata("keygen_result.json").is_ok();
-    let configs_exist = fs::metadata("keygen_configs.bin").is_ok();
+    let Ok(store) = key_store() else {
+        return false;
+    };
+    let exists = |record: Record| match store.contains(key_id, record) {
+        Ok(exists) => exists,
+        Err(e) => {
+            tracing::warn!(record = record.name(), error = %e, "⚠️ Failed to check key store record");
+            false
+        }
+    };
+    let marker_exists = exists(Record::KeygenMarker);
+    let keygen_exists = exists(Record::KeygenResult);
+    let configs_exist = exists(Record::KeygenConfigs);
     let completed = marker_exists && keygen_exists && configs_exist;
     
     tracing::debug!(
@@ -579,7 +1114,10 @@ pub fn is_keygen_completed() -> bool {
 
 
 
-pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyResponse> {
+pub async fn verify(
+    auth: crate::BasicAuth,
+    Json(request): Json<VerifyRequest>,
+) -> Result<ResponseJson<VerifyResponse>, (StatusCode, ResponseJson<VerifyResponse>)> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
     
@@ -588,6 +1126,7 @@ pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyRe
     let message_hash = format!("{:x}", hasher.finish());
     
     tracing::info!(
+        key_id = %request.key_id,
         message = %request.message,
         message_length = request.message.len(),
         message_hash = %message_hash,
@@ -598,9 +1137,24 @@ pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyRe
 
     let start_time = std::time::Instant::now();
     
-    let child_index = request.child_index.unwrap_or(0);
-    match run_verification(&request.message, &request.signature, child_index).await {
-        Ok(is_valid) => {
+    let digest = resolve_key_path(&auth, &request.key_id, request.path.as_deref(), request.child_index).and_then(|key_path| {
+        let payload = match request.digest {
+            Some(_) => vec![],
+            None => signing_payload(request.mode, &request.message, request.typed_data.as_ref())?,
+        };
+        Ok((key_path, message_digest(request.mode, request.hash, request.digest.as_deref(), &payload)?))
+    });
+    let verification = match digest {
+        Ok((key_path, digest)) => {
+            let encoding = request.encoding.unwrap_or(SignatureEncoding::default_for(request.mode));
+            run_verification(&request.key_id, encoding, &digest, &request.signature, request.strict, &key_path)
+                .await
+                .map(|is_valid| (is_valid, digest))
+        },
+        Err(e) => Err(e),
+    };
+    match verification {
+        Ok((is_valid, digest)) => {
             let duration = start_time.elapsed();
             
             tracing::info!(
@@ -611,7 +1165,7 @@ pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyRe
                 "🔍 Signature verification completed"
             );
 
-            ResponseJson(VerifyResponse {
+            Ok(ResponseJson(VerifyResponse {
                 valid: is_valid,
                 success: true,
                 message: if is_valid {
@@ -619,7 +1173,9 @@ pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyRe
                 } else {
                     format!("❌ Signature is NOT valid for message: '{}'", request.message)
                 },
-            })
+                hash: request.digest.is_none().then_some(request.hash),
+                digest: Some(hex::encode(digest)),
+            }))
         },
         Err(e) => {
             let duration = start_time.elapsed();
@@ -631,41 +1187,76 @@ pub async fn verify(Json(request): Json<VerifyRequest>) -> ResponseJson<VerifyRe
                 "❌ Signature verification failed with error"
             );
             
-            ResponseJson(VerifyResponse {
+            let response = ResponseJson(VerifyResponse {
                 valid: false,
                 success: false,
                 message: format!("Verification error: {}", e),
-            })
+                hash: None,
+                digest: None,
+            });
+            if crate::wallets::is_wallet_not_found(&e) {
+                return Err((StatusCode::NOT_FOUND, response));
+            }
+            Ok(response)
         }
     }
 }
 
-async fn run_verification(message: &str, signature_hex: &str, child_index: u32) -> anyhow::Result<bool> {
-    // Load the stored public key for the specified child index
+async fn run_verification(
+    key_id: &str,
+    encoding: SignatureEncoding,
+    digest: &[u8; 32],
+    encoded_signature: &str,
+    strict: bool,
+    key_path: &str,
+) -> anyhow::Result<bool> {
+    // Load the stored public key for the specified key path
     tracing::debug!(
-        child_index = child_index,
+        key_id = %key_id,
+        key_path = %key_path,
         "📂 Loading stored public key for verification"
     );
-    let public_key = load_public_key_for_verification_with_child(child_index)?
-        .ok_or_else(|| anyhow::anyhow!("No public key found for child index {}. Please derive or generate the key first.", child_index))?;
+    let public_key = load_public_key_for_verification_with_path(key_id, key_path)?
+        .ok_or_else(|| anyhow::anyhow!("No public key found for key {}. Please derive or generate the key first.", key_path))?;
     tracing::debug!("✅ Public key loaded successfully");
 
-    // Decode the signature from hex
     tracing::debug!(
-        signature_hex_length = signature_hex.len(),
-        "🔓 Decoding signature from hex format"
-    );
-    let signature_bytes = hex::decode(signature_hex)
-        .map_err(|_| anyhow::anyhow!("Invalid signature format. Expected hex string."))?;
-    tracing::debug!(
-        signature_bytes_length = signature_bytes.len(),
-        "✅ Signature decoded from hex"
+        encoding = encoding.name(),
+        signature_length = encoded_signature.len(),
+        "🔓 Decoding signature"
     );
+    let (signature, recovery_id) = encoding.decode(encoded_signature)?;
+
+    // n - s is as valid as s, so only strict callers care which one they were given
+    let (signature, recovery_id) = match signature.normalize_s() {
+        Some(_) if strict => {
+            anyhow::bail!("Signature has a high S value; strict verification only accepts low-S signatures");
+        }
+        Some(normalized) => {
+            tracing::debug!("🔄 Normalized high-S signature before verification");
+            // Negating s negates R, which flips the parity in the recovery id
+            let recovery_id = recovery_id
+                .map(|id| k256::ecdsa::RecoveryId::new(!id.is_y_odd(), id.is_x_reduced()));
+            (normalized, recovery_id)
+        }
+        None => (signature, recovery_id),
+    };
 
+    match recovery_id {
+        Some(recovery_id) => verify_recoverable_signature(&public_key, digest, &signature, recovery_id),
+        None => verify_prehashed_signature(&public_key, digest, &signature),
+    }
+}
+
+pub fn verify_der_signature(
+    public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
+    message: &[u8],
+    signature_bytes: &[u8],
+) -> anyhow::Result<bool> {
     // Parse the DER-encoded signature using k256's from_der method
     tracing::debug!("📋 Parsing DER-encoded signature");
     use k256::ecdsa::Signature as K256Signature;
-    let k256_signature = K256Signature::from_der(&signature_bytes)
+    let k256_signature = K256Signature::from_der(signature_bytes)
         .map_err(|_| anyhow::anyhow!("Failed to parse DER signature"))?;
     tracing::debug!("✅ DER signature parsed successfully");
     
@@ -700,7 +1291,7 @@ async fn run_verification(message: &str, signature_hex: &str, child_index: u32)
     // Create the message digest (same as used in signing)
     tracing::debug!("🏷️ Computing message digest using Keccak256");
     use sha3::{Digest, Keccak256};
-    let digest = Keccak256::new_with_prefix(message.as_bytes());
+    let digest = Keccak256::new_with_prefix(message);
     tracing::debug!("✅ Message digest computed");
 
     // Verify the signature
@@ -727,3 +1318,334 @@ async fn run_verification(message: &str, signature_hex: &str, child_index: u32)
         }
     }
 }
+
+/// Low-S form of a DER signature over `digest`, with the recovery id that
+/// recovers `public_key` from it. Both parities are tried against the expected key.
+pub fn recoverable_signature(
+    public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
+    digest: &[u8; 32],
+    der_signature: &[u8],
+) -> anyhow::Result<(k256::ecdsa::Signature, k256::ecdsa::RecoveryId)> {
+    use k256::ecdsa::{RecoveryId, Signature as K256Signature, VerifyingKey};
+
+    let signature = K256Signature::from_der(der_signature)
+        .map_err(|_| anyhow::anyhow!("Failed to parse DER signature"))?;
+    // Flipping s to n - s keeps the signature valid but flips the parity of R
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let expected_key = VerifyingKey::from_sec1_bytes(&public_key.to_sec1_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid signing public key"))?;
+
+    for parity in [0u8, 1] {
+        let recovery_id = RecoveryId::from_byte(parity)
+            .ok_or_else(|| anyhow::anyhow!("Invalid recovery id {}", parity))?;
+        if let Ok(recovered_key) = VerifyingKey::recover_from_prehash(digest, &signature, recovery_id)
+            && recovered_key == expected_key
+        {
+            tracing::debug!(recovery_id = parity, "🔑 Recovery id found");
+            return Ok((signature, recovery_id));
+        }
+    }
+    anyhow::bail!("Signature does not recover to the signing public key")
+}
+
+/// Splits a 65-byte r||s||v signature, accepting v as 27/28 or 0/1.
+fn parse_rsv_signature(signature_bytes: &[u8]) -> anyhow::Result<(k256::ecdsa::Signature, k256::ecdsa::RecoveryId)> {
+    use k256::ecdsa::{RecoveryId, Signature as K256Signature};
+
+    if signature_bytes.len() != 65 {
+        anyhow::bail!("Expected a 65-byte r||s||v signature, got {} bytes", signature_bytes.len());
+    }
+    let recovery_id = match signature_bytes[64] {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        v => anyhow::bail!("Invalid recovery id v = {}", v),
+    };
+    let recovery_id = RecoveryId::from_byte(recovery_id)
+        .ok_or_else(|| anyhow::anyhow!("Invalid recovery id"))?;
+    let signature = K256Signature::from_slice(&signature_bytes[..64])
+        .map_err(|_| anyhow::anyhow!("Failed to parse r||s signature"))?;
+    Ok((signature, recovery_id))
+}
+
+/// Checks a signature over a 32-byte digest against `public_key`.
+pub fn verify_prehashed_signature(
+    public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
+    digest: &[u8; 32],
+    signature: &k256::ecdsa::Signature,
+) -> anyhow::Result<bool> {
+    use k256::ecdsa::{signature::hazmat::PrehashVerifier, VerifyingKey};
+
+    let verifying_key = VerifyingKey::from_sec1_bytes(&public_key.to_sec1_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid signing public key"))?;
+    match verifying_key.verify_prehash(digest, signature) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            tracing::debug!(error = %e, "❌ Cryptographic verification failed");
+            Ok(false)
+        }
+    }
+}
+
+/// Checks a signature over a 32-byte digest the way ecrecover does:
+/// the public key recovered with `recovery_id` must be `public_key`.
+pub fn verify_recoverable_signature(
+    public_key: &<tss_ecdsa::curve::TestCurve as CurveTrait>::VerifyingKey,
+    digest: &[u8; 32],
+    signature: &k256::ecdsa::Signature,
+    recovery_id: k256::ecdsa::RecoveryId,
+) -> anyhow::Result<bool> {
+    use k256::ecdsa::VerifyingKey;
+
+    let expected_key = VerifyingKey::from_sec1_bytes(&public_key.to_sec1_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid signing public key"))?;
+
+    match VerifyingKey::recover_from_prehash(digest, signature, recovery_id) {
+        Ok(recovered_key) => Ok(recovered_key == expected_key),
+        Err(e) => {
+            tracing::debug!(error = %e, "❌ Public key recovery failed");
+            Ok(false)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecoverRequest {
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub mode: SigningMode,
+    pub typed_data: Option<serde_json::Value>,
+    pub signature: String, // Hex r||s||v, v = 0/1 or 27/28
+}
+
+#[derive(Serialize)]
+pub struct RecoverResponse {
+    pub success: bool,
+    pub message: String,
+    pub public_key: Option<String>, // Compressed SEC1 hex of the recovered key
+    pub addresses: Option<crate::addresses::KeyAddresses>,
+    pub key_id: Option<String>, // Wallet and path of the matching key, if it is one of the caller's
+    pub path: Option<String>,
+    pub label: Option<String>,
+}
+
+/// Public key recovered from a compact signature over the payload of `mode`.
+fn recover_signing_key(request: &RecoverRequest) -> anyhow::Result<k256::PublicKey> {
+    use k256::ecdsa::VerifyingKey;
+    use sha3::{Digest, Keccak256};
+
+    let payload = signing_payload(request.mode, &request.message, request.typed_data.as_ref())?;
+    let signature_hex = request.signature.strip_prefix("0x").unwrap_or(&request.signature);
+    let signature_bytes = hex::decode(signature_hex)
+        .map_err(|_| anyhow::anyhow!("Invalid signature format. Expected hex string."))?;
+    let (signature, recovery_id) = parse_rsv_signature(&signature_bytes)?;
+    let recovered = VerifyingKey::recover_from_prehash(&Keccak256::digest(&payload), &signature, recovery_id)
+        .map_err(|_| anyhow::anyhow!("No public key can be recovered from this signature"))?;
+    Ok(recovered.into())
+}
+
+/// Recovers the signing public key from a message and an r||s||v signature, and looks
+/// it up among the caller's keys.
+pub async fn recover(
+    auth: crate::BasicAuth,
+    Json(request): Json<RecoverRequest>,
+) -> (StatusCode, ResponseJson<RecoverResponse>) {
+    let failure = |status: StatusCode, message: String| {
+        (status, ResponseJson(RecoverResponse {
+            success: false,
+            message,
+            public_key: None,
+            addresses: None,
+            key_id: None,
+            path: None,
+            label: None,
+        }))
+    };
+
+    let recovered = match recover_signing_key(&request) {
+        Ok(recovered) => recovered,
+        Err(e) => {
+            tracing::warn!(mode = request.mode.name(), error = %e, "⚠️ Public key recovery failed");
+            return failure(StatusCode::BAD_REQUEST, format!("Recovery failed: {}", e));
+        }
+    };
+
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+    let public_key = recovered.to_encoded_point(true);
+    let matching_key = crate::hd_keys::find_accessible_key(&auth, |key| {
+        let key_bytes = hex::decode(&key.public_key_hex).ok()?;
+        (k256::PublicKey::from_sec1_bytes(&key_bytes).ok()? == recovered).then_some(())
+    });
+    let matching_key = match matching_key {
+        Ok(matching_key) => matching_key,
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to search keys for recovered public key");
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, format!("Recovery failed: {}", e));
+        }
+    };
+
+    tracing::info!(
+        mode = request.mode.name(),
+        public_key = %hex::encode(public_key.as_bytes()),
+        key_id = ?matching_key.as_ref().map(|(key_id, _, _)| key_id),
+        "🔑 Recovered signing public key"
+    );
+    let message = match &matching_key {
+        Some((key_id, key, ())) => format!("Signed by key {} of wallet {}", key.path, key_id),
+        None => "Signing key is not one of your keys".to_string(),
+    };
+    let (key_id, key) = matching_key.map(|(key_id, key, ())| (key_id, key)).unzip();
+    (StatusCode::OK, ResponseJson(RecoverResponse {
+        success: true,
+        message,
+        public_key: Some(hex::encode(public_key.as_bytes())),
+        addresses: crate::addresses::KeyAddresses::from_public_key(public_key.as_bytes()).ok(),
+        key_id,
+        path: key.as_ref().map(|key| key.path.clone()),
+        label: key.and_then(|key| key.label),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use sha3::{Digest, Keccak256};
+
+    // r || s || v with v = 27 + y parity, as personal_sign and eth_signTypedData return it
+    fn sign_rsv(key: &SigningKey, payload: &[u8]) -> String {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&Keccak256::digest(payload)).unwrap();
+        let mut rsv = signature.to_bytes().to_vec();
+        rsv.push(27 + recovery_id.to_byte());
+        hex::encode(rsv)
+    }
+
+    #[test]
+    fn ethereum_signatures_recover_to_the_signing_key() {
+        let key = SigningKey::from_slice(&Keccak256::digest(b"waas recover test")).unwrap();
+        let typed_data = serde_json::json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }, { "name": "chainId", "type": "uint256" }],
+                "Transfer": [{ "name": "to", "type": "address" }, { "name": "amount", "type": "uint256" }],
+            },
+            "primaryType": "Transfer",
+            "domain": { "name": "waas", "chainId": 1 },
+            "message": { "to": "0x3535353535353535353535353535353535353535", "amount": "1000000000000000000" },
+        });
+        let cases = [
+            (SigningMode::Eip191, "Hello World", None),
+            (SigningMode::Eip712, "", Some(typed_data)),
+        ];
+
+        for (mode, message, typed_data) in cases {
+            let payload = signing_payload(mode, message, typed_data.as_ref()).unwrap();
+            let request = RecoverRequest {
+                message: message.to_string(),
+                mode,
+                typed_data: typed_data.clone(),
+                signature: format!("0x{}", sign_rsv(&key, &payload)),
+            };
+            let recovered = recover_signing_key(&request).unwrap();
+            assert_eq!(recovered, k256::PublicKey::from(key.verifying_key()), "{}", mode.name());
+
+            // Checked against another message, the signature recovers some other key
+            let other = RecoverRequest {
+                message: "Hello Bob".to_string(),
+                mode: SigningMode::Eip191,
+                typed_data: None,
+                signature: request.signature,
+            };
+            assert_ne!(recover_signing_key(&other).ok(), Some(recovered));
+        }
+    }
+
+    #[test]
+    fn prehash_shift_turns_the_protocol_signature_into_one_over_the_digest() {
+        use k256::elliptic_curve::{ops::Reduce, point::AffineCoordinates, PrimeField};
+        use k256::{ProjectivePoint, Scalar, U256};
+
+        let scalar = |seed: &[u8]| Scalar::from_repr(Keccak256::digest(seed)).unwrap();
+        let x = scalar(b"waas prehash key share sum");
+        let k = scalar(b"waas prehash nonce");
+        let r = <Scalar as Reduce<U256>>::reduce_bytes(&(ProjectivePoint::GENERATOR * k).to_affine().x());
+        let tweak: [u8; 32] = Keccak256::digest(b"waas prehash child tweak").into();
+
+        for key_tweak in [None, Some(&tweak)] {
+            let key = x + key_tweak.map_or(Scalar::ZERO, |tweak| Scalar::from_repr((*tweak).into()).unwrap());
+            let verifying_key = k256::ecdsa::VerifyingKey::from(&k256::ecdsa::SigningKey::from_bytes(&key.to_bytes()).unwrap());
+
+            for hash in [HashAlgorithm::Sha256, HashAlgorithm::Sha256d, HashAlgorithm::Keccak256] {
+                let digest = hash.digest(b"prehashed message");
+                let shift = prehash_shift(&digest, &digest, &r, key_tweak).unwrap();
+
+                // What the protocol computes for the digest passed as its message under shift t
+                let m = <Scalar as Reduce<U256>>::reduce_bytes(&Keccak256::digest(digest));
+                let s = k.invert().unwrap() * (m + r * (x + shift));
+                let signature = k256::ecdsa::Signature::from_scalars(r, s).unwrap();
+                let signature = signature.normalize_s().unwrap_or(signature);
+
+                use k256::ecdsa::signature::hazmat::PrehashVerifier;
+                assert!(verifying_key.verify_prehash(&digest, &signature).is_ok(), "{}", hash.name());
+                let message_digest = Keccak256::digest(digest);
+                assert!(verifying_key.verify_prehash(&message_digest, &signature).is_err(), "{}", hash.name());
+            }
+        }
+        assert!(prehash_shift(b"m", &[1; 32], &Scalar::ZERO, None).is_err());
+    }
+
+    // The nonce point is read from the serialized records, so it is checked against
+    // records from the real protocol and the r of the signature they produce
+    #[test]
+    #[ignore = "runs the full TSS protocol, which takes minutes"]
+    fn nonce_r_is_read_from_presign_records() {
+        crate::session_store::init_test_key_store();
+        crate::master_key::init_test_master_key();
+        let key_id = format!("nonce-r-{}", std::process::id());
+        let (configs, keygen_result) = crate::keygen::generate_threshold_key(&key_id, 3, 2).unwrap();
+        let root_key = keygen_result.keygen_outputs.values().next().unwrap().public_key().unwrap();
+
+        let signers = [configs[0].id(), configs[2].id()];
+        let entry = crate::presign_pool::generate_entry(&key_id, &keygen_result, &signers).unwrap();
+        let r = presign_nonce_r(&entry.records).unwrap();
+
+        let (signer_configs, signer_outputs) = crate::keygen::signing_quorum(&keygen_result, &signers).unwrap();
+        let input = SignHelperInput {
+            public_key_shares: signer_outputs.values().next().unwrap().public_key_shares().to_vec(),
+            presign_outputs: entry.records,
+            key_tweak: None,
+            threshold: keygen_result.threshold(),
+        };
+        let message = b"nonce point of the quorum";
+        let signature = sign_helper(signer_configs, input, SignTarget::Message(message), StdRng::seed_from_u64(5)).unwrap();
+        let signature = k256::ecdsa::Signature::from_der(&signature).unwrap();
+        assert_eq!(*signature.r(), r);
+        assert!(verify_prehashed_signature(&root_key, &SignTarget::Message(message).digest(), &signature).unwrap());
+    }
+
+    #[test]
+    fn only_keccak256_messages_skip_the_prehashed_path() {
+        let payload = b"hello";
+        let digest = HashAlgorithm::Sha256.digest(payload);
+        assert!(matches!(SignTarget::select(HashAlgorithm::Keccak256, false, payload, &digest), SignTarget::Message(_)));
+        assert!(matches!(SignTarget::select(HashAlgorithm::Keccak256, true, payload, &digest), SignTarget::Digest(_)));
+        assert!(matches!(SignTarget::select(HashAlgorithm::Sha256, false, payload, &digest), SignTarget::Digest(_)));
+        assert!(matches!(SignTarget::select(HashAlgorithm::Sha256d, false, payload, &digest), SignTarget::Digest(_)));
+        assert_eq!(SignTarget::Message(payload).digest(), HashAlgorithm::Keccak256.digest(payload));
+    }
+
+    #[test]
+    fn rsv_signatures_take_either_v_convention() {
+        let key = SigningKey::from_slice(&Keccak256::digest(b"waas recover test")).unwrap();
+        let payload = signing_payload(SigningMode::Eip191, "Hello World", None).unwrap();
+        let mut rsv = hex::decode(sign_rsv(&key, &payload)).unwrap();
+
+        let (_, with_27) = parse_rsv_signature(&rsv).unwrap();
+        rsv[64] -= 27;
+        let (_, with_0) = parse_rsv_signature(&rsv).unwrap();
+        assert_eq!(with_27, with_0);
+
+        rsv[64] = 29;
+        assert!(parse_rsv_signature(&rsv).is_err());
+        assert!(parse_rsv_signature(&rsv[..64]).is_err());
+    }
+}
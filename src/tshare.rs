@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use tss_ecdsa::{
+    auxinfo::AuxInfoParticipant,
+    curve::CurveTrait,
+    keygen::KeygenParticipant,
+    tshare::{CoeffPrivate, Input as TshareInput, TshareParticipant},
+    ParticipantConfig, ParticipantIdentifier, ProtocolParticipant, Participant, Identifier,
+};
+
+// TshareHelperOutput: Shamir t-of-n shares of the key produced by keygen
+#[derive(Debug)]
+pub struct TshareHelperOutput<C: CurveTrait> {
+    pub tshare_outputs: HashMap<ParticipantIdentifier, <TshareParticipant<C> as ProtocolParticipant>::Output>,
+}
+
+// Tshare helper: converts the additive n-of-n keygen shares into t-of-n shares
+pub fn tshare_helper<C: CurveTrait>(
+    configs: Vec<ParticipantConfig>,
+    mut auxinfo_outputs: HashMap<ParticipantIdentifier, <AuxInfoParticipant<C> as ProtocolParticipant>::Output>,
+    keygen_outputs: &HashMap<ParticipantIdentifier, <KeygenParticipant<C> as ProtocolParticipant>::Output>,
+    threshold: usize,
+    mut rng: StdRng,
+) -> anyhow::Result<TshareHelperOutput<C>> {
+    tracing::debug!(
+        quorum_size = configs.len(),
+        threshold = threshold,
+        "🔧 Setting up tshare participants"
+    );
+    
+    let tshare_sid = Identifier::random(&mut rng);
+
+    // Each participant re-shares its additive keygen share with a degree t-1 polynomial
+    let mut tshare_quorum = configs
+        .into_iter()
+        .map(|config| {
+            let auxinfo_output = auxinfo_outputs.remove(&config.id())
+                .ok_or_else(|| anyhow::anyhow!("Missing auxinfo output for participant {}", config.id()))?;
+            let keygen_output = keygen_outputs.get(&config.id())
+                .ok_or_else(|| anyhow::anyhow!("Missing keygen output for participant {}", config.id()))?;
+            let share = CoeffPrivate::from(keygen_output.private_key_share().clone());
+            let input = TshareInput::new(auxinfo_output, Some(share), threshold)?;
+            Ok(Participant::<TshareParticipant<C>>::from_config(config, tshare_sid, input)?)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // Run tshare until all parties have outputs
+    let tshare_outputs = crate::protocol::run_protocol("tshare", &mut tshare_quorum, &mut rng)?;
+
+    tracing::debug!(
+        outputs_collected = tshare_outputs.len(),
+        threshold = threshold,
+        "✅ Threshold shares generated"
+    );
+
+    Ok(TshareHelperOutput { tshare_outputs })
+}
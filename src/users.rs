@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::{
+    extract::{Json, Path},
+    http::StatusCode,
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::rbac::Role;
+use crate::session_store::{read_service_json, write_service_json, ServiceRecord};
+
+// Password for the admin account created on first start
+const ADMIN_PASSWORD_ENV: &str = "WAAS_ADMIN_PASSWORD";
+// Owner of wallets created before user accounts existed
+pub const BOOTSTRAP_ADMIN: &str = "admin";
+const MIN_PASSWORD_LEN: usize = 8;
+const MAX_USERNAME_LEN: usize = 64;
+
+// Serializes read-modify-write cycles of the users file
+static USERS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct User {
+    pub username: String,
+    pub password_hash: String, // bcrypt
+    #[serde(default)]
+    pub role: Role,
+    // Written by versions before roles existed; read once to pick the role
+    #[serde(default, skip_serializing)]
+    admin: bool,
+    pub disabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UserStore {
+    users: BTreeMap<String, User>,
+}
+
+// User as returned by the admin API, without the password hash
+#[derive(Serialize)]
+pub struct UserSummary {
+    pub username: String,
+    pub role: Role,
+    pub disabled: bool,
+    pub created_at: String,
+}
+
+impl From<&User> for UserSummary {
+    fn from(user: &User) -> Self {
+        Self {
+            username: user.username.clone(),
+            role: user.role,
+            disabled: user.disabled,
+            created_at: user.created_at.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub role: Role, // Defaults to operator
+}
+
+#[derive(Deserialize)]
+pub struct SetRoleRequest {
+    pub role: Role,
+}
+
+#[derive(Serialize)]
+pub struct UserResponse {
+    pub success: bool,
+    pub message: String,
+    pub user: Option<UserSummary>,
+}
+
+#[derive(Serialize)]
+pub struct ListUsersResponse {
+    pub success: bool,
+    pub message: String,
+    pub users: Vec<UserSummary>,
+}
+
+fn load_users() -> Result<UserStore> {
+    let mut store: UserStore = read_service_json(ServiceRecord::Users)?.unwrap_or_default();
+    assign_legacy_roles(&mut store);
+    Ok(store)
+}
+
+// Users from before roles existed: admins stay admins, everyone else may
+// keep signing with their wallets
+fn assign_legacy_roles(store: &mut UserStore) {
+    for user in store.users.values_mut().filter(|user| user.admin) {
+        user.role = Role::Admin;
+        user.admin = false;
+    }
+}
+
+fn save_users(store: &UserStore) -> Result<()> {
+    write_service_json(ServiceRecord::Users, store)
+}
+
+fn validate_username(username: &str) -> Result<()> {
+    let valid = !username.is_empty()
+        && username.len() <= MAX_USERNAME_LEN
+        && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+        anyhow::bail!(
+            "Invalid username '{}': use 1 to {} letters, digits, '-', '_' or '.'",
+            username,
+            MAX_USERNAME_LEN
+        );
+    }
+    Ok(())
+}
+
+fn new_user(username: &str, password: &str, role: Role) -> Result<User> {
+    validate_username(username)?;
+    if password.len() < MIN_PASSWORD_LEN {
+        anyhow::bail!("Password must be at least {} characters", MIN_PASSWORD_LEN);
+    }
+    Ok(User {
+        username: username.to_string(),
+        password_hash: bcrypt::hash(password, bcrypt::DEFAULT_COST)?,
+        role,
+        admin: false,
+        disabled: false,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+// Creates the admin account on first start so the service is reachable at all
+pub fn init_user_store() -> Result<()> {
+    let _guard = USERS_LOCK.lock().unwrap();
+    let mut store = load_users()?;
+    if !store.users.is_empty() {
+        tracing::info!(users = store.users.len(), "👤 User store loaded");
+        return Ok(());
+    }
+
+    let password = match std::env::var(ADMIN_PASSWORD_ENV) {
+        Ok(password) if !password.is_empty() => password,
+        _ => {
+            // Shown once on the console and never logged; only the bcrypt hash is stored
+            use rand::RngCore;
+            let mut bytes = [0u8; 16];
+            rand::rngs::OsRng.fill_bytes(&mut bytes);
+            let password = hex::encode(bytes);
+            tracing::warn!(
+                username = BOOTSTRAP_ADMIN,
+                "⚠️ {} not set, generated a random admin password. It is printed once below",
+                ADMIN_PASSWORD_ENV
+            );
+            println!("\n🔑 Generated password for the '{}' account: {}\n", BOOTSTRAP_ADMIN, password);
+            password
+        }
+    };
+    let admin = new_user(BOOTSTRAP_ADMIN, &password, Role::Admin)?;
+    store.users.insert(admin.username.clone(), admin);
+    save_users(&store)?;
+
+    tracing::info!(username = BOOTSTRAP_ADMIN, "👤 Created initial admin account");
+    Ok(())
+}
+
+pub fn find_user(username: &str) -> Result<Option<User>> {
+    Ok(load_users()?.users.remove(username))
+}
+
+// Checks a username/password pair. Disabled users and unknown users both fail.
+// bcrypt is deliberately slow, so call this off the async workers.
+pub fn authenticate(username: &str, password: &str) -> Result<Option<User>> {
+    let store = load_users()?;
+    let Some(user) = store.users.get(username) else {
+        return Ok(None);
+    };
+    if user.disabled || !bcrypt::verify(password, &user.password_hash)? {
+        return Ok(None);
+    }
+    Ok(Some(user.clone()))
+}
+
+fn create_user_impl(request: &CreateUserRequest) -> Result<User> {
+    let user = new_user(&request.username, &request.password, request.role)?;
+
+    let _guard = USERS_LOCK.lock().unwrap();
+    let mut store = load_users()?;
+    if store.users.contains_key(&user.username) {
+        anyhow::bail!("User {} already exists", user.username);
+    }
+    store.users.insert(user.username.clone(), user.clone());
+    save_users(&store)?;
+    Ok(user)
+}
+
+// Applies `change` to a user, refusing changes that would leave no active admin
+fn update_user_impl(username: &str, change: impl FnOnce(&mut User)) -> Result<User> {
+    let _guard = USERS_LOCK.lock().unwrap();
+    let mut store = load_users()?;
+    let user = apply_user_change(&mut store, username, change)?;
+    save_users(&store)?;
+    Ok(user)
+}
+
+fn apply_user_change(store: &mut UserStore, username: &str, change: impl FnOnce(&mut User)) -> Result<User> {
+    let user = store
+        .users
+        .get_mut(username)
+        .ok_or_else(|| anyhow::anyhow!("User {} not found", username))?;
+    let previous = user.clone();
+    change(user);
+    let user = user.clone();
+
+    // Keep at least one way into the admin API
+    if !store.users.values().any(|user| user.role == Role::Admin && !user.disabled) {
+        store.users.insert(previous.username.clone(), previous);
+        anyhow::bail!("Cannot remove the last active admin");
+    }
+    Ok(user)
+}
+
+// List users (GET, admin only)
+pub async fn list_users() -> (StatusCode, ResponseJson<ListUsersResponse>) {
+    match load_users() {
+        Ok(store) => (StatusCode::OK, ResponseJson(ListUsersResponse {
+            success: true,
+            message: format!("{} users", store.users.len()),
+            users: store.users.values().map(UserSummary::from).collect(),
+        })),
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to load users");
+            (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(ListUsersResponse {
+                success: false,
+                message: format!("Failed to load users: {}", e),
+                users: vec![],
+            }))
+        }
+    }
+}
+
+// Create a user (POST, admin only)
+pub async fn create_user(
+    auth: crate::BasicAuth,
+    Json(request): Json<CreateUserRequest>,
+) -> (StatusCode, ResponseJson<UserResponse>) {
+    let result = tokio::task::spawn_blocking(move || create_user_impl(&request))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    match result {
+        Ok(user) => {
+            tracing::info!(
+                created_by = %auth.username,
+                username = %user.username,
+                role = user.role.name(),
+                "👤 User created"
+            );
+            (StatusCode::OK, ResponseJson(UserResponse {
+                success: true,
+                message: format!("User {} created", user.username),
+                user: Some(UserSummary::from(&user)),
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "⚠️ Failed to create user");
+            (StatusCode::BAD_REQUEST, ResponseJson(UserResponse {
+                success: false,
+                message: format!("Failed to create user: {}", e),
+                user: None,
+            }))
+        }
+    }
+}
+
+async fn update_user(
+    auth: crate::BasicAuth,
+    username: String,
+    description: String,
+    change: impl FnOnce(&mut User),
+) -> (StatusCode, ResponseJson<UserResponse>) {
+    match update_user_impl(&username, change) {
+        Ok(user) => {
+            tracing::info!(
+                changed_by = %auth.username,
+                username = %user.username,
+                role = user.role.name(),
+                disabled = user.disabled,
+                change = %description,
+                "👤 User updated"
+            );
+            (StatusCode::OK, ResponseJson(UserResponse {
+                success: true,
+                message: format!("User {} {}", user.username, description),
+                user: Some(UserSummary::from(&user)),
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(username = %username, error = %e, "⚠️ Failed to update user");
+            (StatusCode::BAD_REQUEST, ResponseJson(UserResponse {
+                success: false,
+                message: format!("Failed to update user: {}", e),
+                user: None,
+            }))
+        }
+    }
+}
+
+// Disable a user (POST, admin only); their wallets stay in place
+pub async fn disable_user(auth: crate::BasicAuth, Path(username): Path<String>) -> (StatusCode, ResponseJson<UserResponse>) {
+    update_user(auth, username, "disabled".to_string(), |user| user.disabled = true).await
+}
+
+// Re-enable a disabled user (POST, admin only)
+pub async fn enable_user(auth: crate::BasicAuth, Path(username): Path<String>) -> (StatusCode, ResponseJson<UserResponse>) {
+    update_user(auth, username, "enabled".to_string(), |user| user.disabled = false).await
+}
+
+// Change a user's role (POST, admin only)
+pub async fn set_role(
+    auth: crate::BasicAuth,
+    Path(username): Path<String>,
+    Json(request): Json<SetRoleRequest>,
+) -> (StatusCode, ResponseJson<UserResponse>) {
+    let description = format!("is now {}", request.role.name());
+    update_user(auth, username, description, |user| user.role = request.role).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(username: &str, role: Role) -> User {
+        User {
+            username: username.to_string(),
+            password_hash: String::new(),
+            role,
+            admin: false,
+            disabled: false,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn store(users: Vec<User>) -> UserStore {
+        UserStore { users: users.into_iter().map(|user| (user.username.clone(), user)).collect() }
+    }
+
+    #[test]
+    fn the_last_active_admin_cannot_be_removed() {
+        let mut users = store(vec![user("root", Role::Admin), user("alice", Role::Operator)]);
+        for change in [
+            (|user: &mut User| user.role = Role::Approver) as fn(&mut User),
+            |user: &mut User| user.disabled = true,
+        ] {
+            let error = apply_user_change(&mut users, "root", change).err().expect("the change should be refused");
+            assert!(error.to_string().contains("last active admin"), "{}", error);
+        }
+
+        // With a second active admin either one may go
+        apply_user_change(&mut users, "alice", |user| user.role = Role::Admin).unwrap();
+        apply_user_change(&mut users, "root", |user| user.disabled = true).unwrap();
+        let error = apply_user_change(&mut users, "alice", |user| user.role = Role::Viewer).err().expect("the change should be refused");
+        assert!(error.to_string().contains("last active admin"), "{}", error);
+
+        // A refused change leaves the user as it was
+        assert_eq!(users.users["alice"].role, Role::Admin);
+        assert!(users.users["root"].disabled);
+        apply_user_change(&mut users, "root", |user| user.disabled = false).unwrap();
+        assert!(apply_user_change(&mut users, "bob", |user| user.disabled = true).is_err());
+    }
+
+    #[test]
+    fn legacy_admin_flags_become_roles() {
+        let legacy = serde_json::json!({
+            "users": {
+                "root": { "username": "root", "password_hash": "x", "admin": true, "disabled": false, "created_at": "2024-01-01T00:00:00Z" },
+                "alice": { "username": "alice", "password_hash": "x", "admin": false, "disabled": false, "created_at": "2024-01-01T00:00:00Z" },
+            }
+        });
+        let mut users: UserStore = serde_json::from_value(legacy).unwrap();
+        assign_legacy_roles(&mut users);
+        assert_eq!(users.users["root"].role, Role::Admin);
+        assert_eq!(users.users["alice"].role, Role::Operator);
+
+        // The flag is not written back, so the role is what counts from now on
+        let saved = serde_json::to_value(&users).unwrap();
+        assert!(saved["users"]["root"].get("admin").is_none());
+        assert_eq!(saved["users"]["root"]["role"], serde_json::to_value(Role::Admin).unwrap());
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use axum::response::Json as ResponseJson;
+use serde::{Deserialize, Serialize};
+
+use crate::session_store::{key_store, validate_key_id, Record, LEGACY_KEY_ID};
+
+const MAX_WALLET_NAME_LEN: usize = 128;
+
+// Public wallet metadata; the key shares themselves live in the encrypted keygen record
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WalletInfo {
+    pub key_id: String,
+    pub name: Option<String>,
+    pub public_key: String,
+    pub threshold: usize,
+    pub participants: usize,
+    pub created_at: String,
+    // Username of the account that generated the wallet; wallets from before
+    // user accounts existed belong to the bootstrap admin
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+impl WalletInfo {
+    pub fn owner(&self) -> &str {
+        self.owner.as_deref().unwrap_or(crate::users::BOOTSTRAP_ADMIN)
+    }
+}
+
+// Returned both for unknown wallets and for wallets owned by another user, so
+// a caller cannot probe for other tenants' key IDs
+#[derive(Debug)]
+pub struct WalletNotFound(pub String);
+
+impl std::fmt::Display for WalletNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Wallet {} not found", self.0)
+    }
+}
+
+impl std::error::Error for WalletNotFound {}
+
+fn can_access(auth: &crate::BasicAuth, info: &WalletInfo) -> bool {
+    auth.is_admin() || info.owner() == auth.username
+}
+
+pub fn is_wallet_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<WalletNotFound>().is_some()
+}
+
+#[derive(Serialize)]
+pub struct WalletsResponse {
+    pub success: bool,
+    pub message: String,
+    pub wallets: Vec<WalletInfo>,
+}
+
+// Query string for GET endpoints scoped to one wallet
+#[derive(Deserialize)]
+pub struct WalletQuery {
+    pub key_id: String,
+}
+
+pub fn new_key_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+pub fn validate_wallet_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.len() > MAX_WALLET_NAME_LEN {
+        anyhow::bail!("Wallet name must be 1 to {} characters", MAX_WALLET_NAME_LEN);
+    }
+    Ok(())
+}
+
+pub fn save_wallet(info: &WalletInfo) -> Result<()> {
+    key_store()?.put(&info.key_id, Record::Wallet, &serde_json::to_vec_pretty(info)?)
+}
+
+// Wallet metadata for `key_id`, or an error naming the unknown wallet
+pub fn load_wallet(key_id: &str) -> Result<WalletInfo> {
+    validate_key_id(key_id)?;
+    let data = key_store()?
+        .get(key_id, Record::Wallet)?
+        .ok_or_else(|| WalletNotFound(key_id.to_string()))?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+// Wallet metadata for `key_id` if the caller owns it or is an admin,
+// WalletNotFound otherwise
+pub fn authorize(auth: &crate::BasicAuth, key_id: &str) -> Result<WalletInfo> {
+    let info = load_wallet(key_id)?;
+    if !can_access(auth, &info) {
+        tracing::warn!(
+            key_id = %key_id,
+            username = %auth.username,
+            "🚫 Access to a wallet owned by another user"
+        );
+        return Err(WalletNotFound(key_id.to_string()).into());
+    }
+    Ok(info)
+}
+
+// All wallets, oldest first
+pub fn list_wallets() -> Result<Vec<WalletInfo>> {
+    let store = key_store()?;
+    let mut wallets = Vec::new();
+    for key_id in store.key_ids()? {
+        let Some(data) = store.get(&key_id, Record::Wallet)? else {
+            continue;
+        };
+        match serde_json::from_slice::<WalletInfo>(&data) {
+            Ok(info) => wallets.push(info),
+            Err(e) => tracing::warn!(key_id = %key_id, error = %e, "⚠️ Skipping unreadable wallet record"),
+        }
+    }
+    wallets.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(wallets)
+}
+
+// Wallets the caller may use, oldest first
+pub fn accessible_wallets(auth: &crate::BasicAuth) -> Result<Vec<WalletInfo>> {
+    Ok(list_wallets()?
+        .into_iter()
+        .filter(|wallet| can_access(auth, wallet))
+        .collect())
+}
+
+// Deletes every record of a wallet, the wallet record last so a partially deleted
+// wallet stays listed. Returns the names of the records that existed.
+pub fn delete_wallet_records(key_id: &str) -> Result<Vec<&'static str>> {
+    validate_key_id(key_id)?;
+    let store = key_store()?;
+    let mut deleted = Vec::new();
+    for record in Record::ALL.into_iter().filter(|record| *record != Record::Wallet).chain([Record::Wallet]) {
+        if store.delete(key_id, record)? {
+            deleted.push(record.name());
+        }
+    }
+    Ok(deleted)
+}
+
+// Gives the key stored before wallets existed a wallet record, so it shows up
+// under /wallets with the legacy key ID. Needs the master key to read the shares.
+pub fn register_legacy_wallet() -> Result<()> {
+    let store = key_store()?;
+    if store.contains(LEGACY_KEY_ID, Record::Wallet)? || !crate::sign::is_keygen_completed(LEGACY_KEY_ID) {
+        return Ok(());
+    }
+
+    let (configs, keygen_result) = crate::sign::load_keygen_outputs(LEGACY_KEY_ID)?;
+    let info = WalletInfo {
+        key_id: LEGACY_KEY_ID.to_string(),
+        name: Some("Default wallet".to_string()),
+        public_key: crate::keygen::root_public_key_hex(&keygen_result)?,
+        threshold: keygen_result.threshold(),
+        participants: configs.len(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        owner: Some(crate::users::BOOTSTRAP_ADMIN.to_string()),
+    };
+    save_wallet(&info)?;
+
+    tracing::info!(
+        key_id = LEGACY_KEY_ID,
+        "🔄 Registered existing key as wallet"
+    );
+    Ok(())
+}
+
+// Wallet listing endpoint (GET); admins see every wallet, other users their own
+pub async fn wallets(auth: crate::BasicAuth) -> ResponseJson<WalletsResponse> {
+    match accessible_wallets(&auth) {
+        Ok(wallets) => {
+            tracing::debug!(username = %auth.username, wallets = wallets.len(), "📋 Listed wallets");
+            ResponseJson(WalletsResponse {
+                success: true,
+                message: format!("{} wallets", wallets.len()),
+                wallets,
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "❌ Failed to list wallets");
+            ResponseJson(WalletsResponse {
+                success: false,
+                message: format!("Failed to list wallets: {}", e),
+                wallets: vec![],
+            })
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Starts three waas-node participants and the coordinator on localhost, then runs
+// distributed keygen and signing through the coordinator's /nodes endpoints.
+//
+// Needs the openssl CLI for the test certificate, and port 8443 free for the coordinator:
+// `cargo test --test distributed_nodes -- --ignored`
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+const NODE_TOKEN: &str = "distributed-nodes-test-token";
+const ADMIN_PASSWORD: &str = "distributed-nodes-test-admin";
+const COORDINATOR_URL: &str = "https://127.0.0.1:8443";
+const NODE_COUNT: usize = 3;
+
+// Kills the process when the test ends, pass or fail
+struct Process(Child);
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("waas-distributed-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Self-signed end-entity certificate for 127.0.0.1, trusted directly by the coordinator and the test client
+fn create_certificate(dir: &Path) {
+    let status = Command::new("openssl")
+        .args(["req", "-x509", "-newkey", "ec", "-pkeyopt", "ec_paramgen_curve:prime256v1", "-nodes"])
+        .args(["-days", "1", "-subj", "/CN=localhost"])
+        .args(["-addext", "subjectAltName=IP:127.0.0.1,DNS:localhost"])
+        .args(["-addext", "basicConstraints=critical,CA:FALSE"])
+        .args(["-keyout", "key.pem", "-out", "cert.pem"])
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("openssl is needed to create the test certificate");
+    assert!(status.success(), "openssl failed to create the test certificate");
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn spawn(binary: &str, dir: &Path, args: &[&str], envs: &[(&str, String)]) -> Process {
+    let mut command = Command::new(binary);
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("WAAS_NODE_TOKEN", NODE_TOKEN)
+        .env("WAAS_MASTER_PASSPHRASE", "distributed-nodes-test-passphrase")
+        .env("RUST_LOG", "warn")
+        .stdout(Stdio::null());
+    for (key, value) in envs {
+        command.env(key, value);
+    }
+    Process(command.spawn().unwrap())
+}
+
+fn client(dir: &Path) -> reqwest::Client {
+    let cert = reqwest::Certificate::from_pem(&std::fs::read(dir.join("cert.pem")).unwrap()).unwrap();
+    reqwest::Client::builder()
+        .use_rustls_tls()
+        .tls_built_in_root_certs(false)
+        .add_root_certificate(cert)
+        .timeout(Duration::from_secs(600))
+        .build()
+        .unwrap()
+}
+
+async fn call(client: &reqwest::Client, method: reqwest::Method, route: &str, body: serde_json::Value) -> reqwest::Result<serde_json::Value> {
+    client
+        .request(method, format!("{}{}", COORDINATOR_URL, route))
+        .basic_auth("admin", Some(ADMIN_PASSWORD))
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+// Waits until the coordinator is up and reaches every node
+async fn wait_for_nodes(client: &reqwest::Client) {
+    let deadline = Instant::now() + Duration::from_secs(120);
+    loop {
+        if let Ok(status) = call(client, reqwest::Method::GET, "/nodes/status", serde_json::json!({})).await
+            && status["nodes"].as_array().is_some_and(|nodes| {
+                nodes.len() == NODE_COUNT && nodes.iter().all(|node| node["reachable"] == true)
+            })
+        {
+            return;
+        }
+        assert!(Instant::now() < deadline, "coordinator or participant nodes did not come up");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+#[tokio::test]
+#[ignore = "runs the full TSS protocol across processes, which takes minutes"]
+async fn coordinator_runs_keygen_and_signing_across_nodes() {
+    use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+    use sha3::{Digest, Keccak256};
+
+    let dir = temp_dir();
+    create_certificate(&dir);
+
+    let mut processes = Vec::new();
+    let mut node_urls = Vec::new();
+    for index in 1..=NODE_COUNT {
+        let listen = format!("127.0.0.1:{}", free_port());
+        let data_dir = format!("node{}", index);
+        processes.push(spawn(
+            env!("CARGO_BIN_EXE_waas-node"),
+            &dir,
+            &["--listen", &listen, "--data-dir", &data_dir, "--cert", "cert.pem", "--key", "key.pem"],
+            &[],
+        ));
+        node_urls.push(format!("https://{}", listen));
+    }
+    processes.push(spawn(
+        env!("CARGO_BIN_EXE_waas"),
+        &dir,
+        &[],
+        &[
+            ("WAAS_NODES", node_urls.join(",")),
+            ("WAAS_NODE_CA_CERT", dir.join("cert.pem").display().to_string()),
+            ("WAAS_STORAGE_DIR", dir.join("coordinator").display().to_string()),
+            ("WAAS_ADMIN_PASSWORD", ADMIN_PASSWORD.to_string()),
+            ("WAAS_PRESIGN_POOL_TARGET", "0".to_string()),
+        ],
+    ));
+
+    let client = client(&dir);
+    wait_for_nodes(&client).await;
+
+    let keygen = call(&client, reqwest::Method::POST, "/nodes/keygen", serde_json::json!({})).await.unwrap();
+    assert_eq!(keygen["success"], true, "{}", keygen);
+    assert_eq!(keygen["participants"].as_array().unwrap().len(), NODE_COUNT);
+
+    // Every node now holds its own share and auxinfo
+    let status = call(&client, reqwest::Method::GET, "/nodes/status", serde_json::json!({})).await.unwrap();
+    for node in status["nodes"].as_array().unwrap() {
+        assert_eq!(node["status"]["has_key_share"], true, "{}", node);
+        assert_eq!(node["status"]["has_auxinfo"], true, "{}", node);
+    }
+
+    let message = "signed by three processes";
+    let signed = call(&client, reqwest::Method::POST, "/nodes/sign", serde_json::json!({ "message": message })).await.unwrap();
+    assert_eq!(signed["success"], true, "{}", signed);
+
+    let public_key = VerifyingKey::from_sec1_bytes(&hex::decode(keygen["public_key"].as_str().unwrap()).unwrap()).unwrap();
+    let signature = Signature::from_der(&hex::decode(signed["signature"].as_str().unwrap()).unwrap()).unwrap();
+    assert!(public_key.verify_prehash(&Keccak256::digest(message.as_bytes()), &signature).is_ok());
+    assert!(public_key.verify_prehash(&Keccak256::digest(b"another message"), &signature).is_err());
+
+    drop(processes);
+    let _ = std::fs::remove_dir_all(&dir);
+}